@@ -16,8 +16,11 @@ int main(void) {
     printf("#[allow(dead_code)];\n");
 ' > $GEN_C_SOURCE
 
+# The VIDIOC_* numbers depend on the target's struct sizes, so they are not
+# printed by the host's compiler; see the ioctls.rs step below.
 IFS=$'\n'
 for defineline in $(grep '^#define[ 	]\+[_a-zA-Z0-9]\+[ 	]\+' /usr/include/linux/videodev2.h |
+                    grep -v '^#define[ 	]\+VIDIOC_' |
                     sed 's/[ 	]\+/ /g' | sed 's/\"/\\\"/g' | sed 's/\\$/ \.\.\./g'); do
     echo "    printf(\"//${defineline}\n\");" >> $GEN_C_SOURCE
     const=`echo "$defineline" | cut -d ' ' -f 2`
@@ -31,6 +34,18 @@ echo "
 
 gcc -o gen_constants gen_constants.c && ./gen_constants > constants.rs
 
+# Each VIDIOC_* request is rebuilt with the io!/ior!/iow!/iowr! macros from
+# the sizes in v4l2.rs's `size` module. That table is hand-maintained per
+# ABI: when videodev2.h gains an ioctl, add its argument type there and a
+# check_ioctl! line to test_layout.rs's ioctl_sizes, which checks every
+# entry against size_of() of the structs in v4l2.rs.
+grep '^#define[ 	]\+VIDIOC_' /usr/include/linux/videodev2.h |
+    sed 's/[ 	]\+/ /g' |
+    sed -E \
+        -e "s/^#define (VIDIOC_[A-Z0-9_]+) _IO\('V', ?([0-9]+)\)(.*)$/\/\/#define \1 _IO('V', \2)\3\npub static \1: u32 = io!('V', \2);/" \
+        -e "s/^#define (VIDIOC_[A-Z0-9_]+) _IO(R|W|WR)\('V', ?([0-9]+), ?(struct )?([a-zA-Z0-9_]+)\)(.*)$/\/\/#define \1 _IO\2('V', \3, \4\5)\6\npub static \1: u32 = io\L\2\E!('V', \3, size::\5);/" \
+    > ioctls.rs
+
 echo '#[feature(globs)];' > videodev2.rs_
 echo '#[allow(non_camel_case_types)];' >> videodev2.rs_
 bindgen /usr/include/linux/videodev2.h -match videodev2.h >> videodev2.rs_
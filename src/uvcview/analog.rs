@@ -0,0 +1,100 @@
+// Video inputs, analogue TV standards and tuner frequencies.
+//
+// USB grabbers and TV cards multiplex several inputs (composite, S-Video,
+// tuner) onto one capture device. Each input may carry one of the
+// analogue standards, and tuner inputs are tuned in units of 62.5 kHz, or
+// 62.5 Hz when the tuner reports V4L2_TUNER_CAP_LOW.
+
+use v4l2;
+use v4l2::v4l2_std_id;
+
+// An entry of VIDIOC_ENUMINPUT.
+#[deriving(Clone, Show)]
+pub struct Input {
+    pub index: u32,
+    pub name: ~str,
+    // Index of the tuner feeding this input, for tuner inputs.
+    pub tuner: Option<u32>,
+    // Standards the input supports.
+    pub std: v4l2_std_id,
+    // V4L2_IN_ST_* flags at the time of enumeration.
+    pub status: u32,
+    // V4L2_IN_CAP_* flags.
+    pub capabilities: u32,
+}
+
+impl Input {
+    // True for HDMI, DVI and SDI inputs, which take DV timings instead of a
+    // standard.
+    pub fn has_dv_timings(&self) -> bool {
+        (self.capabilities & v4l2::V4L2_IN_CAP_DV_TIMINGS) != 0
+    }
+
+    pub fn has_signal(&self) -> bool {
+        (self.status & (v4l2::V4L2_IN_ST_NO_POWER | v4l2::V4L2_IN_ST_NO_SIGNAL)) == 0
+    }
+}
+
+// An entry of VIDIOC_ENUMSTD.
+#[deriving(Clone, Show)]
+pub struct Standard {
+    pub id: v4l2_std_id,
+    pub name: ~str,
+    // Time per frame in seconds as (numerator, denominator).
+    pub frame_period: (u32, u32),
+    pub frame_lines: u32,
+}
+
+// Standard names accepted on the command line. "auto" is handled by the
+// caller, which asks the driver with VIDIOC_QUERYSTD.
+pub fn std_from_str(s: &str) -> Option<v4l2_std_id> {
+    match s.to_ascii_lower().as_slice() {
+        "pal" => Some(v4l2::V4L2_STD_PAL),
+        "pal-m" => Some(v4l2::V4L2_STD_PAL_M),
+        "pal-n" => Some(v4l2::V4L2_STD_PAL_N),
+        "pal-nc" => Some(v4l2::V4L2_STD_PAL_Nc),
+        "pal-60" => Some(v4l2::V4L2_STD_PAL_60),
+        "ntsc" => Some(v4l2::V4L2_STD_NTSC),
+        "ntsc-jp" => Some(v4l2::V4L2_STD_NTSC_M_JP),
+        "ntsc-443" => Some(v4l2::V4L2_STD_NTSC_443),
+        "secam" => Some(v4l2::V4L2_STD_SECAM),
+        "secam-l" => Some(v4l2::V4L2_STD_SECAM_L),
+        _ => None
+    }
+}
+
+// A short name for a standard mask, naming the family when the mask stays
+// within one.
+pub fn std_to_str(id: v4l2_std_id) -> ~str {
+    if id == v4l2::V4L2_STD_UNKNOWN {
+        "UNKNOWN".to_owned()
+    } else if id == v4l2::V4L2_STD_PAL_M {
+        "PAL-M".to_owned()
+    } else if id == v4l2::V4L2_STD_PAL_60 {
+        "PAL-60".to_owned()
+    } else if (id & !v4l2::V4L2_STD_PAL) == 0 {
+        "PAL".to_owned()
+    } else if (id & !v4l2::V4L2_STD_NTSC) == 0 {
+        "NTSC".to_owned()
+    } else if (id & !v4l2::V4L2_STD_SECAM) == 0 {
+        "SECAM".to_owned()
+    } else if (id & !v4l2::V4L2_STD_525_60) == 0 {
+        "525/60".to_owned()
+    } else if (id & !v4l2::V4L2_STD_625_50) == 0 {
+        "625/50".to_owned()
+    } else {
+        format!("0x{:x}", id)
+    }
+}
+
+// Frequency in MHz to tuner units.
+pub fn to_tuner_units(mhz: f64, tuner_capability: u32) -> u32 {
+    let units_per_mhz = if (tuner_capability & v4l2::V4L2_TUNER_CAP_LOW) != 0 { 16000.0 } else { 16.0 };
+    (mhz * units_per_mhz).round() as u32
+}
+
+// Tuner units to frequency in MHz.
+pub fn from_tuner_units(units: u32, tuner_capability: u32) -> f64 {
+    let units_per_mhz = if (tuner_capability & v4l2::V4L2_TUNER_CAP_LOW) != 0 { 16000.0 } else { 16.0 };
+    units as f64 / units_per_mhz
+}
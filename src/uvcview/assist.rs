@@ -0,0 +1,108 @@
+// Exposure and focus assists drawn over the scaled image: zebra stripes on
+// clipped highlights, a false colour exposure map and focus peaking.
+//
+// Like the scopes, these look at the frame's own luma rather than the
+// converted RGB, at the frame pixel each window pixel shows.
+
+use overlay::{Canvas,BLACK};
+use pixfmt::Frame;
+use scale;
+use scale::{Rect,Region};
+
+// Exposure bands for false colour as (upper luma bound, colour). Luma at or
+// above the last bound is clipped; luma outside any coloured band is shown
+// in grey. Bounds follow the usual IRE bands on studio range luma.
+static FALSE_COLORS: [(u8, Option<(u8, u8, u8)>), ..11] = [
+    (20, Some((128, 0, 160))),      // crushed blacks
+    (38, Some((0, 64, 255))),       // under exposed
+    (60, Some((0, 160, 160))),      // shadow detail
+    (108, None),
+    (121, Some((0, 200, 0))),       // 18% grey
+    (130, None),
+    (139, Some((255, 128, 160))),   // skin tones
+    (185, None),
+    (201, Some((200, 200, 200))),   // highlights
+    (220, Some((255, 255, 0))),     // nearly clipped
+    (235, Some((255, 128, 0))),
+];
+static CLIPPED: (u8, u8, u8) = (255, 0, 0);
+
+static PEAKING_COLOR: (u8, u8, u8) = (255, 0, 255);
+
+pub struct Assist {
+    pub zebra: bool,
+    // Luma at or above which zebra stripes are drawn.
+    pub zebra_level: u8,
+    pub false_color: bool,
+    pub peaking: bool,
+    // Minimum luma gradient that counts as an in-focus edge.
+    pub peaking_level: u32,
+}
+
+impl Assist {
+    pub fn new() -> Assist {
+        Assist {
+            zebra: false,
+            zebra_level: 235,
+            false_color: false,
+            peaking: false,
+            peaking_level: 64,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.zebra || self.false_color || self.peaking
+    }
+
+    // Paints the enabled assists over `dest`, which holds the `src` region
+    // of `frame` as drawn by scale::convert().
+    pub fn apply(&self, frame: &Frame, canvas: &mut Canvas, dest: Rect, src: Region) {
+        if !self.enabled() || frame.width == 0 || frame.height == 0 {
+            return;
+        }
+        for y in range(dest.y, dest.y + dest.height) {
+            for x in range(dest.x, dest.x + dest.width) {
+                let (fx, fy) = scale::frame_pixel(frame, dest, src, x, y);
+                let luma = luma_at(frame, fx, fy);
+                if self.false_color {
+                    canvas.put(x, y, false_color(luma));
+                }
+                if self.peaking && gradient(frame, fx, fy) >= self.peaking_level {
+                    canvas.put(x, y, PEAKING_COLOR);
+                }
+                if self.zebra && luma >= self.zebra_level && is_stripe(x, y) {
+                    canvas.put(x, y, BLACK);
+                }
+            }
+        }
+    }
+}
+
+pub fn false_color(luma: u8) -> (u8, u8, u8) {
+    for &(bound, color) in FALSE_COLORS.iter() {
+        if luma < bound {
+            return color.unwrap_or((luma, luma, luma));
+        }
+    }
+    CLIPPED
+}
+
+// Diagonal stripes 4 window pixels wide, fixed to the window so they stay
+// put while the image moves under them.
+fn is_stripe(x: uint, y: uint) -> bool {
+    (x + y) / 4 % 2 == 0
+}
+
+fn luma_at(frame: &Frame, x: uint, y: uint) -> u8 {
+    let (luma, _, _) = frame.ycbcr_at(x, y);
+    luma
+}
+
+// Sum of the absolute central differences of luma across and down.
+pub fn gradient(frame: &Frame, x: uint, y: uint) -> u32 {
+    let left = luma_at(frame, if x > 0 { x - 1 } else { x }, y) as int;
+    let right = luma_at(frame, if x + 1 < frame.width { x + 1 } else { x }, y) as int;
+    let up = luma_at(frame, x, if y > 0 { y - 1 } else { y }) as int;
+    let down = luma_at(frame, x, if y + 1 < frame.height { y + 1 } else { y }) as int;
+    ((right - left).abs() + (down - up).abs()) as u32
+}
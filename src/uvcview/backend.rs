@@ -0,0 +1,48 @@
+// Everything UvcView asks of the kernel goes through an IoctlBackend, so a
+// scripted device can stand in for /dev/videoN.
+
+use libc::consts::os::posix88::{EINTR,MAP_SHARED};
+use libc::{c_int,c_void};
+use libc;
+use std::os;
+use std::os::{MemoryMap,MapError,MapReadable,MapWritable,MapFd,MapOffset,MapNonStandardFlags};
+use v4l2;
+
+pub trait IoctlBackend {
+    fn ioctl(&mut self, fd: c_int, req: u32, arg: *mut c_void) -> Result<c_int, c_int>;
+    fn mmap(&mut self, fd: c_int, length: uint, offset: uint) -> Result<MemoryMap, MapError>;
+    fn read(&mut self, fd: c_int, buf: *mut u8, length: uint) -> Result<uint, c_int>;
+    fn close(&mut self, fd: c_int);
+}
+
+pub struct KernelBackend;
+
+impl IoctlBackend for KernelBackend {
+    fn ioctl(&mut self, fd: c_int, req: u32, arg: *mut c_void) -> Result<c_int, c_int> {
+        v4l2::v4l2_ioctl(fd, req, arg)
+    }
+
+    fn mmap(&mut self, fd: c_int, length: uint, offset: uint) -> Result<MemoryMap, MapError> {
+        MemoryMap::new(length, &[MapReadable, MapWritable, MapFd(fd), MapOffset(offset),
+                                 MapNonStandardFlags(MAP_SHARED)])
+    }
+
+    fn read(&mut self, fd: c_int, buf: *mut u8, length: uint) -> Result<uint, c_int> {
+        loop {
+            let result = unsafe { libc::read(fd, buf as *mut c_void, length as libc::size_t) };
+            if result >= 0 {
+                return Ok(result as uint);
+            }
+            let errno = os::errno() as c_int;
+            if errno != EINTR {
+                return Err(errno);
+            }
+        }
+    }
+
+    fn close(&mut self, fd: c_int) {
+        unsafe {
+            libc::close(fd);
+        }
+    }
+}
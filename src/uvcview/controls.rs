@@ -0,0 +1,43 @@
+// Device controls such as brightness and exposure, as last reported by the
+// driver. Control events keep them current when another application
+// changes them.
+
+use events::ControlChange;
+use v4l2;
+
+#[deriving(Clone, Show)]
+pub struct Control {
+    pub id: u32,
+    pub name: ~str,
+    // V4L2_CTRL_TYPE_*.
+    pub kind: u32,
+    pub minimum: i32,
+    pub maximum: i32,
+    pub step: i32,
+    pub default_value: i32,
+    // V4L2_CTRL_FLAG_* flags.
+    pub flags: u32,
+    pub value: i64,
+}
+
+impl Control {
+    pub fn is_read_only(&self) -> bool {
+        (self.flags & v4l2::V4L2_CTRL_FLAG_READ_ONLY) != 0
+    }
+
+    // Applies what a V4L2_EVENT_CTRL reported.
+    pub fn update(&mut self, change: &ControlChange) {
+        if (change.changes & v4l2::V4L2_EVENT_CTRL_CH_VALUE) != 0 {
+            self.value = change.value;
+        }
+        if (change.changes & v4l2::V4L2_EVENT_CTRL_CH_FLAGS) != 0 {
+            self.flags = change.flags;
+        }
+        if (change.changes & v4l2::V4L2_EVENT_CTRL_CH_RANGE) != 0 {
+            self.minimum = change.minimum;
+            self.maximum = change.maximum;
+            self.step = change.step;
+            self.default_value = change.default_value;
+        }
+    }
+}
@@ -0,0 +1,239 @@
+// Field order handling and deinterlacing.
+//
+// Analogue capture devices deliver interlaced video in one of several
+// layouts: both fields interleaved line by line in one buffer (INTERLACED,
+// INTERLACED_TB/BT), one field after the other (SEQ_TB/BT), or one field per
+// buffer (ALTERNATE, with v4l2_buffer.field telling TOP from BOTTOM). The
+// Deinterlacer weaves whatever arrives into a full frame and then, unless
+// asked to weave, fills the lines of the older field from the newer one.
+//
+// Lines are handled as bytes, which keeps this independent of the pixel
+// format: packed formats have one line per row, planar formats have their
+// own lines in each plane.
+
+use std::cmp;
+
+use device;
+use device::Field;
+use pixfmt::{Frame,Plane};
+
+#[deriving(Eq, Show)]
+pub enum Method {
+    // Interleaves both fields as they are; sharp on still pictures, combed
+    // where there is motion.
+    Weave,
+    // Interpolates the older field's lines from the newer field.
+    Bob,
+    // Weaves where the picture is still and bobs where it moved since the
+    // previous frame.
+    Adaptive,
+}
+
+pub fn method_from_str(s: &str) -> Option<Method> {
+    match s {
+        "weave" => Some(Weave),
+        "bob" => Some(Bob),
+        "adaptive" => Some(Adaptive),
+        _ => None
+    }
+}
+
+#[deriving(Eq, Show, Clone)]
+enum Parity {
+    Top,
+    Bottom,
+}
+
+fn parity_of(line: uint) -> Parity {
+    if line % 2 == 0 { Top } else { Bottom }
+}
+
+// How the fields of one buffer are laid out.
+enum Layout {
+    // Both fields, line by line.
+    Interleaved,
+    // Both fields, the given one first.
+    Sequential(Parity),
+    // A single field.
+    Single(Parity),
+}
+
+pub struct Deinterlacer {
+    pub method: Method,
+    // Change in a byte between frames above which Adaptive treats the
+    // picture as moving.
+    pub threshold: u8,
+    // Both fields woven together, the same before the latest buffer arrived,
+    // and the deinterlaced result, one Vec per plane.
+    woven: Vec<Vec<u8>>,
+    previous: Vec<Vec<u8>>,
+    output: Vec<Vec<u8>>,
+    // Shape of the latest result and whether it is `output` rather than
+    // `woven`, for frame().
+    fourcc: u32,
+    width: uint,
+    height: uint,
+    bytesperline: Vec<uint>,
+    filtered: bool,
+}
+
+impl Deinterlacer {
+    pub fn new() -> Deinterlacer {
+        Deinterlacer {
+            method: Adaptive,
+            threshold: 16,
+            woven: vec!(),
+            previous: vec!(),
+            output: vec!(),
+            fourcc: 0,
+            width: 0,
+            height: 0,
+            bytesperline: vec!(),
+            filtered: false,
+        }
+    }
+
+    // Forgets earlier fields, e.g. after the format changed.
+    pub fn reset(&mut self) {
+        self.woven.clear();
+        self.previous.clear();
+        self.output.clear();
+        self.bytesperline.clear();
+    }
+
+    // Deinterlaces `frame`, captured with buffer field order `field`, into
+    // the Deinterlacer; frame() returns the result. For Top and Bottom
+    // `frame` is a single field of an alternating stream and is paired with
+    // the latest field of the other parity.
+    pub fn process(&mut self, frame: &Frame, field: Field) {
+        let (layout, newest) = match field {
+            device::Top => (Single(Top), Top),
+            device::Bottom => (Single(Bottom), Bottom),
+            device::InterlacedBt => (Interleaved, Top),
+            device::SeqTb => (Sequential(Top), Bottom),
+            device::SeqBt => (Sequential(Bottom), Top),
+            // INTERLACED leaves the temporal order to the standard; top
+            // first is the common case.
+            _ => (Interleaved, Bottom)
+        };
+        let height = match layout {
+            Single(_) => frame.height * 2,
+            _ => frame.height
+        };
+
+        let fresh = self.woven.len() != frame.planes.len() ||
+            range(0, frame.planes.len()).any(|i| {
+                let plane = frame.planes.get(i);
+                self.woven.get(i).len() != lines(frame.planes.len(), i, height) * plane.bytesperline
+            });
+        if fresh {
+            self.woven = range(0, frame.planes.len()).map(|i| {
+                let bytes = lines(frame.planes.len(), i, height) * frame.planes.get(i).bytesperline;
+                Vec::from_elem(bytes, 0u8)
+            }).collect();
+        }
+        if self.method == Adaptive {
+            self.previous = self.woven.clone();
+        }
+
+        for (i, plane) in frame.planes.iter().enumerate() {
+            let count = lines(frame.planes.len(), i, height);
+            let bpl = plane.bytesperline;
+            let woven = self.woven.get_mut(i);
+            for y in range(0, count) {
+                let source = match layout {
+                    Interleaved => Some(y),
+                    Sequential(first) => {
+                        let first_lines = if first == Top { (count + 1) / 2 } else { count / 2 };
+                        if parity_of(y) == first { Some(y / 2) } else { Some(first_lines + y / 2) }
+                    }
+                    // Until both parities have arrived, line double.
+                    Single(parity) if parity == parity_of(y) || fresh => Some(y / 2),
+                    Single(_) => None
+                };
+                match source {
+                    Some(source) => {
+                        let src = line(plane, source);
+                        let dst = woven.mut_slice(y * bpl, (y + 1) * bpl);
+                        for (d, &s) in dst.mut_iter().zip(src.iter()) {
+                            *d = s;
+                        }
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        self.fourcc = frame.fourcc;
+        self.width = frame.width;
+        self.height = height;
+        self.bytesperline = frame.planes.iter().map(|plane| plane.bytesperline).collect();
+        self.filtered = self.method != Weave;
+        if !self.filtered {
+            return;
+        }
+
+        if self.output.len() != self.woven.len() {
+            self.output = self.woven.clone();
+        }
+        let (method, threshold) = (self.method, self.threshold);
+        for i in range(0, self.woven.len()) {
+            let count = lines(frame.planes.len(), i, height);
+            let bpl = frame.planes.get(i).bytesperline;
+            let woven = self.woven.get(i).as_slice();
+            let previous = if method == Adaptive { self.previous.get(i).as_slice() } else { woven };
+            let output = self.output.get_mut(i);
+            if output.len() != woven.len() {
+                *output = Vec::from_elem(woven.len(), 0u8);
+            }
+            for y in range(0, count) {
+                let row = output.mut_slice(y * bpl, (y + 1) * bpl);
+                if parity_of(y) == newest {
+                    row.copy_from(woven.slice(y * bpl, (y + 1) * bpl));
+                    continue;
+                }
+                // Neighbouring lines of the newest field.
+                let above = (if y > 0 { y - 1 } else { y + 1 }) * bpl;
+                let below = (if y + 1 < count { y + 1 } else { y - 1 }) * bpl;
+                for x in range(0, bpl) {
+                    let (a, b) = (woven[above + x], woven[below + x]);
+                    let interpolated = ((a as uint + b as uint + 1) / 2) as u8;
+                    let moved = method == Bob ||
+                        difference(a, previous[above + x]) > threshold ||
+                        difference(b, previous[below + x]) > threshold;
+                    row[x] = if moved { interpolated } else { woven[y * bpl + x] };
+                }
+            }
+        }
+    }
+
+    // The result of the latest process().
+    pub fn frame<'a>(&'a self) -> Frame<'a> {
+        let planes = if self.filtered { &self.output } else { &self.woven };
+        Frame {
+            fourcc: self.fourcc,
+            width: self.width,
+            height: self.height,
+            planes: planes.iter().zip(self.bytesperline.iter()).map(|(data, &bytesperline)| {
+                Plane { data: data.as_slice(), bytesperline: bytesperline }
+            }).collect(),
+        }
+    }
+}
+
+fn difference(a: u8, b: u8) -> u8 {
+    if a > b { a - b } else { b - a }
+}
+
+// Lines in plane `plane` of a `height` line image; chroma planes of the
+// planar formats are subsampled vertically.
+fn lines(num_planes: uint, plane: uint, height: uint) -> uint {
+    if num_planes == 1 || plane == 0 { height } else { (height + 1) / 2 }
+}
+
+// Line `index` of `plane`, shorter or empty past the end of the plane.
+fn line<'a>(plane: &'a Plane, index: uint) -> &'a [u8] {
+    let length = plane.data.len();
+    let start = cmp::min(index * plane.bytesperline, length);
+    plane.data.slice(start, cmp::min(start + plane.bytesperline, length))
+}
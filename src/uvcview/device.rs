@@ -0,0 +1,944 @@
+// Safe, typed access to a V4L2 device.
+//
+// The structures in v4l2.rs follow the C headers: ioctls take them through
+// a raw pointer, and their unions are reached through *mut accessors. This
+// module is the only place that does either. Each method of Device issues
+// one ioctl and returns what the driver filled in, or the errno, leaving
+// what an error means to the caller. Structures without unions are passed
+// as they are; formats, buffers and capabilities get types of their own.
+
+use backend::{IoctlBackend,KernelBackend};
+use ioctl;
+use libc::consts::os::posix88::EINVAL;
+use libc::{c_int,c_ulong,O_RDWR};
+use libc;
+use std::cmp::min;
+use std::default::Default;
+use std::fmt;
+use std::mem;
+use std::mem::transmute;
+use std::os;
+use std::os::{MemoryMap,MapError};
+use std::raw::Slice;
+use std::str;
+use pixfmt;
+use uvc;
+use v4l2;
+use v4l2::{v4l2_buffer,v4l2_captureparm,v4l2_cropcap,v4l2_dv_timings,v4l2_dv_timings_cap};
+use v4l2::{v4l2_event,v4l2_event_ctrl,v4l2_format,v4l2_input,v4l2_plane,v4l2_queryctrl};
+use v4l2::{v4l2_rect,v4l2_standard,v4l2_std_id,v4l2_tuner};
+use v4l2::{v4l2_bt_timings,v4l2_bt_timings_cap};
+
+static MAX_PLANES: uint = v4l2::VIDEO_MAX_PLANES as uint;
+
+// v4l2_buf_type, as far as uvcview captures.
+#[deriving(Eq, Show, Clone)]
+pub enum BufType {
+    VideoCapture,
+    VideoCaptureMplane,
+    MetaCapture,
+}
+
+impl BufType {
+    pub fn from_v4l2(value: u32) -> Option<BufType> {
+        match value {
+            v4l2::V4L2_BUF_TYPE_VIDEO_CAPTURE => Some(VideoCapture),
+            v4l2::V4L2_BUF_TYPE_VIDEO_CAPTURE_MPLANE => Some(VideoCaptureMplane),
+            v4l2::V4L2_BUF_TYPE_META_CAPTURE => Some(MetaCapture),
+            _ => None
+        }
+    }
+
+    pub fn to_v4l2(self) -> u32 {
+        match self {
+            VideoCapture => v4l2::V4L2_BUF_TYPE_VIDEO_CAPTURE,
+            VideoCaptureMplane => v4l2::V4L2_BUF_TYPE_VIDEO_CAPTURE_MPLANE,
+            MetaCapture => v4l2::V4L2_BUF_TYPE_META_CAPTURE,
+        }
+    }
+
+    pub fn is_mplane(self) -> bool {
+        self == VideoCaptureMplane
+    }
+}
+
+// v4l2_memory.
+#[deriving(Eq, Show, Clone)]
+pub enum Memory {
+    Mmap,
+    UserPtr,
+    DmaBuf,
+}
+
+impl Memory {
+    pub fn from_v4l2(value: u32) -> Option<Memory> {
+        match value {
+            v4l2::V4L2_MEMORY_MMAP => Some(Mmap),
+            v4l2::V4L2_MEMORY_USERPTR => Some(UserPtr),
+            v4l2::V4L2_MEMORY_DMABUF => Some(DmaBuf),
+            _ => None
+        }
+    }
+
+    pub fn to_v4l2(self) -> u32 {
+        match self {
+            Mmap => v4l2::V4L2_MEMORY_MMAP,
+            UserPtr => v4l2::V4L2_MEMORY_USERPTR,
+            DmaBuf => v4l2::V4L2_MEMORY_DMABUF,
+        }
+    }
+}
+
+// v4l2_field. V4L2_FIELD_NONE is Progressive, which keeps clear of
+// Option's None.
+#[deriving(Eq, Show, Clone)]
+pub enum Field {
+    AnyField,
+    Progressive,
+    Top,
+    Bottom,
+    Interlaced,
+    SeqTb,
+    SeqBt,
+    Alternate,
+    InterlacedTb,
+    InterlacedBt,
+}
+
+impl Field {
+    pub fn from_v4l2(value: u32) -> Option<Field> {
+        match value {
+            v4l2::V4L2_FIELD_ANY => Some(AnyField),
+            v4l2::V4L2_FIELD_NONE => Some(Progressive),
+            v4l2::V4L2_FIELD_TOP => Some(Top),
+            v4l2::V4L2_FIELD_BOTTOM => Some(Bottom),
+            v4l2::V4L2_FIELD_INTERLACED => Some(Interlaced),
+            v4l2::V4L2_FIELD_SEQ_TB => Some(SeqTb),
+            v4l2::V4L2_FIELD_SEQ_BT => Some(SeqBt),
+            v4l2::V4L2_FIELD_ALTERNATE => Some(Alternate),
+            v4l2::V4L2_FIELD_INTERLACED_TB => Some(InterlacedTb),
+            v4l2::V4L2_FIELD_INTERLACED_BT => Some(InterlacedBt),
+            _ => None
+        }
+    }
+
+    pub fn to_v4l2(self) -> u32 {
+        match self {
+            AnyField => v4l2::V4L2_FIELD_ANY,
+            Progressive => v4l2::V4L2_FIELD_NONE,
+            Top => v4l2::V4L2_FIELD_TOP,
+            Bottom => v4l2::V4L2_FIELD_BOTTOM,
+            Interlaced => v4l2::V4L2_FIELD_INTERLACED,
+            SeqTb => v4l2::V4L2_FIELD_SEQ_TB,
+            SeqBt => v4l2::V4L2_FIELD_SEQ_BT,
+            Alternate => v4l2::V4L2_FIELD_ALTERNATE,
+            InterlacedTb => v4l2::V4L2_FIELD_INTERLACED_TB,
+            InterlacedBt => v4l2::V4L2_FIELD_INTERLACED_BT,
+        }
+    }
+
+    // The orders --field takes; a single field cannot be asked for.
+    pub fn from_str(s: &str) -> Option<Field> {
+        match s {
+            "any" => Some(AnyField),
+            "none" => Some(Progressive),
+            "interlaced" => Some(Interlaced),
+            "interlaced-tb" => Some(InterlacedTb),
+            "interlaced-bt" => Some(InterlacedBt),
+            "seq-tb" => Some(SeqTb),
+            "seq-bt" => Some(SeqBt),
+            "alternate" => Some(Alternate),
+            _ => None
+        }
+    }
+
+    // The name of the V4L2_FIELD_* constant, without the prefix.
+    pub fn name(self) -> &'static str {
+        match self {
+            AnyField => "ANY",
+            Progressive => "PROGRESSIVE",
+            Top => "TOP",
+            Bottom => "BOTTOM",
+            Interlaced => "INTERLACED",
+            SeqTb => "SEQ_TB",
+            SeqBt => "SEQ_BT",
+            Alternate => "ALTERNATE",
+            InterlacedTb => "INTERLACED_TB",
+            InterlacedBt => "INTERLACED_BT",
+        }
+    }
+
+    // True for a negotiated field order that carries both fields.
+    pub fn is_interlaced(self) -> bool {
+        match self {
+            Interlaced | InterlacedTb | InterlacedBt | SeqTb | SeqBt | Alternate => true,
+            _ => false
+        }
+    }
+}
+
+// Drivers must not report a field order V4L2 does not define; one that
+// does is taken as leaving the choice open.
+fn field_from_driver(value: u32) -> Field {
+    Field::from_v4l2(value).unwrap_or(AnyField)
+}
+
+// A four character code naming a pixel format.
+#[deriving(Eq, Clone)]
+pub struct Fourcc(pub u32);
+
+impl Fourcc {
+    pub fn from_str(s: &str) -> Option<Fourcc> {
+        pixfmt::fourcc_from_str(s).map(|code| Fourcc(code))
+    }
+
+    pub fn code(&self) -> u32 {
+        let Fourcc(code) = *self;
+        code
+    }
+}
+
+impl fmt::Show for Fourcc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", pixfmt::fourcc_to_str(self.code()))
+    }
+}
+
+// From VIDIOC_QUERYCAP.
+#[deriving(Clone, Show)]
+pub struct Capability {
+    pub driver: ~str,
+    pub card: ~str,
+    pub bus_info: ~str,
+    pub version: u32,
+    // V4L2_CAP_* flags of the whole device, and of this node.
+    pub capabilities: u32,
+    pub device_caps: u32,
+}
+
+impl Capability {
+    pub fn has(&self, capability: u32) -> bool {
+        (self.capabilities & capability) != 0
+    }
+
+    // What this node can do, where has() tells what the device behind all
+    // its nodes can. Drivers without V4L2_CAP_DEVICE_CAPS have one node.
+    pub fn node_has(&self, capability: u32) -> bool {
+        if self.has(v4l2::V4L2_CAP_DEVICE_CAPS) {
+            (self.device_caps & capability) != 0
+        } else {
+            self.has(capability)
+        }
+    }
+}
+
+// Negotiated layout of one memory plane.
+#[deriving(Clone, Show)]
+pub struct PlaneFormat {
+    pub bytesperline: u32,
+    pub sizeimage: u32,
+}
+
+// The v4l2_pix_format or v4l2_pix_format_mplane of a v4l2_format,
+// whichever `buf_type` selects.
+#[deriving(Clone, Show)]
+pub struct PixFormat {
+    pub buf_type: BufType,
+    pub width: u32,
+    pub height: u32,
+    pub pixelformat: Fourcc,
+    pub field: Field,
+    // One entry per memory plane. Left empty in a request, the driver
+    // chooses the layout.
+    pub planes: Vec<PlaneFormat>,
+}
+
+impl PixFormat {
+    pub fn new(buf_type: BufType, width: u32, height: u32, pixelformat: Fourcc, field: Field) -> PixFormat {
+        PixFormat {
+            buf_type: buf_type,
+            width: width,
+            height: height,
+            pixelformat: pixelformat,
+            field: field,
+            planes: vec!(),
+        }
+    }
+
+    fn to_v4l2(&self) -> v4l2_format {
+        let mut fmt: v4l2_format = Default::default();
+        fmt._type = self.buf_type.to_v4l2();
+        unsafe {
+            if self.buf_type.is_mplane() {
+                let pix_mp = &mut *fmt.fmt.pix_mp();
+                pix_mp.width = self.width;
+                pix_mp.height = self.height;
+                pix_mp.pixelformat = self.pixelformat.code();
+                pix_mp.field = self.field.to_v4l2();
+                let num_planes = min(self.planes.len(), MAX_PLANES);
+                pix_mp.num_planes = num_planes as u8;
+                for (i, plane) in self.planes.iter().take(num_planes).enumerate() {
+                    pix_mp.plane_fmt[i].bytesperline = plane.bytesperline;
+                    pix_mp.plane_fmt[i].sizeimage = plane.sizeimage;
+                }
+            } else {
+                let pix = &mut *fmt.fmt.pix();
+                pix.width = self.width;
+                pix.height = self.height;
+                pix.pixelformat = self.pixelformat.code();
+                pix.field = self.field.to_v4l2();
+                match self.planes.as_slice().head() {
+                    Some(plane) => {
+                        pix.bytesperline = plane.bytesperline;
+                        pix.sizeimage = plane.sizeimage;
+                    }
+                    None => {}
+                }
+            }
+        }
+        fmt
+    }
+
+    // Reads a format the driver filled in. Multi-planar drivers that leave
+    // num_planes at 0 get the plane count of the pixel format.
+    fn from_v4l2(fmt: &v4l2_format, buf_type: BufType) -> PixFormat {
+        let mut copy = *fmt;
+        let mut planes = vec!();
+        unsafe {
+            if buf_type.is_mplane() {
+                let pix_mp = &*copy.fmt.pix_mp();
+                let num_planes = if pix_mp.num_planes == 0 {
+                    pixfmt::num_planes(pix_mp.pixelformat)
+                } else {
+                    pix_mp.num_planes as uint
+                };
+                for i in range(0, min(num_planes, MAX_PLANES)) {
+                    planes.push(PlaneFormat {
+                        bytesperline: pix_mp.plane_fmt[i].bytesperline,
+                        sizeimage: pix_mp.plane_fmt[i].sizeimage,
+                    });
+                }
+                PixFormat {
+                    buf_type: buf_type,
+                    width: pix_mp.width,
+                    height: pix_mp.height,
+                    pixelformat: Fourcc(pix_mp.pixelformat),
+                    field: field_from_driver(pix_mp.field),
+                    planes: planes,
+                }
+            } else {
+                let pix = &*copy.fmt.pix();
+                planes.push(PlaneFormat {
+                    bytesperline: pix.bytesperline,
+                    sizeimage: pix.sizeimage,
+                });
+                PixFormat {
+                    buf_type: buf_type,
+                    width: pix.width,
+                    height: pix.height,
+                    pixelformat: Fourcc(pix.pixelformat),
+                    field: field_from_driver(pix.field),
+                    planes: planes,
+                }
+            }
+        }
+    }
+}
+
+// The v4l2_meta_format of a V4L2_BUF_TYPE_META_CAPTURE v4l2_format.
+#[deriving(Clone, Show)]
+pub struct MetaFormat {
+    pub dataformat: Fourcc,
+    // Size of each buffer, which holds all metadata of one frame.
+    pub buffersize: u32,
+}
+
+// Where the memory of a buffer plane is, by v4l2_memory.
+#[deriving(Eq, Show, Clone)]
+pub enum Location {
+    // Offset to mmap() the device at.
+    MmapOffset(u32),
+    UserPointer(uint),
+    DmaBufFd(c_int),
+}
+
+#[deriving(Clone, Show)]
+pub struct BufferPlane {
+    pub length: u32,
+    pub bytesused: u32,
+    pub location: Location,
+}
+
+// A v4l2_buffer, with the planes of multi-planar buffers in line.
+#[deriving(Clone, Show)]
+pub struct Buffer {
+    pub index: u32,
+    pub buf_type: BufType,
+    pub memory: Memory,
+    pub planes: Vec<BufferPlane>,
+    // V4L2_BUF_FLAG_* flags.
+    pub flags: u32,
+    pub field: Field,
+    pub sequence: u32,
+    pub timestamp_ns: u64,
+}
+
+impl Buffer {
+    pub fn new(buf_type: BufType, memory: Memory, index: u32, num_planes: uint) -> Buffer {
+        let location = match memory {
+            Mmap => MmapOffset(0),
+            UserPtr => UserPointer(0),
+            DmaBuf => DmaBufFd(-1),
+        };
+        Buffer {
+            index: index,
+            buf_type: buf_type,
+            memory: memory,
+            planes: Vec::from_elem(num_planes, BufferPlane { length: 0, bytesused: 0, location: location }),
+            flags: 0,
+            field: AnyField,
+            sequence: 0,
+            timestamp_ns: 0,
+        }
+    }
+}
+
+// The driver's end of the union in v4l2_buffer and v4l2_plane.
+fn location_of(memory: Memory, offset: u32, userptr: c_ulong, fd: i32) -> Location {
+    match memory {
+        Mmap => MmapOffset(offset),
+        UserPtr => UserPointer(userptr as uint),
+        DmaBuf => DmaBufFd(fd),
+    }
+}
+
+fn meta_format(fmt: &v4l2_format) -> MetaFormat {
+    let mut copy = *fmt;
+    let meta = unsafe { *copy.fmt.meta() };
+    MetaFormat {
+        dataformat: Fourcc(meta.dataformat),
+        buffersize: meta.buffersize,
+    }
+}
+
+// The text of a NUL padded C string field.
+pub fn c_string(bytes: &[u8]) -> ~str {
+    let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    match str::from_utf8(bytes.slice_to(len)) {
+        Some(s) => s.to_owned(),
+        None => "?".to_owned()
+    }
+}
+
+// BT.656/1120 timings from a v4l2_dv_timings, None for other types.
+pub fn bt_timings(timings: &v4l2_dv_timings) -> Option<v4l2_bt_timings> {
+    if timings._type != v4l2::V4L2_DV_BT_656_1120 {
+        return None;
+    }
+    let mut copy = *timings;
+    Some(unsafe { *copy.bt() })
+}
+
+pub fn bt_timings_cap(cap: &v4l2_dv_timings_cap) -> Option<v4l2_bt_timings_cap> {
+    if cap._type != v4l2::V4L2_DV_BT_656_1120 {
+        return None;
+    }
+    let mut copy = *cap;
+    Some(unsafe { *copy.bt() })
+}
+
+// The payload of a V4L2_EVENT_CTRL event and the control value in it.
+pub fn ctrl_event(event: &v4l2_event) -> Option<(v4l2_event_ctrl, i64)> {
+    if event._type != v4l2::V4L2_EVENT_CTRL {
+        return None;
+    }
+    let mut copy = *event;
+    unsafe {
+        let ctrl = &mut *copy.u.ctrl();
+        let value = if ctrl._type == v4l2::V4L2_CTRL_TYPE_INTEGER64 {
+            *ctrl.value64()
+        } else {
+            *ctrl.value() as i64
+        };
+        Some((*ctrl, value))
+    }
+}
+
+// The frame sequence number of a V4L2_EVENT_FRAME_SYNC event.
+pub fn frame_sync_event(event: &v4l2_event) -> Option<u32> {
+    if event._type != v4l2::V4L2_EVENT_FRAME_SYNC {
+        return None;
+    }
+    let mut copy = *event;
+    Some(unsafe { (*copy.u.frame_sync()).frame_sequence })
+}
+
+// The V4L2_EVENT_SRC_CH_* flags of a V4L2_EVENT_SOURCE_CHANGE event.
+pub fn src_change_event(event: &v4l2_event) -> Option<u32> {
+    if event._type != v4l2::V4L2_EVENT_SOURCE_CHANGE {
+        return None;
+    }
+    let mut copy = *event;
+    Some(unsafe { (*copy.u.src_change()).changes })
+}
+
+// The bytes of a mapping, which MemoryMap only hands out as a pointer.
+pub fn mapped<'a>(map: &'a MemoryMap) -> &'a [u8] {
+    unsafe { transmute(Slice { data: map.data as *u8, len: map.len }) }
+}
+
+pub fn mapped_mut<'a>(map: &'a mut MemoryMap) -> &'a mut [u8] {
+    unsafe { transmute(Slice { data: map.data as *u8, len: map.len }) }
+}
+
+pub struct Device {
+    // -1 until opened.
+    pub fd: c_int,
+    backend: ~IoctlBackend,
+}
+
+impl Default for Device {
+    fn default() -> Device {
+        Device::new(-1, ~KernelBackend as ~IoctlBackend)
+    }
+}
+
+impl Device {
+    pub fn new(fd: c_int, backend: ~IoctlBackend) -> Device {
+        Device {
+            fd: fd,
+            backend: backend,
+        }
+    }
+
+    // Opens the node at `path` for non-blocking i/o.
+    pub fn open(path: &Path) -> Result<Device, c_int> {
+        static O_NONBLOCK: c_int = 04000;
+        match path.with_c_str(|path| unsafe { libc::open(path, O_RDWR | O_NONBLOCK, 0) }) {
+            -1 => Err(os::errno() as c_int),
+            fd => Ok(Device::new(fd, ~KernelBackend as ~IoctlBackend))
+        }
+    }
+
+    pub fn close(&mut self) {
+        if self.fd != -1 {
+            self.backend.close(self.fd);
+            self.fd = -1;
+        }
+    }
+
+    // Maps `length` bytes of an MMAP buffer at `offset`.
+    pub fn mmap(&mut self, length: uint, offset: u32) -> Result<MemoryMap, MapError> {
+        self.backend.mmap(self.fd, length, offset as uint)
+    }
+
+    // Maps `length` bytes of an exported dma-buf.
+    pub fn mmap_dmabuf(&mut self, fd: c_int, length: uint) -> Result<MemoryMap, MapError> {
+        self.backend.mmap(fd, length, 0)
+    }
+
+    // Closes a dma-buf VIDIOC_EXPBUF exported.
+    pub fn close_dmabuf(&mut self, fd: c_int) {
+        self.backend.close(fd);
+    }
+
+    pub fn read(&mut self, buf: *mut u8, length: uint) -> Result<uint, c_int> {
+        self.backend.read(self.fd, buf, length)
+    }
+
+    // The only way into the kernel: `req` must take a `T`, which the size
+    // it encodes checks.
+    fn ioctl<T>(&mut self, req: u32, arg: &mut T) -> Result<(), c_int> {
+        assert_eq!(ioctl::size(req), mem::size_of::<T>());
+        self.backend.ioctl(self.fd, req, unsafe { transmute(arg) }).map(|_| ())
+    }
+
+    pub fn query_cap(&mut self) -> Result<Capability, c_int> {
+        let mut cap: v4l2::v4l2_capability = Default::default();
+        try!(self.ioctl(v4l2::VIDIOC_QUERYCAP, &mut cap));
+        Ok(Capability {
+            driver: c_string(cap.driver.as_slice()),
+            card: c_string(cap.card.as_slice()),
+            bus_info: c_string(cap.bus_info.as_slice()),
+            version: cap.version,
+            capabilities: cap.capabilities,
+            device_caps: cap.device_caps,
+        })
+    }
+
+    pub fn get_priority(&mut self) -> Result<u32, c_int> {
+        let mut priority: u32 = 0;
+        try!(self.ioctl(v4l2::VIDIOC_G_PRIORITY, &mut priority));
+        Ok(priority)
+    }
+
+    pub fn set_priority(&mut self, priority: u32) -> Result<(), c_int> {
+        let mut value = priority;
+        self.ioctl(v4l2::VIDIOC_S_PRIORITY, &mut value)
+    }
+
+    pub fn crop_cap(&mut self, buf_type: BufType) -> Result<v4l2_cropcap, c_int> {
+        let mut cropcap: v4l2_cropcap = Default::default();
+        cropcap._type = buf_type.to_v4l2();
+        try!(self.ioctl(v4l2::VIDIOC_CROPCAP, &mut cropcap));
+        Ok(cropcap)
+    }
+
+    pub fn get_selection(&mut self, buf_type: BufType, target: u32) -> Result<v4l2_rect, c_int> {
+        let mut sel: v4l2::v4l2_selection = Default::default();
+        sel._type = buf_type.to_v4l2();
+        sel.target = target;
+        try!(self.ioctl(v4l2::VIDIOC_G_SELECTION, &mut sel));
+        Ok(sel.r)
+    }
+
+    // Sets selection `target` and returns the rectangle the driver chose.
+    pub fn set_selection(&mut self, buf_type: BufType, target: u32, rect: v4l2_rect) -> Result<v4l2_rect, c_int> {
+        let mut sel: v4l2::v4l2_selection = Default::default();
+        sel._type = buf_type.to_v4l2();
+        sel.target = target;
+        sel.r = rect;
+        try!(self.ioctl(v4l2::VIDIOC_S_SELECTION, &mut sel));
+        Ok(sel.r)
+    }
+
+    pub fn get_crop(&mut self, buf_type: BufType) -> Result<v4l2_rect, c_int> {
+        let mut crop: v4l2::v4l2_crop = Default::default();
+        crop._type = buf_type.to_v4l2();
+        try!(self.ioctl(v4l2::VIDIOC_G_CROP, &mut crop));
+        Ok(crop.c)
+    }
+
+    pub fn set_crop(&mut self, buf_type: BufType, rect: v4l2_rect) -> Result<(), c_int> {
+        let mut crop: v4l2::v4l2_crop = Default::default();
+        crop._type = buf_type.to_v4l2();
+        crop.c = rect;
+        self.ioctl(v4l2::VIDIOC_S_CROP, &mut crop)
+    }
+
+    pub fn get_format(&mut self, buf_type: BufType) -> Result<PixFormat, c_int> {
+        let mut fmt: v4l2_format = Default::default();
+        fmt._type = buf_type.to_v4l2();
+        try!(self.ioctl(v4l2::VIDIOC_G_FMT, &mut fmt));
+        Ok(PixFormat::from_v4l2(&fmt, buf_type))
+    }
+
+    // Asks for `format` and returns what the driver settled on.
+    pub fn set_format(&mut self, format: &PixFormat) -> Result<PixFormat, c_int> {
+        let mut fmt = format.to_v4l2();
+        try!(self.ioctl(v4l2::VIDIOC_S_FMT, &mut fmt));
+        Ok(PixFormat::from_v4l2(&fmt, format.buf_type))
+    }
+
+    // What set_format() would settle on, without applying it.
+    pub fn try_format(&mut self, format: &PixFormat) -> Result<PixFormat, c_int> {
+        let mut fmt = format.to_v4l2();
+        try!(self.ioctl(v4l2::VIDIOC_TRY_FMT, &mut fmt));
+        Ok(PixFormat::from_v4l2(&fmt, format.buf_type))
+    }
+
+    pub fn get_meta_format(&mut self) -> Result<MetaFormat, c_int> {
+        let mut fmt: v4l2_format = Default::default();
+        fmt._type = v4l2::V4L2_BUF_TYPE_META_CAPTURE;
+        try!(self.ioctl(v4l2::VIDIOC_G_FMT, &mut fmt));
+        Ok(meta_format(&fmt))
+    }
+
+    // Asks for metadata in `dataformat` and returns what the driver
+    // settled on.
+    pub fn set_meta_format(&mut self, dataformat: u32) -> Result<MetaFormat, c_int> {
+        let mut fmt: v4l2_format = Default::default();
+        fmt._type = v4l2::V4L2_BUF_TYPE_META_CAPTURE;
+        unsafe {
+            (*fmt.fmt.meta()).dataformat = dataformat;
+        }
+        try!(self.ioctl(v4l2::VIDIOC_S_FMT, &mut fmt));
+        Ok(meta_format(&fmt))
+    }
+
+    pub fn get_parm(&mut self, buf_type: BufType) -> Result<v4l2_captureparm, c_int> {
+        let mut parm: v4l2::v4l2_streamparm = Default::default();
+        parm._type = buf_type.to_v4l2();
+        try!(self.ioctl(v4l2::VIDIOC_G_PARM, &mut parm));
+        Ok(unsafe { *parm.parm.capture() })
+    }
+
+    pub fn enum_input(&mut self, index: u32) -> Result<v4l2_input, c_int> {
+        let mut input: v4l2_input = Default::default();
+        input.index = index;
+        try!(self.ioctl(v4l2::VIDIOC_ENUMINPUT, &mut input));
+        Ok(input)
+    }
+
+    pub fn get_input(&mut self) -> Result<u32, c_int> {
+        let mut index: c_int = 0;
+        try!(self.ioctl(v4l2::VIDIOC_G_INPUT, &mut index));
+        Ok(index as u32)
+    }
+
+    pub fn set_input(&mut self, index: u32) -> Result<(), c_int> {
+        let mut value = index as c_int;
+        self.ioctl(v4l2::VIDIOC_S_INPUT, &mut value)
+    }
+
+    pub fn enum_std(&mut self, index: u32) -> Result<v4l2_standard, c_int> {
+        let mut standard: v4l2_standard = Default::default();
+        standard.index = index;
+        try!(self.ioctl(v4l2::VIDIOC_ENUMSTD, &mut standard));
+        Ok(standard)
+    }
+
+    pub fn get_std(&mut self) -> Result<v4l2_std_id, c_int> {
+        let mut id: v4l2_std_id = 0;
+        try!(self.ioctl(v4l2::VIDIOC_G_STD, &mut id));
+        Ok(id)
+    }
+
+    pub fn set_std(&mut self, id: v4l2_std_id) -> Result<(), c_int> {
+        let mut value = id;
+        self.ioctl(v4l2::VIDIOC_S_STD, &mut value)
+    }
+
+    pub fn query_std(&mut self) -> Result<v4l2_std_id, c_int> {
+        let mut id: v4l2_std_id = 0;
+        try!(self.ioctl(v4l2::VIDIOC_QUERYSTD, &mut id));
+        Ok(id)
+    }
+
+    pub fn get_tuner(&mut self, index: u32) -> Result<v4l2_tuner, c_int> {
+        let mut tuner: v4l2_tuner = Default::default();
+        tuner.index = index;
+        try!(self.ioctl(v4l2::VIDIOC_G_TUNER, &mut tuner));
+        Ok(tuner)
+    }
+
+    // Tunes `tuner` of type V4L2_TUNER_* `_type` to `frequency` tuner units.
+    pub fn set_frequency(&mut self, tuner: u32, _type: u32, frequency: u32) -> Result<(), c_int> {
+        let mut value: v4l2::v4l2_frequency = Default::default();
+        value.tuner = tuner;
+        value._type = _type;
+        value.frequency = frequency;
+        self.ioctl(v4l2::VIDIOC_S_FREQUENCY, &mut value)
+    }
+
+    pub fn enum_dv_timings(&mut self, index: u32) -> Result<v4l2_dv_timings, c_int> {
+        let mut timings: v4l2::v4l2_enum_dv_timings = Default::default();
+        timings.index = index;
+        try!(self.ioctl(v4l2::VIDIOC_ENUM_DV_TIMINGS, &mut timings));
+        Ok(timings.timings)
+    }
+
+    pub fn dv_timings_cap(&mut self) -> Result<v4l2_dv_timings_cap, c_int> {
+        let mut cap: v4l2_dv_timings_cap = Default::default();
+        try!(self.ioctl(v4l2::VIDIOC_DV_TIMINGS_CAP, &mut cap));
+        Ok(cap)
+    }
+
+    pub fn get_dv_timings(&mut self) -> Result<v4l2_dv_timings, c_int> {
+        let mut timings: v4l2_dv_timings = Default::default();
+        try!(self.ioctl(v4l2::VIDIOC_G_DV_TIMINGS, &mut timings));
+        Ok(timings)
+    }
+
+    pub fn set_dv_timings(&mut self, timings: &v4l2_dv_timings) -> Result<(), c_int> {
+        let mut value = *timings;
+        self.ioctl(v4l2::VIDIOC_S_DV_TIMINGS, &mut value)
+    }
+
+    pub fn query_dv_timings(&mut self) -> Result<v4l2_dv_timings, c_int> {
+        let mut timings: v4l2_dv_timings = Default::default();
+        try!(self.ioctl(v4l2::VIDIOC_QUERY_DV_TIMINGS, &mut timings));
+        Ok(timings)
+    }
+
+    pub fn subscribe_event(&mut self, _type: u32, id: u32) -> Result<(), c_int> {
+        let mut sub: v4l2::v4l2_event_subscription = Default::default();
+        sub._type = _type;
+        sub.id = id;
+        self.ioctl(v4l2::VIDIOC_SUBSCRIBE_EVENT, &mut sub)
+    }
+
+    pub fn dqevent(&mut self) -> Result<v4l2_event, c_int> {
+        let mut event: v4l2_event = Default::default();
+        try!(self.ioctl(v4l2::VIDIOC_DQEVENT, &mut event));
+        Ok(event)
+    }
+
+    pub fn query_ctrl(&mut self, id: u32) -> Result<v4l2_queryctrl, c_int> {
+        let mut query: v4l2_queryctrl = Default::default();
+        query.id = id;
+        try!(self.ioctl(v4l2::VIDIOC_QUERYCTRL, &mut query));
+        Ok(query)
+    }
+
+    pub fn get_ctrl(&mut self, id: u32) -> Result<i32, c_int> {
+        let mut control: v4l2::v4l2_control = Default::default();
+        control.id = id;
+        try!(self.ioctl(v4l2::VIDIOC_G_CTRL, &mut control));
+        Ok(control.value)
+    }
+
+    // Sets control `id` and returns the value the driver settled on.
+    pub fn set_ctrl(&mut self, id: u32, value: i32) -> Result<i32, c_int> {
+        let mut control: v4l2::v4l2_control = Default::default();
+        control.id = id;
+        control.value = value;
+        try!(self.ioctl(v4l2::VIDIOC_S_CTRL, &mut control));
+        Ok(control.value)
+    }
+
+    // Issues UVC request `query` (UVC_GET_CUR and so on) on control
+    // `selector` of extension unit `unit`. `data` is sent or filled in,
+    // and must be as long as the request expects.
+    pub fn xu_query(&mut self, unit: u8, selector: u8, query: u8, data: &mut [u8]) -> Result<(), c_int> {
+        let mut xu: uvc::uvc_xu_control_query = Default::default();
+        xu.unit = unit;
+        xu.selector = selector;
+        xu.query = query;
+        xu.size = data.len() as u16;
+        xu.data = data.as_mut_ptr();
+        self.ioctl(uvc::UVCIOC_CTRL_QUERY, &mut xu)
+    }
+
+    // Asks for `count` buffers, 0 to free them all, and returns how many
+    // the driver allocated.
+    pub fn req_bufs(&mut self, buf_type: BufType, memory: Memory, count: u32) -> Result<u32, c_int> {
+        let mut req: v4l2::v4l2_requestbuffers = Default::default();
+        req.count = count;
+        req._type = buf_type.to_v4l2();
+        req.memory = memory.to_v4l2();
+        try!(self.ioctl(v4l2::VIDIOC_REQBUFS, &mut req));
+        Ok(req.count)
+    }
+
+    // Adds up to `count` buffers for `format` and returns the index of the
+    // first and how many the driver created.
+    pub fn create_bufs(&mut self, memory: Memory, count: u32, format: &PixFormat) -> Result<(u32, u32), c_int> {
+        let mut create: v4l2::v4l2_create_buffers = Default::default();
+        create.count = count;
+        create.memory = memory.to_v4l2();
+        create.format = format.to_v4l2();
+        try!(self.ioctl(v4l2::VIDIOC_CREATE_BUFS, &mut create));
+        Ok((create.index, create.count))
+    }
+
+    pub fn query_buf(&mut self, buf_type: BufType, memory: Memory, index: u32,
+                     num_planes: uint) -> Result<Buffer, c_int> {
+        let mut buffer = Buffer::new(buf_type, memory, index, num_planes);
+        try!(self.buffer_ioctl(v4l2::VIDIOC_QUERYBUF, &mut buffer));
+        Ok(buffer)
+    }
+
+    pub fn prepare_buf(&mut self, buffer: &Buffer) -> Result<(), c_int> {
+        let mut copy = buffer.clone();
+        self.buffer_ioctl(v4l2::VIDIOC_PREPARE_BUF, &mut copy)
+    }
+
+    pub fn qbuf(&mut self, buffer: &Buffer) -> Result<(), c_int> {
+        let mut copy = buffer.clone();
+        self.buffer_ioctl(v4l2::VIDIOC_QBUF, &mut copy)
+    }
+
+    pub fn dqbuf(&mut self, buf_type: BufType, memory: Memory, num_planes: uint) -> Result<Buffer, c_int> {
+        let mut buffer = Buffer::new(buf_type, memory, 0, num_planes);
+        try!(self.buffer_ioctl(v4l2::VIDIOC_DQBUF, &mut buffer));
+        Ok(buffer)
+    }
+
+    // Exports plane `plane` of MMAP buffer `index` as a dma-buf opened
+    // with `flags`, and returns its fd.
+    pub fn expbuf(&mut self, buf_type: BufType, index: u32, plane: u32, flags: u32) -> Result<c_int, c_int> {
+        let mut expbuf: v4l2::v4l2_exportbuffer = Default::default();
+        expbuf._type = buf_type.to_v4l2();
+        expbuf.index = index;
+        expbuf.plane = plane;
+        expbuf.flags = flags;
+        try!(self.ioctl(v4l2::VIDIOC_EXPBUF, &mut expbuf));
+        Ok(expbuf.fd)
+    }
+
+    pub fn stream_on(&mut self, buf_type: BufType) -> Result<(), c_int> {
+        let mut value: v4l2::v4l2_buf_type = buf_type.to_v4l2();
+        self.ioctl(v4l2::VIDIOC_STREAMON, &mut value)
+    }
+
+    pub fn stream_off(&mut self, buf_type: BufType) -> Result<(), c_int> {
+        let mut value: v4l2::v4l2_buf_type = buf_type.to_v4l2();
+        self.ioctl(v4l2::VIDIOC_STREAMOFF, &mut value)
+    }
+
+    // Issues `req` on `buffer` and reads back what the driver filled in.
+    // Multi-planar buffers point the driver at a plane array on the stack,
+    // which outlives the ioctl.
+    fn buffer_ioctl(&mut self, req: u32, buffer: &mut Buffer) -> Result<(), c_int> {
+        if buffer.planes.len() > MAX_PLANES {
+            return Err(EINVAL);
+        }
+        let mut planes: [v4l2_plane, ..MAX_PLANES] = [Default::default(), ..MAX_PLANES];
+        let num_planes = buffer.planes.len();
+        let memory = buffer.memory;
+
+        let mut buf: v4l2_buffer = Default::default();
+        buf.index = buffer.index;
+        buf._type = buffer.buf_type.to_v4l2();
+        buf.memory = memory.to_v4l2();
+        buf.field = buffer.field.to_v4l2();
+        unsafe {
+            if buffer.buf_type.is_mplane() {
+                for (plane, info) in planes.mut_iter().zip(buffer.planes.iter()) {
+                    plane.length = info.length;
+                    plane.bytesused = info.bytesused;
+                    match info.location {
+                        MmapOffset(offset) => *plane.m.mem_offset() = offset,
+                        UserPointer(address) => *plane.m.userptr() = address as c_ulong,
+                        DmaBufFd(fd) => *plane.m.fd() = fd,
+                    }
+                }
+                *buf.m.planes() = planes.as_mut_ptr();
+                buf.length = num_planes as u32;
+            } else {
+                match buffer.planes.as_slice().head() {
+                    Some(info) => {
+                        buf.length = info.length;
+                        buf.bytesused = info.bytesused;
+                        match info.location {
+                            MmapOffset(offset) => *buf.m.offset() = offset,
+                            UserPointer(address) => *buf.m.userptr() = address as c_ulong,
+                            DmaBufFd(fd) => *buf.m.fd() = fd,
+                        }
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        try!(self.ioctl(req, &mut buf));
+
+        buffer.index = buf.index;
+        buffer.flags = buf.flags;
+        buffer.field = field_from_driver(buf.field);
+        buffer.sequence = buf.sequence;
+        buffer.timestamp_ns = buf.timestamp.tv_sec as u64 * 1000000000 +
+                              buf.timestamp.tv_usec as u64 * 1000;
+        unsafe {
+            if buffer.buf_type.is_mplane() {
+                for (info, plane) in buffer.planes.mut_iter().zip(planes.mut_iter()) {
+                    info.length = plane.length;
+                    info.bytesused = plane.bytesused;
+                    info.location = location_of(memory, *plane.m.mem_offset(), *plane.m.userptr(),
+                                                *plane.m.fd());
+                }
+            } else {
+                match buffer.planes.mut_iter().next() {
+                    Some(info) => {
+                        info.length = buf.length;
+                        info.bytesused = buf.bytesused;
+                        info.location = location_of(memory, *buf.m.offset(), *buf.m.userptr(), *buf.m.fd());
+                    }
+                    None => {}
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+// Digital video timings of HDMI, DVI and SDI receivers.
+//
+// Unlike a webcam, a receiver cannot choose its frame size: the source
+// decides, and the driver only reports what it sees with
+// VIDIOC_QUERY_DV_TIMINGS. Capturing anything means applying those timings
+// with VIDIOC_S_DV_TIMINGS and sizing the format to match, and doing that
+// again whenever V4L2_EVENT_SOURCE_CHANGE says the source switched modes.
+
+use std::fmt;
+
+use device;
+use v4l2;
+use v4l2::{v4l2_dv_timings,v4l2_dv_timings_cap};
+
+// How to pick the timings of a DV input in init().
+#[deriving(Eq, Show)]
+pub enum Request {
+    // Whatever VIDIOC_QUERY_DV_TIMINGS detects.
+    Detect,
+    // Entry `n` of VIDIOC_ENUM_DV_TIMINGS.
+    Index(u32),
+}
+
+pub fn request_from_str(s: &str) -> Option<Request> {
+    match s {
+        "auto" => Some(Detect),
+        _ => from_str::<u32>(s).map(|index| Index(index))
+    }
+}
+
+// BT.656/1120 timings, the only kind V4L2 defines.
+pub struct Timings {
+    pub width: u32,
+    pub height: u32,
+    pub interlaced: bool,
+    pub pixelclock: u64,
+    // Active plus blanking, per frame.
+    pub total_width: u32,
+    pub total_height: u32,
+    // As the driver reported them, for VIDIOC_S_DV_TIMINGS.
+    pub raw: v4l2_dv_timings,
+}
+
+impl Timings {
+    pub fn from_v4l2(raw: v4l2_dv_timings) -> Option<Timings> {
+        let bt = match device::bt_timings(&raw) {
+            Some(bt) => bt,
+            None => return None
+        };
+        let interlaced = bt.interlaced == v4l2::V4L2_DV_INTERLACED;
+        let mut total_height = bt.height + bt.vfrontporch + bt.vsync + bt.vbackporch;
+        if interlaced {
+            total_height += bt.il_vfrontporch + bt.il_vsync + bt.il_vbackporch;
+        }
+        Some(Timings {
+            width: bt.width,
+            height: bt.height,
+            interlaced: interlaced,
+            pixelclock: bt.pixelclock,
+            total_width: bt.width + bt.hfrontporch + bt.hsync + bt.hbackporch,
+            total_height: total_height,
+            raw: raw,
+        })
+    }
+
+    // Frames per second, 0 when the blanking is unknown.
+    pub fn frame_rate(&self) -> f64 {
+        let total = self.total_width as u64 * self.total_height as u64;
+        if total == 0 {
+            return 0.0;
+        }
+        self.pixelclock as f64 / total as f64
+    }
+
+    // True if both describe the same video mode, which needs no
+    // reconfiguration to switch between.
+    pub fn same_mode(&self, other: &Timings) -> bool {
+        self.width == other.width && self.height == other.height &&
+            self.interlaced == other.interlaced && self.pixelclock == other.pixelclock &&
+            self.total_width == other.total_width && self.total_height == other.total_height
+    }
+}
+
+impl fmt::Show for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}x{}{}{:.2}", self.width, self.height,
+               if self.interlaced { "i" } else { "p" }, self.frame_rate())
+    }
+}
+
+// Limits from VIDIOC_DV_TIMINGS_CAP.
+#[deriving(Show)]
+pub struct Capability {
+    pub min_width: u32,
+    pub max_width: u32,
+    pub min_height: u32,
+    pub max_height: u32,
+    pub min_pixelclock: u64,
+    pub max_pixelclock: u64,
+    // V4L2_DV_BT_CAP_* flags.
+    pub capabilities: u32,
+}
+
+impl Capability {
+    pub fn from_v4l2(cap: &v4l2_dv_timings_cap) -> Option<Capability> {
+        let bt = match device::bt_timings_cap(cap) {
+            Some(bt) => bt,
+            None => return None
+        };
+        Some(Capability {
+            min_width: bt.min_width,
+            max_width: bt.max_width,
+            min_height: bt.min_height,
+            max_height: bt.max_height,
+            min_pixelclock: bt.min_pixelclock,
+            max_pixelclock: bt.max_pixelclock,
+            capabilities: bt.capabilities,
+        })
+    }
+
+    // True if the receiver can capture `timings`.
+    pub fn supports(&self, timings: &Timings) -> bool {
+        let scan = if timings.interlaced {
+            v4l2::V4L2_DV_BT_CAP_INTERLACED
+        } else {
+            v4l2::V4L2_DV_BT_CAP_PROGRESSIVE
+        };
+        // Some drivers leave the scan flags out; treat that as no limit.
+        let scan_ok = (self.capabilities & (v4l2::V4L2_DV_BT_CAP_INTERLACED |
+                                            v4l2::V4L2_DV_BT_CAP_PROGRESSIVE)) == 0 ||
+            (self.capabilities & scan) != 0;
+        scan_ok &&
+            timings.width >= self.min_width && timings.width <= self.max_width &&
+            timings.height >= self.min_height && timings.height <= self.max_height &&
+            timings.pixelclock >= self.min_pixelclock && timings.pixelclock <= self.max_pixelclock
+    }
+}
@@ -0,0 +1,62 @@
+// Typed V4L2 events.
+//
+// Drivers queue an event on the file handle for everything subscribed to
+// with VIDIOC_SUBSCRIBE_EVENT and raise POLLPRI until VIDIOC_DQEVENT has
+// taken them all. Events of other types are dropped.
+
+use device;
+use v4l2;
+use v4l2::v4l2_event;
+
+// The new state of a control, from V4L2_EVENT_CTRL.
+#[deriving(Eq, Show, Clone)]
+pub struct ControlChange {
+    pub id: u32,
+    // V4L2_EVENT_CTRL_CH_* flags telling what changed.
+    pub changes: u32,
+    pub value: i64,
+    pub flags: u32,
+    pub minimum: i32,
+    pub maximum: i32,
+    pub step: i32,
+    pub default_value: i32,
+}
+
+#[deriving(Eq, Show, Clone)]
+pub enum Event {
+    // A control changed value, flags or range, for instance because
+    // another application set it.
+    Control(ControlChange),
+    // The driver will deliver no more buffers.
+    EndOfStream,
+    // The device started receiving the frame with this sequence number.
+    FrameSync(u32),
+    // The source of the input with this index changed; V4L2_EVENT_SRC_CH_*
+    // flags tell how.
+    SourceChange(u32, u32),
+}
+
+pub fn from_v4l2(event: &v4l2_event) -> Option<Event> {
+    match event._type {
+        v4l2::V4L2_EVENT_CTRL => {
+            device::ctrl_event(event).map(|(ctrl, value)| {
+                Control(ControlChange {
+                    id: event.id,
+                    changes: ctrl.changes,
+                    value: value,
+                    flags: ctrl.flags,
+                    minimum: ctrl.minimum,
+                    maximum: ctrl.maximum,
+                    step: ctrl.step,
+                    default_value: ctrl.default_value,
+                })
+            })
+        }
+        v4l2::V4L2_EVENT_EOS => Some(EndOfStream),
+        v4l2::V4L2_EVENT_FRAME_SYNC => device::frame_sync_event(event).map(|sequence| FrameSync(sequence)),
+        v4l2::V4L2_EVENT_SOURCE_CHANGE => {
+            device::src_change_event(event).map(|changes| SourceChange(event.id, changes))
+        }
+        _ => None
+    }
+}
@@ -0,0 +1,1037 @@
+// Scripted stand-in for a V4L2 capture driver.
+//
+// The device answers the ioctls UvcView issues the way a simple UVC driver
+// would. Each field below scripts one way to deviate from that, so error
+// handling and buggy-driver workarounds can be exercised without hardware.
+
+use backend::IoctlBackend;
+use libc::consts::os::posix88::{EINVAL,EAGAIN,EBUSY,ENOENT,ENOLINK,ENOMEM,ENOTTY};
+use libc::{c_int,c_void};
+use std::cmp::{min,max};
+use std::mem::transmute;
+use std::num::{from_u64,Saturating};
+use std::ptr::{copy_nonoverlapping_memory,set_memory};
+use std::raw::Slice;
+use std::os::{MemoryMap,MapError,MapReadable,MapWritable};
+use pixfmt;
+use time;
+use uvc;
+use uvc::uvc_xu_control_query;
+use v4l2;
+use v4l2::{v4l2_buffer,v4l2_capability,v4l2_create_buffers,v4l2_crop,v4l2_cropcap,v4l2_format};
+use v4l2::{v4l2_control,v4l2_dv_timings,v4l2_dv_timings_cap,v4l2_enum_dv_timings,v4l2_event};
+use v4l2::{v4l2_event_subscription,v4l2_exportbuffer,v4l2_queryctrl};
+use v4l2::{v4l2_frequency,v4l2_input,v4l2_plane,v4l2_requestbuffers,v4l2_rect,v4l2_selection};
+use v4l2::{v4l2_standard,v4l2_std_id,v4l2_streamparm,v4l2_tuner};
+
+// Pseudo request for scripting read() failures with FakeDevice::fail().
+pub static READ: u32 = 0xffffffff;
+
+pub static CARD: &'static str = "Fake Camera";
+
+// Crop bounds and default, the whole sensor.
+pub static SENSOR: v4l2_rect = v4l2_rect { left: 0, top: 0, width: 1280, height: 720 };
+
+// Range of the tuner, in units of 62.5 kHz.
+pub static TUNER_LOW: u32 = 44 * 16;
+pub static TUNER_HIGH: u32 = 958 * 16;
+
+// Largest mode the receiver takes.
+pub static DV_MAX: (u32, u32) = (1920, 1080);
+
+// Clock of the camera behind a metadata node, and the size of its
+// buffers, uvcvideo's UVC_METADATA_BUF_SIZE.
+pub static META_CLOCK_HZ: u64 = 48000000;
+pub static META_BUFFER_SIZE: u32 = 10240;
+
+// How long before VIDIOC_DQBUF monotonic timestamps say frames arrived.
+pub static CAPTURE_NS: u64 = 4000000;
+
+// First fd VIDIOC_EXPBUF hands out.
+static DMABUF_FD_BASE: c_int = 1000;
+
+// What uvcvideo answers a request the control does not support with.
+static EBADRQC: c_int = 56;
+
+pub struct FakeDevice {
+    // Reported by VIDIOC_QUERYCAP. Setting V4L2_CAP_VIDEO_CAPTURE_MPLANE
+    // instead of V4L2_CAP_VIDEO_CAPTURE makes this a multi-planar device.
+    pub capabilities: u32,
+    // Size VIDIOC_S_FMT settles on, None to accept the requested size.
+    pub size: Option<(u32, u32)>,
+    // The bridge cannot scale, so the frame takes the size of the crop
+    // rectangle.
+    pub fixed_scale: bool,
+    // Format VIDIOC_S_FMT settles on, None to accept the requested format.
+    pub pixelformat: Option<u32>,
+    // Reported as-is by VIDIOC_S_FMT for the first plane, None to report
+    // correct values.
+    pub bytesperline: Option<u32>,
+    pub sizeimage: Option<u32>,
+    // Field order VIDIOC_S_FMT settles on, None for V4L2_FIELD_NONE. With
+    // V4L2_FIELD_ALTERNATE buffers alternate between TOP and BOTTOM.
+    pub field: Option<u32>,
+    // Most buffers VIDIOC_REQBUFS will grant.
+    pub max_buffers: u32,
+    // Inputs by name, true for the input fed by tuner 0.
+    pub inputs: Vec<(&'static str, bool)>,
+    // Standards VIDIOC_ENUMSTD lists. Without any the standard ioctls fail
+    // with ENOTTY, as for a webcam. Setting a 525 line standard makes
+    // VIDIOC_S_FMT settle on 720x480, other standards on 720x576.
+    pub standards: Vec<(v4l2_std_id, &'static str)>,
+    // Reported by VIDIOC_QUERYSTD.
+    pub detected_std: v4l2_std_id,
+    // Modes VIDIOC_ENUM_DV_TIMINGS lists, at 60 Hz. Any make input 0 an
+    // HDMI input, and the DV timing ioctls fail with ENOTTY without.
+    pub dv_timings: Vec<(u32, u32)>,
+    // Mode VIDIOC_QUERY_DV_TIMINGS detects, None for no signal.
+    pub dv_signal: Option<(u32, u32)>,
+    // After the given number of dequeued buffers the source switches to
+    // the given mode, raising V4L2_EVENT_SOURCE_CHANGE if subscribed.
+    pub source_change: Option<(u32, (u32, u32))>,
+    // Controls as (id, name, minimum, maximum, value), sorted by id.
+    pub controls: Vec<(u32, &'static str, i32, i32, i32)>,
+    // After the given number of dequeued buffers another application sets
+    // the given control to the given value.
+    pub control_changes: Vec<(u32, u32, i32)>,
+    // Extension unit controls as (unit, selector, UVC_CONTROL_CAP_* flags,
+    // value). The length of the value is what GET_LEN reports.
+    pub xu_controls: Vec<(u8, u8, u8, Vec<u8>)>,
+    // Be the metadata node of a camera rather than its capture node:
+    // formats are V4L2_META_FMT_UVC, and each drain of the queue yields
+    // the payload header of one frame, as meta_block() builds it.
+    pub meta: bool,
+    // Timestamp dequeued buffers with CLOCK_MONOTONIC, CAPTURE_NS before
+    // the VIDIOC_DQBUF, and V4L2_BUF_FLAG_TIMESTAMP_MONOTONIC as uvcvideo
+    // does, rather than leave them without a timestamp.
+    pub monotonic: bool,
+    // read() returns at most this many bytes, as for a frame cut short.
+    pub read_length: Option<u32>,
+    // After this many dequeued buffers the stream ends.
+    pub eos_after: Option<u32>,
+    // Priority another application's file handle holds. While it is
+    // higher than ours, requests that change the device fail with EBUSY.
+    pub other_priority: u32,
+    // Another application streams from the device, so VIDIOC_S_FMT and
+    // VIDIOC_REQBUFS fail with EBUSY.
+    pub other_streaming: bool,
+    // Requests that fail with the given errno.
+    pub errors: Vec<(u32, c_int)>,
+
+    queued: Vec<u32>,
+    streaming: bool,
+    sequence: u32,
+    // Plane sizes of the current format and their sum.
+    plane_lengths: Vec<u32>,
+    length: u32,
+    // Plane sizes of each allocated buffer, indexed by buffer index.
+    lengths: Vec<Vec<u32>>,
+    crop: v4l2_rect,
+    input: u32,
+    std: v4l2_std_id,
+    frequency: u32,
+    dv_current: Option<(u32, u32)>,
+    priority: u32,
+    // The format last set, returned by VIDIOC_G_FMT.
+    format: Option<v4l2_format>,
+    // Event subscriptions as (type, id), and the events waiting.
+    subscribed: Vec<(u32, u32)>,
+    events: Vec<v4l2_event>,
+    // Buffers exported with VIDIOC_EXPBUF, and the dma-bufs queued with
+    // each DMABUF buffer, by index.
+    exported: Vec<c_int>,
+    dmabufs: Vec<(u32, Vec<c_int>)>,
+    // Mappings handed out, as (offset, address), for DQBUF to fill.
+    maps: Vec<(uint, *mut u8)>,
+    // Set when DQBUF returned this drain's frame of metadata.
+    meta_drained: bool,
+}
+
+impl FakeDevice {
+    pub fn new() -> FakeDevice {
+        FakeDevice {
+            capabilities: v4l2::V4L2_CAP_VIDEO_CAPTURE | v4l2::V4L2_CAP_STREAMING,
+            size: None,
+            fixed_scale: false,
+            pixelformat: None,
+            bytesperline: None,
+            sizeimage: None,
+            field: None,
+            max_buffers: 32,
+            inputs: vec!(("Camera 1", false)),
+            standards: vec!(),
+            detected_std: v4l2::V4L2_STD_UNKNOWN,
+            dv_timings: vec!(),
+            dv_signal: None,
+            source_change: None,
+            controls: vec!((v4l2::V4L2_CID_BRIGHTNESS, "Brightness", 0, 255, 128),
+                           (v4l2::V4L2_CID_EXPOSURE_ABSOLUTE, "Exposure (Absolute)", 3, 2047, 250)),
+            control_changes: vec!(),
+            xu_controls: vec!(),
+            meta: false,
+            monotonic: false,
+            read_length: None,
+            eos_after: None,
+            other_priority: v4l2::V4L2_PRIORITY_UNSET,
+            other_streaming: false,
+            errors: vec!(),
+            queued: vec!(),
+            streaming: false,
+            sequence: 0,
+            plane_lengths: vec!(),
+            length: 0,
+            lengths: vec!(),
+            crop: SENSOR,
+            input: 0,
+            std: v4l2::V4L2_STD_UNKNOWN,
+            frequency: TUNER_LOW,
+            dv_current: None,
+            priority: v4l2::V4L2_PRIORITY_DEFAULT,
+            format: None,
+            subscribed: vec!(),
+            events: vec!(),
+            exported: vec!(),
+            dmabufs: vec!(),
+            maps: vec!(),
+            meta_drained: false,
+        }
+    }
+
+    pub fn fail(mut self, req: u32, errno: c_int) -> FakeDevice {
+        self.errors.push((req, errno));
+        self
+    }
+
+    fn errno_for(&self, req: u32) -> Option<c_int> {
+        for &(r, errno) in self.errors.iter() {
+            if r == req {
+                return Some(errno);
+            }
+        }
+        None
+    }
+
+    // True if another application keeps `req` from changing the device.
+    fn busy(&self, req: u32) -> bool {
+        match req {
+            v4l2::VIDIOC_S_FMT | v4l2::VIDIOC_REQBUFS if self.other_streaming => true,
+            v4l2::VIDIOC_S_FMT | v4l2::VIDIOC_S_INPUT | v4l2::VIDIOC_S_STD |
+            v4l2::VIDIOC_S_FREQUENCY | v4l2::VIDIOC_S_DV_TIMINGS | v4l2::VIDIOC_S_CTRL |
+            v4l2::VIDIOC_S_CROP | v4l2::VIDIOC_S_SELECTION => self.other_priority > self.priority,
+            _ => false
+        }
+    }
+
+    // Whether the node has formats of `_type`.
+    fn has_type(&self, _type: u32) -> bool {
+        (_type == v4l2::V4L2_BUF_TYPE_META_CAPTURE) == self.meta
+    }
+
+    fn control_index(&self, id: u32) -> Option<uint> {
+        self.controls.iter().position(|&(control, _, _, _, _)| control == id)
+    }
+
+    // Queues an event of `_type` for `id` if it is subscribed to; `fill`
+    // sets the type specific data.
+    fn raise(&mut self, _type: u32, id: u32, fill: |&mut v4l2_event|) {
+        if !self.subscribed.contains(&(_type, id)) {
+            return;
+        }
+        let mut event: v4l2_event = Default::default();
+        event._type = _type;
+        event.id = id;
+        fill(&mut event);
+        self.events.push(event);
+    }
+
+    // Raises the events due after the buffer with `sequence`.
+    fn raise_frame_events(&mut self, sequence: u32) {
+        self.raise(v4l2::V4L2_EVENT_FRAME_SYNC, 0, |event| {
+            unsafe {
+                (*event.u.frame_sync()).frame_sequence = sequence;
+            }
+        });
+
+        let frames = sequence + 1;
+        match self.source_change {
+            Some((after, mode)) if after == frames => {
+                self.dv_signal = Some(mode);
+                let input = self.input;
+                self.raise(v4l2::V4L2_EVENT_SOURCE_CHANGE, input, |event| {
+                    unsafe {
+                        (*event.u.src_change()).changes = v4l2::V4L2_EVENT_SRC_CH_RESOLUTION;
+                    }
+                });
+                self.source_change = None;
+            }
+            _ => {}
+        }
+
+        let changes: Vec<(u32, u32, i32)> = self.control_changes.iter().filter(|&&(after, _, _)| {
+            after == frames
+        }).map(|&change| change).collect();
+        for &(_, id, value) in changes.iter() {
+            let index = match self.control_index(id) {
+                Some(index) => index,
+                None => continue
+            };
+            let (_, name, minimum, maximum, _) = *self.controls.get(index);
+            *self.controls.get_mut(index) = (id, name, minimum, maximum, value);
+            self.raise(v4l2::V4L2_EVENT_CTRL, id, |event| {
+                let ctrl = unsafe { &mut *event.u.ctrl() };
+                ctrl.changes = v4l2::V4L2_EVENT_CTRL_CH_VALUE;
+                ctrl._type = v4l2::V4L2_CTRL_TYPE_INTEGER;
+                unsafe {
+                    *ctrl.value() = value;
+                }
+                ctrl.minimum = minimum;
+                ctrl.maximum = maximum;
+                ctrl.step = 1;
+            });
+        }
+
+        if self.eos_after == Some(frames) {
+            self.raise(v4l2::V4L2_EVENT_EOS, 0, |_| {});
+        }
+    }
+
+    // Answers UVCIOC_CTRL_QUERY the way uvcvideo does.
+    fn xu_query(&mut self, query: &mut uvc_xu_control_query) -> Result<c_int, c_int> {
+        let index = match self.xu_controls.iter().position(|&(unit, selector, _, _)| {
+            unit == query.unit && selector == query.selector
+        }) {
+            Some(index) => index,
+            None => return Err(ENOENT)
+        };
+        let data: &mut [u8] = unsafe {
+            transmute(Slice { data: query.data as *u8, len: query.size as uint })
+        };
+        let (info, value) = match *self.xu_controls.get(index) {
+            (_, _, info, ref value) => (info, value.clone())
+        };
+        let expected = match query.query {
+            uvc::UVC_GET_LEN => 2,
+            uvc::UVC_GET_INFO => 1,
+            _ => value.len()
+        };
+        if data.len() != expected {
+            return Err(EINVAL);
+        }
+        let required = match query.query {
+            uvc::UVC_SET_CUR => uvc::UVC_CONTROL_CAP_SET,
+            uvc::UVC_GET_CUR | uvc::UVC_GET_MIN | uvc::UVC_GET_MAX | uvc::UVC_GET_RES |
+            uvc::UVC_GET_DEF => uvc::UVC_CONTROL_CAP_GET,
+            _ => 0
+        };
+        if (info & required) != required {
+            return Err(EBADRQC);
+        }
+        match query.query {
+            uvc::UVC_GET_LEN => {
+                data[0] = value.len() as u8;
+                data[1] = (value.len() >> 8) as u8;
+            }
+            uvc::UVC_GET_INFO => data[0] = info,
+            uvc::UVC_GET_CUR | uvc::UVC_GET_DEF => {
+                data.copy_from(value.as_slice());
+            }
+            uvc::UVC_GET_MIN => for byte in data.mut_iter() { *byte = 0 },
+            uvc::UVC_GET_MAX | uvc::UVC_GET_RES => for byte in data.mut_iter() { *byte = 0xff },
+            uvc::UVC_SET_CUR => {
+                let copy = Vec::from_slice(data);
+                *self.xu_controls.get_mut(index) = (query.unit, query.selector, info, copy);
+            }
+            _ => return Err(EINVAL)
+        }
+        Ok(0)
+    }
+
+    fn s_fmt(&mut self, fmt: &mut v4l2_format) {
+        self.plane_lengths = self.try_fmt(fmt);
+        self.length = self.plane_lengths.iter().fold(0, |sum, &length| sum + length);
+        self.format = Some(*fmt);
+    }
+
+    // Adjusts `fmt` the way VIDIOC_S_FMT would, without applying it, and
+    // returns the resulting plane sizes.
+    fn try_fmt(&self, fmt: &mut v4l2_format) -> Vec<u32> {
+        if fmt._type == v4l2::V4L2_BUF_TYPE_VIDEO_CAPTURE_MPLANE {
+            return self.try_fmt_mplane(fmt);
+        }
+        if fmt._type == v4l2::V4L2_BUF_TYPE_META_CAPTURE {
+            unsafe {
+                (*fmt.fmt.meta()).dataformat = v4l2::V4L2_META_FMT_UVC;
+                (*fmt.fmt.meta()).buffersize = META_BUFFER_SIZE;
+            }
+            return vec!(META_BUFFER_SIZE);
+        }
+        let pix = fmt.fmt.pix();
+        unsafe {
+            match self.size {
+                Some((width, height)) => {
+                    (*pix).width = width;
+                    (*pix).height = height;
+                }
+                None => {}
+            }
+            if self.fixed_scale {
+                (*pix).width = self.crop.width as u32;
+                (*pix).height = self.crop.height as u32;
+            }
+            match self.pixelformat {
+                Some(pixelformat) => (*pix).pixelformat = pixelformat,
+                None => {}
+            }
+            (*pix).field = self.field.unwrap_or(v4l2::V4L2_FIELD_NONE);
+            let bytesperline = pixfmt::min_bytesperline((*pix).pixelformat, (*pix).width as uint);
+            (*pix).bytesperline = self.bytesperline.unwrap_or(bytesperline as u32);
+            let sizeimage = pixfmt::image_size((*pix).pixelformat, (*pix).bytesperline as uint,
+                                               (*pix).height as uint);
+            (*pix).sizeimage = self.sizeimage.unwrap_or(sizeimage as u32);
+            vec!((*pix).sizeimage)
+        }
+    }
+
+    fn try_fmt_mplane(&self, fmt: &mut v4l2_format) -> Vec<u32> {
+        let pix_mp = fmt.fmt.pix_mp();
+        unsafe {
+            match self.size {
+                Some((width, height)) => {
+                    (*pix_mp).width = width;
+                    (*pix_mp).height = height;
+                }
+                None => {}
+            }
+            if self.fixed_scale {
+                (*pix_mp).width = self.crop.width as u32;
+                (*pix_mp).height = self.crop.height as u32;
+            }
+            match self.pixelformat {
+                Some(pixelformat) => (*pix_mp).pixelformat = pixelformat,
+                None => {}
+            }
+            (*pix_mp).field = self.field.unwrap_or(v4l2::V4L2_FIELD_NONE);
+            let pixelformat = (*pix_mp).pixelformat;
+            let num_planes = pixfmt::num_planes(pixelformat);
+            (*pix_mp).num_planes = num_planes as u8;
+            let mut lengths = vec!();
+            for i in range(0, num_planes) {
+                let plane = &mut (*pix_mp).plane_fmt[i];
+                let bytesperline = pixfmt::plane_min_bytesperline(pixelformat, i,
+                                                                  (*pix_mp).width as uint);
+                plane.bytesperline = bytesperline as u32;
+                plane.sizeimage = pixfmt::plane_size(pixelformat, i, bytesperline,
+                                                     (*pix_mp).height as uint) as u32;
+                if i == 0 {
+                    plane.bytesperline = self.bytesperline.unwrap_or(plane.bytesperline);
+                    plane.sizeimage = self.sizeimage.unwrap_or(plane.sizeimage);
+                }
+                lengths.push(plane.sizeimage);
+            }
+            lengths
+        }
+    }
+}
+
+fn push_le(data: &mut Vec<u8>, value: u64, bytes: uint) {
+    for i in range(0, bytes) {
+        data.push((value >> (8 * i)) as u8);
+    }
+}
+
+// The V4L2_META_FMT_UVC block of frame `sequence` from a 30 fps camera:
+// the exposure of each frame starts 33.3 ms after the last, the frame
+// goes out 5 ms later and its header reaches the host after another 1 ms.
+pub fn meta_block(sequence: u32) -> Vec<u8> {
+    let ns = 1000000000 + sequence as u64 * 33333333 + 6000000;
+    let sof = (ns / 1000000) & 0x7ff;
+    let pts = sequence as u64 * META_CLOCK_HZ / 30;
+    let stc = pts + META_CLOCK_HZ / 200;
+    let flags = uvc::UVC_STREAM_EOH | uvc::UVC_STREAM_SCR | uvc::UVC_STREAM_PTS |
+                (sequence % 2) as u8 * uvc::UVC_STREAM_FID;
+    let mut block = vec!();
+    push_le(&mut block, ns, 8);
+    push_le(&mut block, sof, 2);
+    block.push(12);
+    block.push(flags);
+    push_le(&mut block, pts, 4);
+    push_le(&mut block, stc, 4);
+    push_le(&mut block, sof, 2);
+    block
+}
+
+// The planes array of a multi-planar v4l2_buffer, None for single-planar
+// buffers.
+fn planes<'a>(buf: &'a mut v4l2_buffer) -> Option<&'a mut [v4l2_plane]> {
+    if buf._type != v4l2::V4L2_BUF_TYPE_VIDEO_CAPTURE_MPLANE {
+        return None;
+    }
+    unsafe {
+        let planes = *buf.m.planes();
+        Some(transmute(Slice { data: planes as *v4l2_plane, len: buf.length as uint }))
+    }
+}
+
+// Keeps a crop rectangle inside the sensor with an even width and height,
+// the way drivers round to their hardware's granularity.
+// CEA-861 style 60 Hz timings for a `width`x`height` mode.
+pub fn bt_timings(width: u32, height: u32) -> v4l2_dv_timings {
+    let mut timings: v4l2_dv_timings = Default::default();
+    timings._type = v4l2::V4L2_DV_BT_656_1120;
+    let bt = unsafe { &mut *timings.bt() };
+    bt.width = width;
+    bt.height = height;
+    bt.hfrontporch = 88;
+    bt.hsync = 44;
+    bt.hbackporch = 148;
+    bt.vfrontporch = 4;
+    bt.vsync = 5;
+    bt.vbackporch = 36;
+    bt.pixelclock = (width as u64 + 280) * (height as u64 + 45) * 60;
+    timings
+}
+
+fn adjust_crop(rect: &v4l2_rect) -> v4l2_rect {
+    let clamp = |v: i32, lo: i32, hi: i32| if v < lo { lo } else if v > hi { hi } else { v };
+    let left = clamp(rect.left, 0, SENSOR.width - 2);
+    let top = clamp(rect.top, 0, SENSOR.height - 2);
+    v4l2_rect {
+        left: left,
+        top: top,
+        width: clamp(rect.width, 2, SENSOR.width - left) & !1,
+        height: clamp(rect.height, 2, SENSOR.height - top) & !1,
+    }
+}
+
+// Plane sizes requested by the format passed to VIDIOC_CREATE_BUFS.
+fn format_lengths(fmt: &mut v4l2_format) -> Vec<u32> {
+    unsafe {
+        if fmt._type == v4l2::V4L2_BUF_TYPE_VIDEO_CAPTURE_MPLANE {
+            let pix_mp = fmt.fmt.pix_mp();
+            range(0, (*pix_mp).num_planes as uint).map(|i| {
+                (*pix_mp).plane_fmt[i].sizeimage
+            }).collect()
+        } else {
+            vec!((*fmt.fmt.pix()).sizeimage)
+        }
+    }
+}
+
+impl IoctlBackend for FakeDevice {
+    fn ioctl(&mut self, _fd: c_int, req: u32, arg: *mut c_void) -> Result<c_int, c_int> {
+        match self.errno_for(req) {
+            Some(errno) => return Err(errno),
+            None => {}
+        }
+        if self.busy(req) {
+            return Err(EBUSY);
+        }
+
+        match req {
+            v4l2::VIDIOC_QUERYCAP => {
+                let cap: &mut v4l2_capability = unsafe { transmute(arg) };
+                cap.capabilities = self.capabilities;
+                if self.meta {
+                    cap.capabilities |= v4l2::V4L2_CAP_META_CAPTURE | v4l2::V4L2_CAP_DEVICE_CAPS;
+                    cap.device_caps = v4l2::V4L2_CAP_META_CAPTURE | v4l2::V4L2_CAP_STREAMING;
+                }
+                for (dst, &src) in cap.card.mut_iter().zip(CARD.as_bytes().iter()) {
+                    *dst = src;
+                }
+            }
+            v4l2::VIDIOC_ENUMINPUT => {
+                let input: &mut v4l2_input = unsafe { transmute(arg) };
+                if input.index as uint >= self.inputs.len() {
+                    return Err(EINVAL);
+                }
+                let &(name, tuner) = self.inputs.get(input.index as uint);
+                for (dst, &src) in input.name.mut_iter().zip(name.as_bytes().iter()) {
+                    *dst = src;
+                }
+                input._type = if tuner { v4l2::V4L2_INPUT_TYPE_TUNER } else { v4l2::V4L2_INPUT_TYPE_CAMERA };
+                input.tuner = 0;
+                input.std = self.standards.iter().fold(0, |std, &(id, _)| std | id);
+                if input.index == 0 && !self.dv_timings.is_empty() {
+                    input.capabilities = v4l2::V4L2_IN_CAP_DV_TIMINGS;
+                }
+            }
+            v4l2::VIDIOC_G_INPUT => {
+                let index: &mut c_int = unsafe { transmute(arg) };
+                *index = self.input as c_int;
+            }
+            v4l2::VIDIOC_S_INPUT => {
+                let index: &mut c_int = unsafe { transmute(arg) };
+                if *index < 0 || *index as uint >= self.inputs.len() {
+                    return Err(EINVAL);
+                }
+                self.input = *index as u32;
+            }
+            v4l2::VIDIOC_ENUMSTD => {
+                let standard: &mut v4l2_standard = unsafe { transmute(arg) };
+                if standard.index as uint >= self.standards.len() {
+                    return Err(EINVAL);
+                }
+                let &(id, name) = self.standards.get(standard.index as uint);
+                standard.id = id;
+                for (dst, &src) in standard.name.mut_iter().zip(name.as_bytes().iter()) {
+                    *dst = src;
+                }
+                let (period, lines) = if (id & v4l2::V4L2_STD_525_60) != 0 { (1001, 525) } else { (1200, 625) };
+                standard.frameperiod.numerator = period;
+                standard.frameperiod.denominator = 30000;
+                standard.framelines = lines;
+            }
+            v4l2::VIDIOC_G_STD | v4l2::VIDIOC_S_STD | v4l2::VIDIOC_QUERYSTD if self.standards.is_empty() => {
+                return Err(ENOTTY);
+            }
+            v4l2::VIDIOC_G_STD => {
+                let id: &mut v4l2_std_id = unsafe { transmute(arg) };
+                *id = self.std;
+            }
+            v4l2::VIDIOC_S_STD => {
+                let id: &mut v4l2_std_id = unsafe { transmute(arg) };
+                match self.standards.iter().find(|&&(std, _)| (std & *id) != 0) {
+                    Some(&(std, _)) => self.std = std,
+                    None => return Err(EINVAL)
+                }
+                self.size = Some(if (self.std & v4l2::V4L2_STD_525_60) != 0 { (720, 480) } else { (720, 576) });
+            }
+            v4l2::VIDIOC_QUERYSTD => {
+                let id: &mut v4l2_std_id = unsafe { transmute(arg) };
+                *id = self.detected_std;
+            }
+            v4l2::VIDIOC_G_TUNER => {
+                let tuner: &mut v4l2_tuner = unsafe { transmute(arg) };
+                if tuner.index != 0 || !self.inputs.iter().any(|&(_, tuner)| tuner) {
+                    return Err(EINVAL);
+                }
+                tuner._type = v4l2::V4L2_TUNER_ANALOG_TV;
+                tuner.rangelow = TUNER_LOW;
+                tuner.rangehigh = TUNER_HIGH;
+            }
+            v4l2::VIDIOC_S_FREQUENCY => {
+                let frequency: &mut v4l2_frequency = unsafe { transmute(arg) };
+                if frequency.tuner != 0 || frequency.frequency < TUNER_LOW || frequency.frequency > TUNER_HIGH {
+                    return Err(EINVAL);
+                }
+                self.frequency = frequency.frequency;
+            }
+            v4l2::VIDIOC_G_FREQUENCY => {
+                let frequency: &mut v4l2_frequency = unsafe { transmute(arg) };
+                frequency.frequency = self.frequency;
+            }
+            v4l2::VIDIOC_DV_TIMINGS_CAP | v4l2::VIDIOC_ENUM_DV_TIMINGS | v4l2::VIDIOC_QUERY_DV_TIMINGS |
+            v4l2::VIDIOC_G_DV_TIMINGS | v4l2::VIDIOC_S_DV_TIMINGS if self.dv_timings.is_empty() => {
+                return Err(ENOTTY);
+            }
+            v4l2::VIDIOC_DV_TIMINGS_CAP => {
+                let cap: &mut v4l2_dv_timings_cap = unsafe { transmute(arg) };
+                cap._type = v4l2::V4L2_DV_BT_656_1120;
+                let bt = unsafe { &mut *cap.bt() };
+                let (max_width, max_height) = DV_MAX;
+                bt.min_width = 640;
+                bt.min_height = 480;
+                bt.max_width = max_width;
+                bt.max_height = max_height;
+                bt.min_pixelclock = 25000000;
+                bt.max_pixelclock = 165000000;
+                bt.capabilities = v4l2::V4L2_DV_BT_CAP_PROGRESSIVE;
+            }
+            v4l2::VIDIOC_ENUM_DV_TIMINGS => {
+                let timings: &mut v4l2_enum_dv_timings = unsafe { transmute(arg) };
+                if timings.index as uint >= self.dv_timings.len() {
+                    return Err(EINVAL);
+                }
+                let &(width, height) = self.dv_timings.get(timings.index as uint);
+                timings.timings = bt_timings(width, height);
+            }
+            v4l2::VIDIOC_QUERY_DV_TIMINGS => {
+                let timings: &mut v4l2_dv_timings = unsafe { transmute(arg) };
+                match self.dv_signal {
+                    Some((width, height)) => *timings = bt_timings(width, height),
+                    None => return Err(ENOLINK)
+                }
+            }
+            v4l2::VIDIOC_G_DV_TIMINGS => {
+                let timings: &mut v4l2_dv_timings = unsafe { transmute(arg) };
+                let (width, height) = self.dv_current.unwrap_or(*self.dv_timings.get(0));
+                *timings = bt_timings(width, height);
+            }
+            v4l2::VIDIOC_S_DV_TIMINGS => {
+                // Like real drivers, refuses while buffers are allocated.
+                if self.streaming || !self.lengths.is_empty() {
+                    return Err(EBUSY);
+                }
+                let timings: &mut v4l2_dv_timings = unsafe { transmute(arg) };
+                let bt = unsafe { &*timings.bt() };
+                self.dv_current = Some((bt.width, bt.height));
+                self.size = self.dv_current;
+            }
+            v4l2::VIDIOC_QUERYCTRL => {
+                let query: &mut v4l2_queryctrl = unsafe { transmute(arg) };
+                let next = (query.id & v4l2::V4L2_CTRL_FLAG_NEXT_CTRL) != 0;
+                let id = query.id & !v4l2::V4L2_CTRL_FLAG_NEXT_CTRL;
+                let found = self.controls.iter().find(|&&(control, _, _, _, _)| {
+                    if next { control > id } else { control == id }
+                });
+                match found {
+                    Some(&(control, name, minimum, maximum, _)) => {
+                        query.id = control;
+                        query._type = v4l2::V4L2_CTRL_TYPE_INTEGER;
+                        for (dst, &src) in query.name.mut_iter().zip(name.as_bytes().iter()) {
+                            *dst = src;
+                        }
+                        query.minimum = minimum;
+                        query.maximum = maximum;
+                        query.step = 1;
+                        query.default_value = minimum + (maximum - minimum) / 2;
+                    }
+                    None => return Err(EINVAL)
+                }
+            }
+            v4l2::VIDIOC_G_CTRL => {
+                let control: &mut v4l2_control = unsafe { transmute(arg) };
+                match self.control_index(control.id) {
+                    Some(index) => {
+                        let (_, _, _, _, value) = *self.controls.get(index);
+                        control.value = value;
+                    }
+                    None => return Err(EINVAL)
+                }
+            }
+            v4l2::VIDIOC_S_CTRL => {
+                let control: &mut v4l2_control = unsafe { transmute(arg) };
+                match self.control_index(control.id) {
+                    Some(index) => {
+                        let (id, name, minimum, maximum, _) = *self.controls.get(index);
+                        let value = if control.value < minimum {
+                            minimum
+                        } else if control.value > maximum {
+                            maximum
+                        } else {
+                            control.value
+                        };
+                        *self.controls.get_mut(index) = (id, name, minimum, maximum, value);
+                        control.value = value;
+                    }
+                    None => return Err(EINVAL)
+                }
+            }
+            v4l2::VIDIOC_SUBSCRIBE_EVENT => {
+                let sub: &mut v4l2_event_subscription = unsafe { transmute(arg) };
+                let supported = match sub._type {
+                    v4l2::V4L2_EVENT_CTRL => self.control_index(sub.id).is_some(),
+                    v4l2::V4L2_EVENT_EOS | v4l2::V4L2_EVENT_FRAME_SYNC => true,
+                    v4l2::V4L2_EVENT_SOURCE_CHANGE => !self.dv_timings.is_empty(),
+                    _ => false
+                };
+                if !supported {
+                    return Err(EINVAL);
+                }
+                self.subscribed.push((sub._type, sub.id));
+            }
+            v4l2::VIDIOC_DQEVENT => {
+                let event: &mut v4l2_event = unsafe { transmute(arg) };
+                match self.events.shift() {
+                    Some(next) => {
+                        *event = next;
+                        event.pending = self.events.len() as u32;
+                    }
+                    None => return Err(ENOENT)
+                }
+            }
+            v4l2::VIDIOC_CROPCAP => {
+                let cropcap: &mut v4l2_cropcap = unsafe { transmute(arg) };
+                cropcap.bounds = SENSOR;
+                cropcap.defrect = SENSOR;
+                cropcap.pixelaspect.numerator = 1;
+                cropcap.pixelaspect.denominator = 1;
+            }
+            v4l2::VIDIOC_S_CROP => {
+                let crop: &mut v4l2_crop = unsafe { transmute(arg) };
+                self.crop = adjust_crop(&crop.c);
+            }
+            v4l2::VIDIOC_G_CROP => {
+                let crop: &mut v4l2_crop = unsafe { transmute(arg) };
+                crop.c = self.crop;
+            }
+            v4l2::VIDIOC_S_SELECTION => {
+                let sel: &mut v4l2_selection = unsafe { transmute(arg) };
+                if sel.target != v4l2::V4L2_SEL_TGT_CROP {
+                    return Err(EINVAL);
+                }
+                self.crop = adjust_crop(&sel.r);
+                sel.r = self.crop;
+            }
+            v4l2::VIDIOC_G_SELECTION => {
+                let sel: &mut v4l2_selection = unsafe { transmute(arg) };
+                sel.r = match sel.target {
+                    v4l2::V4L2_SEL_TGT_CROP => self.crop,
+                    v4l2::V4L2_SEL_TGT_CROP_DEFAULT | v4l2::V4L2_SEL_TGT_CROP_BOUNDS => SENSOR,
+                    _ => return Err(EINVAL)
+                };
+            }
+            v4l2::VIDIOC_S_FMT => {
+                let fmt: &mut v4l2_format = unsafe { transmute(arg) };
+                if !self.has_type(fmt._type) {
+                    return Err(EINVAL);
+                }
+                self.s_fmt(fmt);
+            }
+            v4l2::VIDIOC_G_FMT => {
+                let fmt: &mut v4l2_format = unsafe { transmute(arg) };
+                if !self.has_type(fmt._type) {
+                    return Err(EINVAL);
+                }
+                match self.format {
+                    Some(format) if format._type == fmt._type => *fmt = format,
+                    _ => {
+                        // Nobody set a format yet; the driver's default.
+                        let _type = fmt._type;
+                        *fmt = Default::default();
+                        fmt._type = _type;
+                        unsafe {
+                            if _type == v4l2::V4L2_BUF_TYPE_VIDEO_CAPTURE_MPLANE {
+                                (*fmt.fmt.pix_mp()).width = 1280;
+                                (*fmt.fmt.pix_mp()).height = 720;
+                                (*fmt.fmt.pix_mp()).pixelformat = v4l2::V4L2_PIX_FMT_NV12M;
+                            } else if _type != v4l2::V4L2_BUF_TYPE_META_CAPTURE {
+                                (*fmt.fmt.pix()).width = 1280;
+                                (*fmt.fmt.pix()).height = 720;
+                                (*fmt.fmt.pix()).pixelformat = v4l2::V4L2_PIX_FMT_YUYV;
+                            }
+                        }
+                        self.s_fmt(fmt);
+                    }
+                }
+            }
+            v4l2::VIDIOC_G_PRIORITY => {
+                let priority: &mut u32 = unsafe { transmute(arg) };
+                *priority = max(self.priority, self.other_priority);
+            }
+            v4l2::VIDIOC_S_PRIORITY => {
+                let priority: &mut u32 = unsafe { transmute(arg) };
+                if *priority > v4l2::V4L2_PRIORITY_RECORD {
+                    return Err(EINVAL);
+                }
+                // Only one file handle may record.
+                if *priority == v4l2::V4L2_PRIORITY_RECORD &&
+                    self.other_priority == v4l2::V4L2_PRIORITY_RECORD {
+                    return Err(EBUSY);
+                }
+                self.priority = if *priority == v4l2::V4L2_PRIORITY_UNSET {
+                    v4l2::V4L2_PRIORITY_DEFAULT
+                } else {
+                    *priority
+                };
+            }
+            v4l2::VIDIOC_G_PARM => {
+                let parm: &mut v4l2_streamparm = unsafe { transmute(arg) };
+                unsafe {
+                    (*parm.parm.capture()).timeperframe.numerator = 1;
+                    (*parm.parm.capture()).timeperframe.denominator = 30;
+                }
+            }
+            v4l2::VIDIOC_TRY_FMT => {
+                let fmt: &mut v4l2_format = unsafe { transmute(arg) };
+                if !self.has_type(fmt._type) {
+                    return Err(EINVAL);
+                }
+                self.try_fmt(fmt);
+            }
+            v4l2::VIDIOC_REQBUFS => {
+                let req: &mut v4l2_requestbuffers = unsafe { transmute(arg) };
+                req.count = min(req.count, self.max_buffers);
+                self.lengths.clear();
+                self.lengths.grow(req.count as uint, &self.plane_lengths);
+                self.maps.clear();
+            }
+            v4l2::VIDIOC_CREATE_BUFS => {
+                let create: &mut v4l2_create_buffers = unsafe { transmute(arg) };
+                let lengths = format_lengths(&mut create.format);
+                create.index = self.lengths.len() as u32;
+                let left = self.max_buffers.saturating_sub(create.index);
+                if left == 0 && create.count > 0 {
+                    return Err(ENOMEM);
+                }
+                create.count = min(create.count, left);
+                self.lengths.grow(create.count as uint, &lengths);
+            }
+            v4l2::VIDIOC_EXPBUF => {
+                let expbuf: &mut v4l2_exportbuffer = unsafe { transmute(arg) };
+                match self.lengths.as_slice().get(expbuf.index as uint) {
+                    Some(lengths) if (expbuf.plane as uint) < lengths.len() => {}
+                    _ => return Err(EINVAL)
+                }
+                // Past any fd the test process holds; close() ignores it.
+                let fd = DMABUF_FD_BASE + self.exported.len() as c_int;
+                self.exported.push(fd);
+                expbuf.fd = fd;
+            }
+            v4l2::VIDIOC_PREPARE_BUF => {
+                let buf: &mut v4l2_buffer = unsafe { transmute(arg) };
+                if buf.index as uint >= self.lengths.len() {
+                    return Err(EINVAL);
+                }
+            }
+            v4l2::VIDIOC_QUERYBUF => {
+                let buf: &mut v4l2_buffer = unsafe { transmute(arg) };
+                if buf.index as uint >= self.lengths.len() {
+                    return Err(EINVAL);
+                }
+                let index = buf.index;
+                let lengths = self.lengths.get(index as uint);
+                match planes(buf) {
+                    Some(planes) => {
+                        if planes.len() < lengths.len() {
+                            return Err(EINVAL);
+                        }
+                        for (i, &length) in lengths.iter().enumerate() {
+                            planes[i].length = length;
+                            unsafe {
+                                *planes[i].m.mem_offset() = index * 0x100000 + i as u32 * 0x10000;
+                            }
+                        }
+                    }
+                    None => {
+                        buf.length = *lengths.get(0);
+                        unsafe {
+                            *buf.m.offset() = index * 0x100000;
+                        }
+                    }
+                }
+            }
+            v4l2::VIDIOC_QBUF => {
+                let buf: &mut v4l2_buffer = unsafe { transmute(arg) };
+                let index = buf.index;
+                if index as uint >= self.lengths.len() {
+                    return Err(EINVAL);
+                }
+                match planes(buf) {
+                    Some(planes) if planes.len() < self.lengths.get(index as uint).len() => {
+                        return Err(EINVAL);
+                    }
+                    _ => {}
+                }
+                if buf.memory == v4l2::V4L2_MEMORY_DMABUF {
+                    let fds: Vec<c_int> = match planes(buf) {
+                        Some(planes) => planes.mut_iter().map(|plane| unsafe { *plane.m.fd() }).collect(),
+                        None => vec!(unsafe { *buf.m.fd() })
+                    };
+                    if !fds.iter().all(|fd| self.exported.contains(fd)) {
+                        return Err(EINVAL);
+                    }
+                    self.dmabufs.retain(|&(queued, _)| queued != index);
+                    self.dmabufs.push((index, fds));
+                }
+                self.queued.push(index);
+            }
+            v4l2::VIDIOC_DQBUF => {
+                if !self.streaming {
+                    return Err(EINVAL);
+                }
+                let buf: &mut v4l2_buffer = unsafe { transmute(arg) };
+                if self.meta && self.meta_drained {
+                    self.meta_drained = false;
+                    return Err(EAGAIN);
+                }
+                match self.queued.shift() {
+                    Some(index) => {
+                        buf.index = index;
+                        buf.sequence = self.sequence;
+                        buf.field = match self.field {
+                            Some(v4l2::V4L2_FIELD_ALTERNATE) if self.sequence % 2 == 0 => {
+                                v4l2::V4L2_FIELD_TOP
+                            }
+                            Some(v4l2::V4L2_FIELD_ALTERNATE) => v4l2::V4L2_FIELD_BOTTOM,
+                            Some(field) => field,
+                            None => v4l2::V4L2_FIELD_NONE
+                        };
+                        let sequence = self.sequence;
+                        self.raise_frame_events(sequence);
+                        self.sequence += 1;
+                        let lengths = self.lengths.get(index as uint);
+                        match planes(buf) {
+                            Some(planes) => {
+                                for (i, &length) in lengths.iter().enumerate() {
+                                    planes[i].bytesused = length;
+                                }
+                            }
+                            None => buf.bytesused = *lengths.get(0)
+                        }
+                        match self.dmabufs.iter().find(|&&(queued, _)| queued == index) {
+                            Some(&(_, ref fds)) => {
+                                match planes(buf) {
+                                    Some(planes) => {
+                                        for (plane, &fd) in planes.mut_iter().zip(fds.iter()) {
+                                            unsafe {
+                                                *plane.m.fd() = fd;
+                                            }
+                                        }
+                                    }
+                                    None => unsafe {
+                                        *buf.m.fd() = *fds.get(0);
+                                    }
+                                }
+                            }
+                            None => {}
+                        }
+                        if self.monotonic {
+                            let ns = time::precise_time_ns() - CAPTURE_NS;
+                            buf.timestamp.tv_sec = from_u64(ns / 1000000000).unwrap();
+                            buf.timestamp.tv_usec = from_u64(ns % 1000000000 / 1000).unwrap();
+                            buf.flags |= v4l2::V4L2_BUF_FLAG_TIMESTAMP_MONOTONIC;
+                        }
+                        if self.meta {
+                            let block = meta_block(sequence);
+                            let offset = index as uint * 0x100000;
+                            match self.maps.iter().find(|&&(map, _)| map == offset) {
+                                Some(&(_, data)) => unsafe {
+                                    copy_nonoverlapping_memory(data, block.as_ptr(), block.len());
+                                },
+                                None => {}
+                            }
+                            buf.bytesused = block.len() as u32;
+                            self.meta_drained = true;
+                        }
+                    }
+                    None => return Err(EAGAIN)
+                }
+            }
+            v4l2::VIDIOC_STREAMON => {
+                self.streaming = true;
+            }
+            uvc::UVCIOC_CTRL_QUERY => {
+                let query: &mut uvc_xu_control_query = unsafe { transmute(arg) };
+                return self.xu_query(query);
+            }
+            v4l2::VIDIOC_STREAMOFF => {
+                self.streaming = false;
+                self.queued.clear();
+            }
+            _ => return Err(EINVAL)
+        }
+        Ok(0)
+    }
+
+    fn mmap(&mut self, _fd: c_int, length: uint, offset: uint) -> Result<MemoryMap, MapError> {
+        let map = try!(MemoryMap::new(length, &[MapReadable, MapWritable]));
+        self.maps.push((offset, map.data));
+        Ok(map)
+    }
+
+    fn read(&mut self, _fd: c_int, buf: *mut u8, length: uint) -> Result<uint, c_int> {
+        match self.errno_for(READ) {
+            Some(errno) => return Err(errno),
+            None => {}
+        }
+        let count = min(length, self.read_length.map_or(self.length, |limit| min(limit, self.length)) as uint);
+        unsafe {
+            set_memory(buf, self.sequence as u8, count);
+        }
+        self.sequence += 1;
+        Ok(count)
+    }
+
+    fn close(&mut self, _fd: c_int) {}
+}
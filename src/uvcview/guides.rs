@@ -0,0 +1,165 @@
+// Composition guides and region of interest measurements.
+//
+// Guides are laid over the image rectangle in the window. The ROI is given
+// in frame pixels so its statistics do not depend on zoom or window size.
+
+use std::num::Float;
+
+use overlay::Canvas;
+use pixfmt::Frame;
+use scale::Rect;
+
+static GUIDE_COLOR: (u8, u8, u8) = (200, 200, 200);
+static ACTION_SAFE_COLOR: (u8, u8, u8) = (0, 200, 0);
+static TITLE_SAFE_COLOR: (u8, u8, u8) = (200, 200, 0);
+
+// Safe areas as a percentage of the picture, per EBU R 95.
+static ACTION_SAFE: uint = 93;
+static TITLE_SAFE: uint = 90;
+
+pub struct Guides {
+    pub thirds: bool,
+    pub crosshair: bool,
+    pub safe_areas: bool,
+}
+
+impl Guides {
+    pub fn new() -> Guides {
+        Guides { thirds: false, crosshair: false, safe_areas: false }
+    }
+
+    // Draws the enabled guides over the image at `dest`.
+    pub fn draw(&self, canvas: &mut Canvas, dest: Rect) {
+        if dest.width == 0 || dest.height == 0 {
+            return;
+        }
+        if self.thirds {
+            for i in range(1u, 3) {
+                canvas.fill_rect(dest.x + dest.width * i / 3, dest.y, 1, dest.height, GUIDE_COLOR);
+                canvas.fill_rect(dest.x, dest.y + dest.height * i / 3, dest.width, 1, GUIDE_COLOR);
+            }
+        }
+        if self.crosshair {
+            let size = (if dest.width < dest.height { dest.width } else { dest.height }) / 10;
+            let (cx, cy) = (dest.x + dest.width / 2, dest.y + dest.height / 2);
+            canvas.fill_rect(cx - size / 2, cy, size, 1, GUIDE_COLOR);
+            canvas.fill_rect(cx, cy - size / 2, 1, size, GUIDE_COLOR);
+        }
+        if self.safe_areas {
+            let area = |percent: uint| -> Rect {
+                let width = dest.width * percent / 100;
+                let height = dest.height * percent / 100;
+                Rect {
+                    x: dest.x + (dest.width - width) / 2,
+                    y: dest.y + (dest.height - height) / 2,
+                    width: width,
+                    height: height,
+                }
+            };
+            let action = area(ACTION_SAFE);
+            canvas.draw_rect(action.x, action.y, action.width, action.height, ACTION_SAFE_COLOR);
+            let title = area(TITLE_SAFE);
+            canvas.draw_rect(title.x, title.y, title.width, title.height, TITLE_SAFE_COLOR);
+        }
+    }
+}
+
+#[deriving(Eq, Show, Clone)]
+pub struct ChannelStats {
+    pub mean: f64,
+    pub min: u8,
+    pub max: u8,
+    pub stddev: f64,
+}
+
+#[deriving(Show)]
+pub struct RoiStats {
+    pub pixels: uint,
+    pub luma: ChannelStats,
+    pub red: ChannelStats,
+    pub green: ChannelStats,
+    pub blue: ChannelStats,
+}
+
+impl RoiStats {
+    // Lines for the on-screen overlay.
+    pub fn lines(&self) -> Vec<~str> {
+        let line = |name: &str, c: &ChannelStats| -> ~str {
+            format!("{} {:.1} {} {} {:.2}", name, c.mean, c.min, c.max, c.stddev)
+        };
+        vec!(format!("ROI {} PX  MEAN MIN MAX SD", self.pixels),
+             line("Y", &self.luma),
+             line("R", &self.red),
+             line("G", &self.green),
+             line("B", &self.blue))
+    }
+}
+
+// Accumulates one channel's samples.
+struct Accumulator {
+    sum: f64,
+    sum_squares: f64,
+    min: u8,
+    max: u8,
+}
+
+impl Accumulator {
+    fn new() -> Accumulator {
+        Accumulator { sum: 0.0, sum_squares: 0.0, min: 255, max: 0 }
+    }
+
+    fn add(&mut self, v: u8) {
+        self.sum += v as f64;
+        self.sum_squares += v as f64 * v as f64;
+        if v < self.min {
+            self.min = v;
+        }
+        if v > self.max {
+            self.max = v;
+        }
+    }
+
+    fn stats(&self, n: uint) -> ChannelStats {
+        let mean = self.sum / n as f64;
+        let variance = self.sum_squares / n as f64 - mean * mean;
+        ChannelStats {
+            mean: mean,
+            min: self.min,
+            max: self.max,
+            stddev: if variance > 0.0 { variance.sqrt() } else { 0.0 },
+        }
+    }
+}
+
+// Statistics over the part of `roi` inside `frame`, None if that is empty.
+pub fn measure(frame: &Frame, roi: Rect) -> Option<RoiStats> {
+    let x1 = if roi.x + roi.width < frame.width { roi.x + roi.width } else { frame.width };
+    let y1 = if roi.y + roi.height < frame.height { roi.y + roi.height } else { frame.height };
+    if roi.x >= x1 || roi.y >= y1 {
+        return None;
+    }
+
+    let mut luma = Accumulator::new();
+    let mut red = Accumulator::new();
+    let mut green = Accumulator::new();
+    let mut blue = Accumulator::new();
+    for y in range(roi.y, y1) {
+        for x in range(roi.x, x1) {
+            let (l, _, _) = frame.ycbcr_at(x, y);
+            let (r, g, b) = frame.rgb_at(x, y);
+            luma.add(l);
+            red.add(r);
+            green.add(g);
+            blue.add(b);
+        }
+    }
+
+    let n = (x1 - roi.x) * (y1 - roi.y);
+    Some(RoiStats {
+        pixels: n,
+        luma: luma.stats(n),
+        red: red.stats(n),
+        green: green.stats(n),
+        blue: blue.stats(n),
+    })
+}
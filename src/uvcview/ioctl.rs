@@ -0,0 +1,75 @@
+#![macro_escape]
+#![allow(dead_code)]
+
+// ioctl request numbers, laid out as <asm-generic/ioctl.h> does: the
+// transfer direction in the top two bits, then 14 bits of argument size,
+// the ioctl type and its number. x86, x86_64, ARM and aarch64 all use this
+// layout; Alpha, MIPS, PowerPC and SPARC do not and are not supported.
+//
+// The size is the only part that differs between those targets, so the
+// request numbers are built here from the sizes of the argument structs
+// rather than copied from headers compiled for one of them.
+
+pub static IOC_NRBITS: u32 = 8;
+pub static IOC_TYPEBITS: u32 = 8;
+pub static IOC_SIZEBITS: u32 = 14;
+pub static IOC_DIRBITS: u32 = 2;
+
+pub static IOC_NRSHIFT: u32 = 0;
+pub static IOC_TYPESHIFT: u32 = IOC_NRSHIFT + IOC_NRBITS;
+pub static IOC_SIZESHIFT: u32 = IOC_TYPESHIFT + IOC_TYPEBITS;
+pub static IOC_DIRSHIFT: u32 = IOC_SIZESHIFT + IOC_SIZEBITS;
+
+pub static IOC_NONE: u32 = 0;
+pub static IOC_WRITE: u32 = 1;
+pub static IOC_READ: u32 = 2;
+
+// _IOC(dir, type, nr, size)
+macro_rules! ioc(
+    ($dir:expr, $ty:expr, $nr:expr, $size:expr) => (
+        ($dir << ::ioctl::IOC_DIRSHIFT) | ($size << ::ioctl::IOC_SIZESHIFT) |
+        (($ty as u32) << ::ioctl::IOC_TYPESHIFT) | ($nr << ::ioctl::IOC_NRSHIFT)
+    )
+)
+
+// _IO(type, nr): no argument.
+macro_rules! io(
+    ($ty:expr, $nr:expr) => (ioc!(::ioctl::IOC_NONE, $ty, $nr, 0))
+)
+
+// _IOR(type, nr, sizeof(arg)): the driver fills in the argument.
+macro_rules! ior(
+    ($ty:expr, $nr:expr, $size:expr) => (ioc!(::ioctl::IOC_READ, $ty, $nr, $size))
+)
+
+// _IOW(type, nr, sizeof(arg)): the driver reads the argument.
+macro_rules! iow(
+    ($ty:expr, $nr:expr, $size:expr) => (ioc!(::ioctl::IOC_WRITE, $ty, $nr, $size))
+)
+
+// _IOWR(type, nr, sizeof(arg)): both.
+macro_rules! iowr(
+    ($ty:expr, $nr:expr, $size:expr) => (
+        ioc!(::ioctl::IOC_READ | ::ioctl::IOC_WRITE, $ty, $nr, $size)
+    )
+)
+
+// _IOC_DIR()
+pub fn dir(req: u32) -> u32 {
+    (req >> IOC_DIRSHIFT) & ((1 << IOC_DIRBITS) - 1)
+}
+
+// _IOC_TYPE()
+pub fn ioc_type(req: u32) -> u32 {
+    (req >> IOC_TYPESHIFT) & ((1 << IOC_TYPEBITS) - 1)
+}
+
+// _IOC_NR()
+pub fn nr(req: u32) -> u32 {
+    (req >> IOC_NRSHIFT) & ((1 << IOC_NRBITS) - 1)
+}
+
+// _IOC_SIZE(): the size of the argument `req` copies in or out.
+pub fn size(req: u32) -> uint {
+    ((req >> IOC_SIZESHIFT) & ((1 << IOC_SIZEBITS) - 1)) as uint
+}
@@ -0,0 +1,316 @@
+// Latency of the capture and display pipeline.
+//
+// Drivers that set V4L2_BUF_FLAG_TIMESTAMP_MONOTONIC stamp each buffer with
+// CLOCK_MONOTONIC, the clock time::precise_time_ns() reads, as the frame is
+// captured; uvcvideo does when its first packet arrives. Reading the same
+// clock as the frame is dequeued, converted and flipped tells how long the
+// frame took to get through each stage. Timestamps of other drivers come
+// from clocks that cannot be compared, so those frames are only counted.
+//
+// None of that covers the time a frame spends in the camera or on its way
+// to the screen. For those, the flash test turns the window white and
+// waits for a camera pointed at the screen to see it: the time from the
+// flip that showed the flash to the flip that showed the frame capturing
+// it is the glass-to-glass latency.
+
+use std::fmt;
+use std::iter::range_step;
+use pixfmt::Frame;
+use scale::Rect;
+use v4l2;
+
+// Latencies kept per stage.
+static WINDOW: uint = 600;
+
+// How long the window stays dark between flashes, and how long a change
+// may take to show in the camera before the flash is given up on.
+static FLASH_PERIOD_NS: u64 = 1000000000;
+static FLASH_TIMEOUT_NS: u64 = 2000000000;
+
+// Rise of mean luma over the dark level that counts as seeing the flash.
+pub static FLASH_RISE: f64 = 48.0;
+
+#[deriving(Eq, Show, Clone)]
+pub enum Stage {
+    // From the buffer timestamp to VIDIOC_DQBUF, conversion end and flip.
+    Dequeue,
+    Convert,
+    Flip,
+    // From the flip showing the flash to the flip showing it captured.
+    Glass,
+}
+
+impl Stage {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Dequeue => "dqbuf",
+            Convert => "convert",
+            Flip => "flip",
+            Glass => "glass",
+        }
+    }
+}
+
+// Recent latencies of a stage in nanoseconds.
+pub struct Distribution {
+    samples: Vec<u64>,
+    // Latencies added since the reset, including those no longer kept.
+    pub count: u64,
+}
+
+impl Distribution {
+    pub fn new() -> Distribution {
+        Distribution { samples: vec!(), count: 0 }
+    }
+
+    pub fn add(&mut self, ns: u64) {
+        if self.samples.len() == WINDOW {
+            self.samples.shift();
+        }
+        self.samples.push(ns);
+        self.count += 1;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    // The `q` quantile of the kept latencies, `q` from 0 for the least to
+    // 1 for the most.
+    pub fn quantile(&self, q: f64) -> Option<u64> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let mut sorted = self.samples.clone();
+        sorted.sort();
+        let i = ((sorted.len() - 1) as f64 * q + 0.5) as uint;
+        Some(*sorted.get(i))
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let sum = self.samples.iter().fold(0.0, |sum, &ns| sum + ns as f64);
+        Some(sum / self.samples.len() as f64)
+    }
+}
+
+impl fmt::Show for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ms = |q: f64| self.quantile(q).unwrap_or(0) as f64 / 1e6;
+        if self.samples.is_empty() {
+            return write!(f, "no samples");
+        }
+        write!(f, "min {:.2} med {:.2} p95 {:.2} max {:.2} mean {:.2} ms ({})",
+               ms(0.0), ms(0.5), ms(0.95), ms(1.0), self.mean().unwrap() / 1e6, self.count)
+    }
+}
+
+#[deriving(Eq, Show, Clone)]
+pub enum FlashState {
+    // The window is dark, since the given time.
+    Dark(u64),
+    // The window turns white with the next flip.
+    Showing,
+    // The flip at the given time turned the window white, and the camera
+    // has not seen it yet.
+    Lit(u64),
+    // The flash was seen or given up on at the given time; the window is
+    // dark until the camera sees that.
+    Clearing(u64),
+}
+
+pub struct Latency {
+    pub dequeue: Distribution,
+    pub convert: Distribution,
+    pub flip: Distribution,
+    pub glass: Distribution,
+    // Frames whose timestamps are not CLOCK_MONOTONIC.
+    pub incomparable: u64,
+    // The flash test, None when not flashing.
+    pub flash: Option<FlashState>,
+    // Flashes the camera did not see in time.
+    pub missed: u64,
+    // Mean luma of the dark window in the captured image.
+    dark_luma: f64,
+    // Timestamp and dequeue time of the current frame.
+    timestamp_ns: Option<u64>,
+    dequeued_ns: u64,
+    // Flip time of the flash the current frame captured.
+    seen: Option<u64>,
+}
+
+impl Latency {
+    pub fn new(flash: bool) -> Latency {
+        Latency {
+            dequeue: Distribution::new(),
+            convert: Distribution::new(),
+            flip: Distribution::new(),
+            glass: Distribution::new(),
+            incomparable: 0,
+            flash: if flash { Some(Dark(0)) } else { None },
+            missed: 0,
+            dark_luma: 0.0,
+            timestamp_ns: None,
+            dequeued_ns: 0,
+            seen: None,
+        }
+    }
+
+    // Forgets the stream so far, keeping to flash or not.
+    pub fn reset(&mut self) {
+        *self = Latency::new(self.flash.is_some());
+    }
+
+    pub fn distribution<'a>(&'a self, stage: Stage) -> &'a Distribution {
+        match stage {
+            Dequeue => &self.dequeue,
+            Convert => &self.convert,
+            Flip => &self.flip,
+            Glass => &self.glass,
+        }
+    }
+
+    // Adds how long the current frame took to reach a stage at `now_ns`.
+    fn reached(&mut self, stage: Stage, now_ns: u64) {
+        match self.timestamp_ns {
+            Some(timestamp_ns) if now_ns >= timestamp_ns => {
+                let latency = now_ns - timestamp_ns;
+                match stage {
+                    Dequeue => self.dequeue.add(latency),
+                    Convert => self.convert.add(latency),
+                    _ => self.flip.add(latency),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Starts on a frame with the given v4l2_buffer timestamp and flags,
+    // dequeued at `now_ns`.
+    pub fn dequeued(&mut self, timestamp_ns: u64, flags: u32, now_ns: u64) {
+        let monotonic = (flags & v4l2::V4L2_BUF_FLAG_TIMESTAMP_MASK) == v4l2::V4L2_BUF_FLAG_TIMESTAMP_MONOTONIC;
+        self.timestamp_ns = if monotonic && timestamp_ns != 0 {
+            Some(timestamp_ns)
+        } else {
+            self.incomparable += 1;
+            None
+        };
+        self.dequeued_ns = now_ns;
+        self.seen = None;
+        self.reached(Dequeue, now_ns);
+    }
+
+    pub fn converted(&mut self, now_ns: u64) {
+        self.reached(Convert, now_ns);
+    }
+
+    pub fn flipped(&mut self, now_ns: u64) {
+        self.reached(Flip, now_ns);
+        match self.flash {
+            Some(Showing) => self.flash = Some(Lit(now_ns)),
+            _ => {}
+        }
+        match self.seen.take() {
+            Some(shown_ns) => self.glass.add(now_ns - shown_ns),
+            None => {}
+        }
+    }
+
+    // Moves the flash test on by the mean luma of the current frame where
+    // the camera sees the window.
+    pub fn observe(&mut self, luma: f64) {
+        let now = self.dequeued_ns;
+        let state = match self.flash {
+            Some(state) => state,
+            None => return
+        };
+        self.flash = Some(match state {
+            Dark(since) => {
+                self.dark_luma = luma;
+                if elapsed(now, since) >= FLASH_PERIOD_NS { Showing } else { state }
+            }
+            Lit(shown_ns) if luma >= self.dark_luma + FLASH_RISE => {
+                self.seen = Some(shown_ns);
+                Clearing(now)
+            }
+            Lit(shown_ns) if elapsed(now, shown_ns) >= FLASH_TIMEOUT_NS => {
+                self.missed += 1;
+                Clearing(now)
+            }
+            Clearing(since) if luma < self.dark_luma + FLASH_RISE / 2.0 ||
+                               elapsed(now, since) >= FLASH_TIMEOUT_NS => Dark(now),
+            _ => state
+        });
+    }
+
+    // Whether the window is to be white.
+    pub fn flash_lit(&self) -> bool {
+        match self.flash {
+            Some(Showing) | Some(Lit(_)) => true,
+            _ => false
+        }
+    }
+
+    // Lines for the on-screen overlay.
+    pub fn lines(&self) -> Vec<~str> {
+        let mut lines = vec!();
+        for &stage in [Dequeue, Convert, Flip, Glass].iter() {
+            let distribution = self.distribution(stage);
+            if !distribution.is_empty() {
+                lines.push(format!("{} {}", stage.name(), distribution).to_ascii_upper());
+            }
+        }
+        if self.incomparable > 0 {
+            lines.push(format!("{} FRAMES WITHOUT MONOTONIC TIMESTAMPS", self.incomparable));
+        }
+        if self.missed > 0 {
+            lines.push(format!("{} FLASHES MISSED", self.missed));
+        }
+        lines
+    }
+}
+
+// The report printed when the stream ends.
+impl fmt::Show for Latency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "latency from buffer timestamp:"));
+        for &stage in [Dequeue, Convert, Flip].iter() {
+            try!(write!(f, "\n  {:8s} {}", stage.name(), self.distribution(stage)));
+        }
+        if self.incomparable > 0 {
+            try!(write!(f, "\n  {} frames without monotonic timestamps", self.incomparable));
+        }
+        if self.flash.is_some() {
+            try!(write!(f, "\nglass-to-glass latency:\n  {:8s} {}", Glass.name(), self.glass));
+            try!(write!(f, "\n  {} flashes missed", self.missed));
+        }
+        Ok(())
+    }
+}
+
+fn elapsed(now_ns: u64, since_ns: u64) -> u64 {
+    if now_ns > since_ns { now_ns - since_ns } else { 0 }
+}
+
+// Mean luma of `region` of `frame`, from every 8th pixel of every 8th
+// line; None if the region is outside the frame.
+pub fn mean_luma(frame: &Frame, region: Rect) -> Option<f64> {
+    let x1 = if region.x + region.width < frame.width { region.x + region.width } else { frame.width };
+    let y1 = if region.y + region.height < frame.height { region.y + region.height } else { frame.height };
+    let mut sum = 0u64;
+    let mut n = 0u64;
+    for y in range_step(region.y, y1, 8) {
+        for x in range_step(region.x, x1, 8) {
+            let (luma, _, _) = frame.ycbcr_at(x, y);
+            sum += luma as u64;
+            n += 1;
+        }
+    }
+    if n == 0 {
+        None
+    } else {
+        Some(sum as f64 / n as f64)
+    }
+}
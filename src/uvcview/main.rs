@@ -3,21 +3,44 @@
 extern crate getopts;
 extern crate sdl;
 extern crate libc;
+extern crate time;
 #[phase(syntax, link)]
 extern crate log;
 
-use getopts::{getopts,optopt,optflag,usage};
+use getopts::{getopts,optopt,optflag,optmulti,usage};
 use libc::consts::os::c95::EXIT_FAILURE;
 use libc::consts::os::posix88::{EINTR};
-use std::mem::{transmute};
 use std::default::Default;
-use std::mem;
 use std::os;
-use std::ptr::{null};
+use scale::Rect;
+use latency::Latency;
+use meta::MetaNode;
+use testsrc::TestPattern;
 use uvcview::UvcView;
 
+mod analog;
+mod assist;
+mod backend;
+mod controls;
+mod deinterlace;
+mod device;
+mod dv;
+mod events;
+mod font;
+mod guides;
+mod ioctl;
+mod latency;
+mod meta;
+mod overlay;
+mod pixfmt;
+mod scale;
+mod scopes;
+mod stats;
+mod testsrc;
+mod uvc;
 mod v4l2;
 mod uvcview;
+mod xu;
 
 pub fn main() {
     let args = os::args();
@@ -36,6 +59,59 @@ pub fn main() {
         optopt("y", "height", format!("set height (default: {})",
                                       uvcview.height).as_slice(),
                "<y>"),
+        optopt("f", "format", format!("set pixel format (default: {})",
+                                      uvcview.pixelformat).as_slice(),
+               "<fourcc>"),
+        optopt("i", "io", "set streaming i/o method (default: mmap)",
+               "<read|mmap|userptr|dmabuf>"),
+        optopt("n", "buffers", format!("set number of buffers to request (default: {})",
+                                       uvcview.buffer_count).as_slice(),
+               "<N>"),
+        optopt("", "create-bufs", "add buffers for another size with VIDIOC_CREATE_BUFS",
+               "<count>:<width>x<height>"),
+        optopt("t", "test-pattern", "use a generated test pattern instead of a device",
+               "<bars|gradient|boxes>"),
+        optopt("r", "rate", "set test pattern frame rate (default: 30)",
+               "<fps>"),
+        optopt("w", "window", "set initial window size (default: capture size)",
+               "<width>x<height>"),
+        optopt("", "scale", "set scaling mode (default: fit)", "<fit|integer>"),
+        optopt("", "filter", "set scaling filter (default: nearest)", "<nearest|bilinear>"),
+        optopt("", "scopes", "show scopes (toggle with 'h', 'w' and 'v')",
+               "<histogram,waveform,vectorscope>"),
+        optopt("", "zebra", "zebra stripes at or above luma (toggle with 'e')", "<luma>"),
+        optflag("", "false-color", "false colour exposure map (toggle with 'c')"),
+        optopt("", "peaking", "focus peaking at or above gradient (toggle with 'k')", "<level>"),
+        optopt("", "guides", "show guides (toggle with 'g', 'x' and 'a')",
+               "<thirds,crosshair,safe>"),
+        optopt("", "field", "field order",
+               "<any|none|interlaced|interlaced-tb|interlaced-bt|seq-tb|seq-bt|alternate>"),
+        optopt("", "deinterlace", "deinterlacer for interlaced video (cycle with 'd')",
+               "<weave|bob|adaptive>"),
+        optopt("", "input", "select video input (cycle with 't')", "<index>"),
+        optopt("", "std", "select TV standard (cycle with 'm', detect with 'o')",
+               "<auto|pal|pal-m|pal-n|pal-nc|pal-60|ntsc|ntsc-jp|ntsc-443|secam|secam-l>"),
+        optopt("", "frequency", "tune the tuner input (fine tune with up and down)", "<MHz>"),
+        optopt("", "dv-timings", "set HDMI/DVI/SDI input timings, detected or listed (detect with 'o')",
+               "<auto|index>"),
+        optopt("", "priority", "claim access priority on the device", "<background|interactive|record>"),
+        optflag("", "monitor", "watch a device another application owns without changing it"),
+        optopt("", "crop", "crop to a rectangle in sensor pixels (drag with the right button, reset with 'u')",
+               "<x>,<y>,<w>,<h>"),
+        optopt("", "roi", "measure a region of interest (toggle with 'r')", "<x>,<y>,<w>,<h>"),
+        optopt("", "xu-config", "name extension unit controls, one '<name> <unit> <selector>' per line",
+               "<file>"),
+        optmulti("", "xu-get", "print an extension unit control and exit", "<name|unit:selector>"),
+        optmulti("", "xu-set", "set an extension unit control to hex bytes and exit",
+                 "<name|unit:selector>=<value>"),
+        optopt("", "meta", "match UVC payload headers from a metadata node with the frames (shown on the hud)",
+               "<auto|path>"),
+        optopt("", "meta-clock", "nominal camera clock to measure drift against", "<MHz>"),
+        optflag("", "hud", "show stream information (toggle with 'i')"),
+        optflag("s", "stats", "show frame statistics (toggle with 's')"),
+        optflag("", "latency", "measure latency from buffer timestamps to dequeue, conversion and flip (shown with 's')"),
+        optflag("", "latency-flash",
+                "also flash the window for a camera pointed at it, to measure glass-to-glass latency in --roi"),
         optflag("h", "help", "show help messages"),
     ];
 
@@ -71,6 +147,155 @@ pub fn main() {
     uvcview.height = matches.opt_str("height").map_or(uvcview.height, |s| {
         from_str::<u32>(s.as_slice()).unwrap_or_else(|| { fail!("invalid option argument") })
     });
+    uvcview.pixelformat = matches.opt_str("format").map_or(uvcview.pixelformat, |s| {
+        device::Fourcc::from_str(s.as_slice()).unwrap_or_else(|| { fail!("invalid option argument") })
+    });
+    uvcview.io_method = matches.opt_str("io").map_or(uvcview.io_method, |s| {
+        uvcview::io_method_from_str(s.as_slice()).unwrap_or_else(|| { fail!("invalid option argument") })
+    });
+    uvcview.buffer_count = matches.opt_str("buffers").map_or(uvcview.buffer_count, |s| {
+        from_str::<u32>(s.as_slice()).unwrap_or_else(|| { fail!("invalid option argument") })
+    });
+    let create_bufs = matches.opt_str("create-bufs").map(|s| {
+        parse_create_bufs(s.as_slice()).unwrap_or_else(|| { fail!("invalid option argument") })
+    });
+    let window_size = matches.opt_str("window").map(|s| {
+        parse_size(s.as_slice()).unwrap_or_else(|| { fail!("invalid option argument") })
+    });
+    uvcview.scale_mode = matches.opt_str("scale").map_or(uvcview.scale_mode, |s| {
+        scale::scale_mode_from_str(s.as_slice()).unwrap_or_else(|| { fail!("invalid option argument") })
+    });
+    uvcview.filter = matches.opt_str("filter").map_or(uvcview.filter, |s| {
+        scale::filter_from_str(s.as_slice()).unwrap_or_else(|| { fail!("invalid option argument") })
+    });
+    let rate = matches.opt_str("rate").map_or(30.0, |s| {
+        match from_str::<f64>(s.as_slice()) {
+            Some(rate) if rate > 0.0 => rate,
+            _ => fail!("invalid option argument")
+        }
+    });
+    uvcview.show_stats = matches.opt_present("stats");
+    if matches.opt_present("latency") || matches.opt_present("latency-flash") {
+        uvcview.latency = Some(Latency::new(matches.opt_present("latency-flash")));
+        uvcview.show_stats = true;
+    }
+    uvcview.show_hud = matches.opt_present("hud");
+    match matches.opt_str("scopes") {
+        Some(list) => {
+            for name in list.as_slice().split(',') {
+                match name {
+                    "histogram" => uvcview.show_histogram = true,
+                    "waveform" => uvcview.show_waveform = true,
+                    "vectorscope" => uvcview.show_vectorscope = true,
+                    _ => fail!("invalid option argument")
+                }
+            }
+        }
+        None => {}
+    }
+    match matches.opt_str("guides") {
+        Some(list) => {
+            for name in list.as_slice().split(',') {
+                match name {
+                    "thirds" => uvcview.guides.thirds = true,
+                    "crosshair" => uvcview.guides.crosshair = true,
+                    "safe" => uvcview.guides.safe_areas = true,
+                    _ => fail!("invalid option argument")
+                }
+            }
+        }
+        None => {}
+    }
+    uvcview.field = matches.opt_str("field").map_or(uvcview.field, |s| {
+        device::Field::from_str(s.as_slice()).unwrap_or_else(|| { fail!("invalid option argument") })
+    });
+    uvcview.deinterlacer.method = matches.opt_str("deinterlace").map_or(uvcview.deinterlacer.method, |s| {
+        deinterlace::method_from_str(s.as_slice()).unwrap_or_else(|| { fail!("invalid option argument") })
+    });
+    uvcview.input = matches.opt_str("input").map(|s| {
+        from_str::<u32>(s.as_slice()).unwrap_or_else(|| { fail!("invalid option argument") })
+    });
+    uvcview.std = matches.opt_str("std").map(|s| {
+        if s.as_slice() == "auto" {
+            v4l2::V4L2_STD_UNKNOWN
+        } else {
+            analog::std_from_str(s.as_slice()).unwrap_or_else(|| { fail!("invalid option argument") })
+        }
+    });
+    uvcview.frequency = matches.opt_str("frequency").map(|s| {
+        from_str::<f64>(s.as_slice()).unwrap_or_else(|| { fail!("invalid option argument") })
+    });
+    uvcview.dv_request = matches.opt_str("dv-timings").map(|s| {
+        dv::request_from_str(s.as_slice()).unwrap_or_else(|| { fail!("invalid option argument") })
+    });
+    uvcview.priority = matches.opt_str("priority").map(|s| {
+        uvcview::priority_from_str(s.as_slice()).unwrap_or_else(|| { fail!("invalid option argument") })
+    });
+    uvcview.monitor = matches.opt_present("monitor");
+    uvcview.crop = matches.opt_str("crop").map(|s| {
+        parse_rect(s.as_slice()).unwrap_or_else(|| { fail!("invalid option argument") })
+    });
+    uvcview.roi = matches.opt_str("roi").map(|s| {
+        parse_rect(s.as_slice()).unwrap_or_else(|| { fail!("invalid option argument") })
+    });
+    uvcview.show_roi = uvcview.roi.is_some();
+    match matches.opt_str("zebra") {
+        Some(s) => {
+            uvcview.assist.zebra = true;
+            uvcview.assist.zebra_level = from_str::<u8>(s.as_slice()).unwrap_or_else(|| {
+                fail!("invalid option argument")
+            });
+        }
+        None => {}
+    }
+    uvcview.assist.false_color = matches.opt_present("false-color");
+    match matches.opt_str("peaking") {
+        Some(s) => {
+            uvcview.assist.peaking = true;
+            uvcview.assist.peaking_level = from_str::<u32>(s.as_slice()).unwrap_or_else(|| {
+                fail!("invalid option argument")
+            });
+        }
+        None => {}
+    }
+    match matches.opt_str("test-pattern") {
+        Some(name) => {
+            let pattern = testsrc::pattern_from_str(name.as_slice()).unwrap_or_else(|| {
+                fail!("invalid option argument")
+            });
+            uvcview.test_pattern = Some(TestPattern::new(pattern, rate));
+        }
+        _ => {}
+    }
+
+    let xu_gets = matches.opt_strs("xu-get");
+    let xu_sets = matches.opt_strs("xu-set");
+    if !xu_gets.is_empty() || !xu_sets.is_empty() {
+        let definitions = matches.opt_str("xu-config").map_or(vec!(), |path| {
+            xu::load_definitions(&Path::new(path)).unwrap_or_else(|e| { fail!("{}", e) })
+        });
+        match uvcview.open() {
+            Ok(_) => {}
+            Err(e) => fail!("{}", e)
+        }
+        run_xu_commands(&mut uvcview, definitions.as_slice(), xu_sets.as_slice(), xu_gets.as_slice());
+        return;
+    }
+
+    match matches.opt_str("meta") {
+        Some(s) => {
+            let path = match s.as_slice() {
+                "auto" => meta::find_node(&uvcview.device_path).unwrap_or_else(|e| { fail!("{}", e) }),
+                path => Path::new(path)
+            };
+            let mut node = MetaNode::open(path).unwrap_or_else(|e| { fail!("{}", e) });
+            node.clock.nominal_hz = matches.opt_str("meta-clock").map(|s| {
+                from_str::<f64>(s.as_slice()).unwrap_or_else(|| { fail!("invalid option argument") }) * 1e6
+            });
+            uvcview.meta = Some(node);
+        }
+        None => {}
+    }
 
     match uvcview.open().and_then(|uvcview| {
           uvcview.init()
@@ -85,6 +310,16 @@ pub fn main() {
         }
     }
 
+    match create_bufs {
+        Some((count, width, height)) => {
+            match uvcview.create_buffers(count, width, height) {
+                Ok(created) => info!("created {} buffers for {}x{}", created, width, height),
+                Err(e) => fail!("{}", e)
+            }
+        }
+        None => {}
+    }
+
     match sdl::init(&[sdl::InitVideo]) {
         true => {}
         false => {
@@ -92,10 +327,9 @@ pub fn main() {
         }
     }
 
-    sdl::wm::set_caption("uvcview", "uvcview");
+    sdl::wm::set_caption(format!("uvcview - {}", uvcview.card).as_slice(), "uvcview");
 
-    let width = uvcview.width as uint;
-    let height = uvcview.height as uint;
+    let (width, height) = window_size.unwrap_or((uvcview.width, uvcview.height));
 /*    let surface = match sdl::video::Surface::new(
                             &[sdl::video::HWSurface],
                             width as int, height as int, 24,
@@ -104,74 +338,362 @@ pub fn main() {
         Err(err) => fail!("Surface::new() failed. {}", err)
     };
     */
+    set_video_mode(&mut uvcview, width as int, height as int, false);
+
+    uvcview.start_capturing();
+    main_loop(&mut uvcview, width as int, height as int);
+    uvcview.stop_capturing();
+    match uvcview.latency {
+        Some(ref latency) => println!("{}", latency),
+        None => {}
+    }
+}
+
+// Applies each "<control>=<value>" of `sets`, then prints each control of
+// `gets`, without initializing the stream.
+fn run_xu_commands(uvcview: &mut UvcView, definitions: &[xu::Definition], sets: &[~str], gets: &[~str]) {
+    let resolve = |s: &str| {
+        xu::resolve(definitions, s).unwrap_or_else(|| { fail!("unknown extension unit control {}", s) })
+    };
+    for set in sets.iter() {
+        let parts: Vec<&str> = set.as_slice().splitn('=', 1).collect();
+        if parts.len() != 2 {
+            fail!("invalid option argument");
+        }
+        let (unit, selector) = resolve(*parts.get(0));
+        let value = xu::parse_value(*parts.get(1)).unwrap_or_else(|| { fail!("invalid option argument") });
+        match uvcview.xu_set(unit, selector, value.as_slice()) {
+            Ok(()) => {}
+            Err(e) => fail!("{}", e)
+        }
+    }
+    for get in gets.iter() {
+        let (unit, selector) = resolve(get.as_slice());
+        match uvcview.xu_get(unit, selector) {
+            Ok((info, value)) => println!("{} = {} ({})", get, xu::value_to_str(value.as_slice()), info),
+            Err(e) => fail!("{}", e)
+        }
+    }
+}
+
+// Parses "<width>x<height>".
+fn parse_size(s: &str) -> Option<(u32, u32)> {
+    let size: Vec<&str> = s.split('x').collect();
+    if size.len() != 2 {
+        return None;
+    }
+    match (from_str::<u32>(*size.get(0)), from_str::<u32>(*size.get(1))) {
+        (Some(width), Some(height)) => Some((width, height)),
+        _ => None
+    }
+}
+
+// Parses "<count>:<width>x<height>".
+fn parse_create_bufs(s: &str) -> Option<(u32, u32, u32)> {
+    let parts: Vec<&str> = s.split(':').collect();
+    if parts.len() != 2 {
+        return None;
+    }
+    match (from_str::<u32>(*parts.get(0)), parse_size(*parts.get(1))) {
+        (Some(count), Some((width, height))) => Some((count, width, height)),
+        _ => None
+    }
+}
+
+// Parses "<x>,<y>,<width>,<height>".
+fn parse_rect(s: &str) -> Option<Rect> {
+    let parts: Vec<&str> = s.split(',').collect();
+    if parts.len() != 4 {
+        return None;
+    }
+    let values: Vec<Option<uint>> = parts.iter().map(|v| from_str::<uint>(*v)).collect();
+    match values.as_slice() {
+        [Some(x), Some(y), Some(width), Some(height)] if width > 0 && height > 0 => {
+            Some(Rect { x: x, y: y, width: width, height: height })
+        }
+        _ => None
+    }
+}
+
+// A size of 0x0 in fullscreen mode picks the current desktop resolution.
+fn set_video_mode(uvcview: &mut UvcView, width: int, height: int, fullscreen: bool) {
+    let video_flags = if fullscreen {
+        ~[sdl::video::DoubleBuf, sdl::video::Fullscreen]
+    } else {
+        ~[sdl::video::DoubleBuf, sdl::video::Resizable]
+    };
     let surface = match sdl::video::set_video_mode(
-            width as int, height as int, 24,
-            [sdl::video::HWSurface], [sdl::video::DoubleBuf]) {
+            width, height, 24,
+            [sdl::video::HWSurface], video_flags.as_slice()) {
         Ok(surface) => surface,
         Err(err) => fail!("sdl::video::set_video_mode() failed! {}", err)
     };
     uvcview.set_surface(surface);
+}
 
-    uvcview.start_capturing();
-    main_loop(&mut uvcview);
-    uvcview.stop_capturing();
+// State of the SDL window the viewer draws into.
+struct Window {
+    // Size to return to when leaving fullscreen.
+    size: (int, int),
+    fullscreen: bool,
 }
 
-fn main_loop(uvcview: &mut UvcView) {
+// Handles all pending SDL events. Returns false once the window is closed.
+fn handle_events(uvcview: &mut UvcView, window: &mut Window) -> bool {
     loop {
         match sdl::event::poll_event() {
+            sdl::event::NoEvent => {
+                return true;
+            }
             sdl::event::QuitEvent => {
-                return;
+                return false;
+            }
+            sdl::event::ResizeEvent(width, height) => {
+                window.size = (width as int, height as int);
+                set_video_mode(uvcview, width as int, height as int, false);
+            }
+            sdl::event::KeyEvent(sdl::event::FKey, true, _, _) => {
+                window.fullscreen = !window.fullscreen;
+                let (width, height) = if window.fullscreen { (0, 0) } else { window.size };
+                set_video_mode(uvcview, width, height, window.fullscreen);
+            }
+            sdl::event::KeyEvent(sdl::event::BKey, true, _, _) => {
+                uvcview.filter = match uvcview.filter {
+                    scale::Nearest => scale::Bilinear,
+                    scale::Bilinear => scale::Nearest,
+                };
+            }
+            sdl::event::KeyEvent(sdl::event::NKey, true, _, _) => {
+                uvcview.scale_mode = match uvcview.scale_mode {
+                    scale::Fit => scale::Integer,
+                    scale::Integer => scale::Fit,
+                };
+            }
+            sdl::event::KeyEvent(sdl::event::DKey, true, _, _) => {
+                uvcview.deinterlacer.method = match uvcview.deinterlacer.method {
+                    deinterlace::Weave => deinterlace::Bob,
+                    deinterlace::Bob => deinterlace::Adaptive,
+                    deinterlace::Adaptive => deinterlace::Weave,
+                };
+            }
+            sdl::event::KeyEvent(sdl::event::SKey, true, _, _) => {
+                uvcview.show_stats = !uvcview.show_stats;
+            }
+            sdl::event::KeyEvent(sdl::event::IKey, true, _, _) => {
+                uvcview.show_hud = !uvcview.show_hud;
+            }
+            sdl::event::KeyEvent(sdl::event::GKey, true, _, _) => {
+                uvcview.guides.thirds = !uvcview.guides.thirds;
+            }
+            sdl::event::KeyEvent(sdl::event::XKey, true, _, _) => {
+                uvcview.guides.crosshair = !uvcview.guides.crosshair;
+            }
+            sdl::event::KeyEvent(sdl::event::AKey, true, _, _) => {
+                uvcview.guides.safe_areas = !uvcview.guides.safe_areas;
+            }
+            sdl::event::KeyEvent(sdl::event::RKey, true, _, _) => {
+                uvcview.show_roi = !uvcview.show_roi && uvcview.roi.is_some();
+            }
+            sdl::event::KeyEvent(sdl::event::EKey, true, _, _) => {
+                uvcview.assist.zebra = !uvcview.assist.zebra;
+            }
+            sdl::event::KeyEvent(sdl::event::CKey, true, _, _) => {
+                uvcview.assist.false_color = !uvcview.assist.false_color;
+            }
+            sdl::event::KeyEvent(sdl::event::KKey, true, _, _) => {
+                uvcview.assist.peaking = !uvcview.assist.peaking;
+            }
+            sdl::event::KeyEvent(sdl::event::HKey, true, _, _) => {
+                uvcview.show_histogram = !uvcview.show_histogram;
+            }
+            sdl::event::KeyEvent(sdl::event::WKey, true, _, _) => {
+                uvcview.show_waveform = !uvcview.show_waveform;
+            }
+            sdl::event::KeyEvent(sdl::event::VKey, true, _, _) => {
+                uvcview.show_vectorscope = !uvcview.show_vectorscope;
+            }
+            sdl::event::KeyEvent(sdl::event::PKey, true, _, _) => {
+                uvcview.show_inspector = !uvcview.show_inspector;
+            }
+            sdl::event::KeyEvent(sdl::event::ZKey, true, _, _) => {
+                uvcview.reset_zoom();
+            }
+            sdl::event::KeyEvent(sdl::event::UKey, true, _, _) => {
+                match uvcview.reset_crop() {
+                    Ok(()) => {}
+                    Err(e) => error!("{}", e)
+                }
+            }
+            sdl::event::KeyEvent(sdl::event::TKey, true, _, _) => {
+                let count = uvcview.enum_inputs().len() as u32;
+                if count > 1 {
+                    let next = (uvcview.input.unwrap_or(0) + 1) % count;
+                    match uvcview.set_input(next) {
+                        Ok(()) => {}
+                        Err(e) => error!("{}", e)
+                    }
+                }
+            }
+            sdl::event::KeyEvent(sdl::event::MKey, true, _, _) => {
+                let standards = uvcview.enum_standards();
+                if standards.len() > 0 {
+                    let current = uvcview.std.and_then(|id| {
+                        standards.iter().position(|standard| standard.id == id)
+                    });
+                    let next = match current {
+                        Some(i) => (i + 1) % standards.len(),
+                        None => 0
+                    };
+                    match uvcview.set_std(standards.get(next).id) {
+                        Ok(()) => {}
+                        Err(e) => error!("{}", e)
+                    }
+                }
+            }
+            sdl::event::KeyEvent(sdl::event::OKey, true, _, _) => {
+                let result = if uvcview.dv_timings.is_some() {
+                    uvcview.detect_dv_timings().map(|_| ())
+                } else {
+                    uvcview.detect_std().map(|_| ())
+                };
+                match result {
+                    Ok(()) => {}
+                    Err(e) => error!("{}", e)
+                }
+            }
+            sdl::event::KeyEvent(sdl::event::UpKey, true, _, _) => {
+                fine_tune(uvcview, FINE_TUNE_STEP);
+            }
+            sdl::event::KeyEvent(sdl::event::DownKey, true, _, _) => {
+                fine_tune(uvcview, -FINE_TUNE_STEP);
+            }
+            sdl::event::MouseButtonEvent(sdl::event::RightMouse, true, x, y) => {
+                uvcview.begin_selection(x as uint, y as uint);
+            }
+            sdl::event::MouseButtonEvent(sdl::event::RightMouse, false, _, _) => {
+                match uvcview.end_selection() {
+                    Ok(()) => {}
+                    Err(e) => error!("{}", e)
+                }
+            }
+            sdl::event::MouseButtonEvent(sdl::event::WheelUpMouse, true, x, y) => {
+                uvcview.zoom_at(x as uint, y as uint, 1.25);
+            }
+            sdl::event::MouseButtonEvent(sdl::event::WheelDownMouse, true, x, y) => {
+                uvcview.zoom_at(x as uint, y as uint, 0.8);
+            }
+            sdl::event::MouseMotionEvent(state, x, y, dx, dy) => {
+                uvcview.cursor = Some((x as uint, y as uint));
+                if state.contains(&sdl::event::LeftMouseState) {
+                    uvcview.pan_by(dx as int, dy as int);
+                }
+                if state.contains(&sdl::event::RightMouseState) {
+                    uvcview.update_selection(x as uint, y as uint);
+                }
             }
             _ => {
             }
         }
-        loop {
-            let mut set: FdSet = unsafe { mem::zeroed() };
-            let mut tv = libc::timeval { tv_sec: 2, tv_usec: 0 };
+    }
+}
 
-            FdSet(&mut set, uvcview.fd);
+// Frequency step in MHz of the up and down keys.
+static FINE_TUNE_STEP: f64 = 0.25;
 
-            let result = unsafe {
-                select(uvcview.fd + 1, transmute(&mut set),
-                       null(), null(), transmute(&mut tv))
-            };
+fn fine_tune(uvcview: &mut UvcView, step: f64) {
+    match uvcview.frequency {
+        Some(mhz) => {
+            match uvcview.set_frequency(mhz + step) {
+                Ok(()) => {}
+                Err(e) => error!("{}", e)
+            }
+        }
+        None => {}
+    }
+}
+
+fn main_loop(uvcview: &mut UvcView, width: int, height: int) {
+    let mut window = Window {
+        size: (width, height),
+        fullscreen: false,
+    };
+
+    loop {
+        if !handle_events(uvcview, &mut window) {
+            return;
+        }
+        if uvcview.test_pattern.is_some() {
+            uvcview.read_frame();
+            continue;
+        }
+        loop {
+            // Pending V4L2 events raise POLLPRI.
+            let mut fds = [PollFd { fd: uvcview.device.fd, events: POLLIN | POLLPRI, revents: 0 }];
+            let result = unsafe { poll(fds.as_mut_ptr(), 1, 2000) };
             match result {
                 -1 => {
                     if os::errno() == EINTR as int {
                         continue
                     }
-                    fail!("select() failed");
+                    fail!("poll() failed");
                 }
                 0 => {
-                    fail!("select() timeout");
+                    // A receiver sends nothing while its source is unplugged;
+                    // a source change event tells when it is back.
+                    if uvcview.dv_timings.is_some() {
+                        break;
+                    }
+                    fail!("poll() timeout");
                 }
                 _ => {
-                    if uvcview.read_frame() {
+                    if (fds[0].revents & POLLPRI) != 0 {
+                        let mut ended = false;
+                        match uvcview.dequeue_events(|_, event| {
+                            match *event {
+                                events::EndOfStream => ended = true,
+                                events::Control(ref change) => debug!("control {:x} changed: {}", change.id, change.value),
+                                _ => {}
+                            }
+                        }) {
+                            Ok(()) => {}
+                            Err(e) => error!("{}", e)
+                        }
+                        if ended {
+                            info!("end of stream");
+                            return;
+                        }
+                    }
+                    if (fds[0].revents & (POLLERR | POLLHUP | POLLNVAL)) != 0 {
+                        // VIDIOC_DQBUF tells what went wrong, an unplugged
+                        // camera say; the next poll() would return at once.
+                        if (fds[0].revents & POLLNVAL) == 0 && uvcview.read_frame() {
+                            break;
+                        }
+                        fail!("poll() error 0x{:x}", fds[0].revents);
+                    }
+                    if (fds[0].revents & POLLIN) != 0 && uvcview.read_frame() {
                         break;
                     }
 
-                    // EAGAIN - continue select loop
+                    // EAGAIN or only events - continue poll loop
                 }
             }
         }
     }
 }
 
-pub static FD_SETSIZE: uint = 1024;
-
-pub struct FdSet {
-    fds_bits: [u64, ..(FD_SETSIZE / 64)]
-}
+static POLLIN: libc::c_short = 0x1;
+static POLLPRI: libc::c_short = 0x2;
+static POLLERR: libc::c_short = 0x8;
+static POLLHUP: libc::c_short = 0x10;
+static POLLNVAL: libc::c_short = 0x20;
 
-pub fn FdSet(set: &mut FdSet, fd: i32) {
-    set.fds_bits[(fd / 64) as uint] |= (1 << (fd % 64)) as u64;
+pub struct PollFd {
+    fd: libc::c_int,
+    events: libc::c_short,
+    revents: libc::c_short,
 }
 
 extern {
-    pub fn select(nfds: libc::c_int,
-                  readfds: *FdSet,
-                  writefds: *FdSet,
-                  errorfds: *FdSet,
-                  timeout: *libc::timeval) -> libc::c_int;
+    pub fn poll(fds: *mut PollFd, nfds: libc::c_ulong, timeout: libc::c_int) -> libc::c_int;
 }
@@ -0,0 +1,483 @@
+// The metadata node of a UVC camera.
+//
+// uvcvideo gives each camera a second /dev/videoN which streams, alongside
+// the frames of the capture node, the UVC payload headers they came in
+// with as V4L2_META_FMT_UVC. A header may carry the camera clock at the
+// start of the frame (PTS) and a source clock reference (SCR) pairing the
+// camera clock with the USB frame counter. Together with the host time
+// each header arrived at, they tell how long the camera took to send a
+// frame and how fast its clock runs against the host's.
+//
+// The driver completes the metadata buffer of a frame along with the
+// frame's own buffer and gives both the same sequence number, which is
+// how the two are matched up.
+
+use libc::c_int;
+use libc::consts::os::posix88::EAGAIN;
+use std::cmp::min;
+use std::io::{IoResult,IoError};
+use std::io;
+use std::io::fs;
+use std::os::MemoryMap;
+use std::os::error_string;
+
+use device;
+use device::Device;
+use uvc;
+use v4l2;
+
+// sizeof(struct uvc_meta_buf), the part of a block before the header
+// fields that bHeaderLength and bmHeaderInfo announce.
+static BLOCK_SIZE: uint = 12;
+
+// Frames whose metadata is held until the frame is dequeued.
+static PENDING_FRAMES: uint = 8;
+
+// One payload header, and when it arrived.
+#[deriving(Clone, Eq, Show)]
+pub struct Block {
+    // Host CLOCK_MONOTONIC time and USB frame number on arrival.
+    pub ns: u64,
+    pub sof: u16,
+    // UVC_STREAM_* flags.
+    pub flags: u8,
+    // Camera clock when the exposure of the frame began.
+    pub pts: Option<u32>,
+    // Camera clock, and the 11 bit USB frame number, when the camera put
+    // the first data of the frame on the bus.
+    pub scr: Option<(u32, u16)>,
+    // Header bytes after PTS and SCR, defined by the vendor.
+    pub vendor: Vec<u8>,
+}
+
+fn le16(data: &[u8]) -> u16 {
+    data[0] as u16 | data[1] as u16 << 8
+}
+
+fn le32(data: &[u8]) -> u32 {
+    le16(data) as u32 | le16(data.slice_from(2)) as u32 << 16
+}
+
+fn le64(data: &[u8]) -> u64 {
+    le32(data) as u64 | le32(data.slice_from(4)) as u64 << 32
+}
+
+// Splits a V4L2_META_FMT_UVC buffer into its blocks. A block that is cut
+// short ends the list.
+pub fn parse(data: &[u8]) -> Vec<Block> {
+    let mut blocks = vec!();
+    let mut rest = data;
+    while rest.len() >= BLOCK_SIZE {
+        // bHeaderLength counts itself and bmHeaderInfo, which the block
+        // holds in its length and flags.
+        let length = rest[10] as uint;
+        let end = BLOCK_SIZE - 2 + length;
+        if length < 2 || rest.len() < end {
+            break;
+        }
+        let flags = rest[11];
+        let mut header = rest.slice(BLOCK_SIZE, end);
+        let pts = if (flags & uvc::UVC_STREAM_PTS) != 0 && header.len() >= 4 {
+            let pts = le32(header);
+            header = header.slice_from(4);
+            Some(pts)
+        } else {
+            None
+        };
+        let scr = if (flags & uvc::UVC_STREAM_SCR) != 0 && header.len() >= 6 {
+            let scr = (le32(header), le16(header.slice_from(4)) & 0x7ff);
+            header = header.slice_from(6);
+            Some(scr)
+        } else {
+            None
+        };
+        blocks.push(Block {
+            ns: le64(rest),
+            sof: le16(rest.slice_from(8)),
+            flags: flags,
+            pts: pts,
+            scr: scr,
+            vendor: Vec::from_slice(header),
+        });
+        rest = rest.slice_from(end);
+    }
+    blocks
+}
+
+// What the headers of one frame tell.
+#[deriving(Clone, Eq, Show)]
+pub struct FrameMeta {
+    pub sequence: u32,
+    pub pts: Option<u32>,
+    // The first SCR of the frame, and the host time and USB frame number
+    // its header arrived at; the first header's if none has an SCR.
+    pub scr: Option<(u32, u16)>,
+    pub ns: u64,
+    pub sof: u16,
+    // How many headers the frame came with, and whether any flagged an
+    // error.
+    pub headers: uint,
+    pub error: bool,
+}
+
+impl FrameMeta {
+    pub fn from_blocks(sequence: u32, blocks: &[Block]) -> Option<FrameMeta> {
+        let first = match blocks.head() {
+            Some(first) => first,
+            None => return None
+        };
+        let timed = blocks.iter().find(|block| block.scr.is_some()).unwrap_or(first);
+        Some(FrameMeta {
+            sequence: sequence,
+            pts: blocks.iter().filter_map(|block| block.pts).next(),
+            scr: timed.scr,
+            ns: timed.ns,
+            sof: timed.sof,
+            headers: blocks.len(),
+            error: blocks.iter().any(|block| (block.flags & uvc::UVC_STREAM_ERR) != 0),
+        })
+    }
+}
+
+// The camera clock as the SCRs show it against host time.
+pub struct Clock {
+    // Host time of the first SCR, and of the latest with its clock.
+    first_ns: Option<u64>,
+    last: Option<(u64, u32)>,
+    // Camera clock ticks from the first SCR to the latest.
+    ticks: u64,
+    // dwClockFrequency of the camera in Hz, if known.
+    pub nominal_hz: Option<f64>,
+}
+
+impl Clock {
+    pub fn new() -> Clock {
+        Clock {
+            first_ns: None,
+            last: None,
+            ticks: 0,
+            nominal_hz: None,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.first_ns = None;
+        self.last = None;
+        self.ticks = 0;
+    }
+
+    // Adds an SCR clock of `stc` that arrived at host time `ns`.
+    pub fn update(&mut self, ns: u64, stc: u32) {
+        match self.last {
+            // Headers repeat the SCR of the frame; only the first arrival
+            // of each is close to when it was sampled.
+            Some((_, last_stc)) if stc == last_stc => return,
+            Some((last_ns, _)) if ns <= last_ns => return,
+            // The 32 bit clock wraps, every 90 s at 48 MHz.
+            Some((_, last_stc)) => self.ticks += (stc - last_stc) as u64,
+            None => self.first_ns = Some(ns)
+        }
+        self.last = Some((ns, stc));
+    }
+
+    // Camera clock frequency in Hz, once a second of SCRs came in.
+    pub fn frequency(&self) -> Option<f64> {
+        match (self.first_ns, self.last) {
+            (Some(first_ns), Some((last_ns, _))) if last_ns - first_ns >= 1000000000 => {
+                Some(self.ticks as f64 * 1e9 / (last_ns - first_ns) as f64)
+            }
+            _ => None
+        }
+    }
+
+    // How far the camera clock runs from its nominal frequency, in parts
+    // per million of host time.
+    pub fn drift_ppm(&self) -> Option<f64> {
+        match (self.frequency(), self.nominal_hz) {
+            (Some(frequency), Some(nominal)) => Some((frequency / nominal - 1.0) * 1e6),
+            _ => None
+        }
+    }
+
+    // Time from the start of the exposure of a frame to the camera
+    // sending it, at the nominal frequency if known.
+    pub fn transit_ns(&self, meta: &FrameMeta) -> Option<f64> {
+        let frequency = match self.nominal_hz.or(self.frequency()) {
+            Some(frequency) => frequency,
+            None => return None
+        };
+        match (meta.pts, meta.scr) {
+            (Some(pts), Some((stc, _))) => Some((stc - pts) as f64 * 1e9 / frequency),
+            _ => None
+        }
+    }
+}
+
+pub struct MetaNode {
+    pub path: Path,
+    pub device: Device,
+    // Size of each buffer, from the driver.
+    pub buffersize: u32,
+    buffers: Vec<MemoryMap>,
+    // Headers dequeued ahead of their frames, by sequence.
+    pending: Vec<(u32, Vec<Block>)>,
+    pub clock: Clock,
+    // What the headers of the latest frame told.
+    pub last: Option<FrameMeta>,
+}
+
+impl MetaNode {
+    pub fn new(path: Path, device: Device) -> MetaNode {
+        MetaNode {
+            path: path,
+            device: device,
+            buffersize: 0,
+            buffers: vec!(),
+            pending: vec!(),
+            clock: Clock::new(),
+            last: None,
+        }
+    }
+
+    pub fn open(path: Path) -> IoResult<MetaNode> {
+        match Device::open(&path) {
+            Ok(device) => Ok(MetaNode::new(path, device)),
+            Err(errno) => {
+                Err(IoError {
+                    kind: io::OtherIoError,
+                    desc: "MetaNode::open(): open() failed",
+                    detail: Some(format!("{}: {}", path.display(), error_string(errno as uint)))
+                })
+            }
+        }
+    }
+
+    fn error(&self, desc: &'static str, errno: c_int) -> IoError {
+        IoError {
+            kind: io::OtherIoError,
+            desc: desc,
+            detail: Some(format!("{}: {}", self.path.display(), error_string(errno as uint)))
+        }
+    }
+
+    // Checks the node streams UVC payload headers, and maps `count`
+    // buffers for them. A `monitor` takes the format the owner of the
+    // device set.
+    pub fn init(&mut self, count: u32, monitor: bool) -> IoResult<()> {
+        match self.device.query_cap() {
+            Ok(cap) => {
+                if !cap.node_has(v4l2::V4L2_CAP_META_CAPTURE) || !cap.has(v4l2::V4L2_CAP_STREAMING) {
+                    return Err(IoError {
+                        kind: io::OtherIoError,
+                        desc: "MetaNode::init(): V4L2_CAP_META_CAPTURE not supported",
+                        detail: Some(format!("{} is no metadata node", self.path.display()))
+                    });
+                }
+            }
+            Err(errno) => return Err(self.error("MetaNode::init(): VIDIOC_QUERYCAP failed", errno))
+        }
+
+        let (result, desc) = if monitor {
+            (self.device.get_meta_format(), "MetaNode::init(): VIDIOC_G_FMT failed")
+        } else {
+            (self.device.set_meta_format(v4l2::V4L2_META_FMT_UVC), "MetaNode::init(): VIDIOC_S_FMT failed")
+        };
+        let format = match result {
+            Ok(format) => format,
+            Err(errno) => return Err(self.error(desc, errno))
+        };
+        if format.dataformat.code() != v4l2::V4L2_META_FMT_UVC {
+            return Err(IoError {
+                kind: io::OtherIoError,
+                desc: "MetaNode::init(): unsupported metadata format",
+                detail: Some(format!("{} selected {}", self.path.display(), format.dataformat))
+            });
+        }
+        self.buffersize = format.buffersize;
+
+        let granted = match self.device.req_bufs(device::MetaCapture, device::Mmap, count) {
+            Ok(granted) => granted,
+            Err(errno) => return Err(self.error("MetaNode::init(): VIDIOC_REQBUFS failed", errno))
+        };
+        for index in range(0, granted) {
+            let buffer = match self.device.query_buf(device::MetaCapture, device::Mmap, index, 1) {
+                Ok(buffer) => buffer,
+                Err(errno) => return Err(self.error("MetaNode::init(): VIDIOC_QUERYBUF failed", errno))
+            };
+            let plane = buffer.planes.get(0);
+            let offset = match plane.location {
+                device::MmapOffset(offset) => offset,
+                _ => 0
+            };
+            match self.device.mmap(plane.length as uint, offset) {
+                Ok(map) => self.buffers.push(map),
+                Err(e) => {
+                    return Err(IoError {
+                        kind: io::OtherIoError,
+                        desc: "MetaNode::init(): mmap() failed",
+                        detail: Some(format!("{}: {}", self.path.display(), e))
+                    });
+                }
+            }
+        }
+        info!("metadata from {}, {} buffers of {} bytes", self.path.display(), granted,
+              self.buffersize);
+        Ok(())
+    }
+
+    fn queue(&mut self, index: u32) -> Result<(), c_int> {
+        self.device.qbuf(&device::Buffer::new(device::MetaCapture, device::Mmap, index, 1))
+    }
+
+    pub fn start(&mut self) -> IoResult<()> {
+        for index in range(0, self.buffers.len() as u32) {
+            match self.queue(index) {
+                Ok(()) => {}
+                Err(errno) => return Err(self.error("MetaNode::start(): VIDIOC_QBUF failed", errno))
+            }
+        }
+        match self.device.stream_on(device::MetaCapture) {
+            Ok(()) => Ok(()),
+            Err(errno) => Err(self.error("MetaNode::start(): VIDIOC_STREAMON failed", errno))
+        }
+    }
+
+    pub fn stop(&mut self) -> IoResult<()> {
+        self.pending.clear();
+        match self.device.stream_off(device::MetaCapture) {
+            Ok(()) => Ok(()),
+            Err(errno) => Err(self.error("MetaNode::stop(): VIDIOC_STREAMOFF failed", errno))
+        }
+    }
+
+    // Dequeues the headers of every frame completed so far.
+    pub fn dequeue(&mut self) -> IoResult<()> {
+        loop {
+            let buffer = match self.device.dqbuf(device::MetaCapture, device::Mmap, 1) {
+                Ok(buffer) => buffer,
+                Err(EAGAIN) => return Ok(()),
+                Err(errno) => return Err(self.error("MetaNode::dequeue(): VIDIOC_DQBUF failed", errno))
+            };
+            if buffer.index as uint >= self.buffers.len() {
+                return Err(IoError {
+                    kind: io::OtherIoError,
+                    desc: "MetaNode::dequeue(): VIDIOC_DQBUF failed",
+                    detail: Some(format!("{} returned buffer {} of {}", self.path.display(),
+                                         buffer.index, self.buffers.len()))
+                });
+            }
+
+            let blocks = {
+                let data = device::mapped(self.buffers.get(buffer.index as uint));
+                parse(data.slice_to(min(buffer.planes.get(0).bytesused as uint, data.len())))
+            };
+            for block in blocks.iter() {
+                match block.scr {
+                    Some((stc, _)) => self.clock.update(block.ns, stc),
+                    None => {}
+                }
+            }
+            self.pending.push((buffer.sequence, blocks));
+            if self.pending.len() > PENDING_FRAMES {
+                self.pending.shift();
+            }
+
+            match self.queue(buffer.index) {
+                Ok(()) => {}
+                Err(errno) => return Err(self.error("MetaNode::dequeue(): VIDIOC_QBUF failed", errno))
+            }
+        }
+    }
+
+    // The headers of frame `sequence`. Those of older frames are dropped,
+    // their frames are not coming any more.
+    pub fn take(&mut self, sequence: u32) -> Option<FrameMeta> {
+        let meta = match self.pending.iter().position(|&(pending, _)| pending == sequence) {
+            Some(i) => {
+                let (_, blocks) = self.pending.remove(i).unwrap();
+                FrameMeta::from_blocks(sequence, blocks.as_slice())
+            }
+            None => None
+        };
+        // Sequence numbers wrap like the clock.
+        self.pending.retain(|&(pending, _)| ((pending - sequence) as i32) > 0);
+        if meta.is_some() {
+            self.last = meta.clone();
+        }
+        meta
+    }
+
+    pub fn hud_lines(&self) -> Vec<~str> {
+        let mut lines = vec!();
+        match self.last {
+            Some(ref last) => {
+                let pts = last.pts.map_or("-".to_owned(), |pts| pts.to_str());
+                let scr = last.scr.map_or("-".to_owned(), |(stc, sof)| format!("{}/{}", stc, sof));
+                lines.push(format!("PTS {} SCR {}", pts, scr));
+                match self.clock.transit_ns(last) {
+                    Some(ns) => lines.push(format!("CAMERA DELAY {:.2} MS", ns / 1e6)),
+                    None => {}
+                }
+            }
+            None => lines.push("NO METADATA".to_owned())
+        }
+        match (self.clock.frequency(), self.clock.drift_ppm()) {
+            (Some(frequency), Some(ppm)) => {
+                lines.push(format!("CLOCK {:.4} MHZ {:.1} PPM", frequency / 1e6, ppm));
+            }
+            (Some(frequency), None) => lines.push(format!("CLOCK {:.4} MHZ", frequency / 1e6)),
+            _ => {}
+        }
+        lines
+    }
+}
+
+impl Drop for MetaNode {
+    fn drop(&mut self) {
+        // Unmapping comes first, drivers refuse to free mapped buffers.
+        self.buffers.clear();
+        self.device.close();
+    }
+}
+
+// The metadata node of the camera whose capture node is `capture`: the
+// other node with the same bus_info that carries metadata.
+pub fn find_node(capture: &Path) -> IoResult<Path> {
+    let query_cap = |path: &Path| {
+        Device::open(path).and_then(|mut device| {
+            let cap = device.query_cap();
+            device.close();
+            cap
+        })
+    };
+    let bus_info = match query_cap(capture) {
+        Ok(cap) => cap.bus_info,
+        Err(errno) => {
+            return Err(IoError {
+                kind: io::OtherIoError,
+                desc: "find_node(): VIDIOC_QUERYCAP failed",
+                detail: Some(format!("{}: {}", capture.display(), error_string(errno as uint)))
+            });
+        }
+    };
+
+    let mut nodes = try!(fs::readdir(&Path::new("/dev")));
+    nodes.sort_by(|a, b| a.as_vec().cmp(&b.as_vec()));
+    for node in nodes.iter() {
+        match node.filename_str() {
+            Some(name) if name.starts_with("video") && node != capture => {}
+            _ => continue
+        }
+        match query_cap(node) {
+            Ok(cap) if cap.bus_info == bus_info && cap.node_has(v4l2::V4L2_CAP_META_CAPTURE) => {
+                return Ok(node.clone());
+            }
+            _ => {}
+        }
+    }
+    Err(IoError {
+        kind: io::FileNotFound,
+        desc: "find_node(): no metadata node",
+        detail: Some(format!("no node carries the metadata of {} ({})", capture.display(), bus_info))
+    })
+}
@@ -0,0 +1,102 @@
+// Drawing on top of the converted image in the SDL surface.
+//
+// The surface is 24 bits per pixel in B, G, R byte order; all colours here
+// are given as (r, g, b).
+
+use font;
+
+pub static WHITE: (u8, u8, u8) = (255, 255, 255);
+pub static BLACK: (u8, u8, u8) = (0, 0, 0);
+
+pub struct Canvas<'a> {
+    pixels: &'a mut [u8],
+    pitch: uint,
+    pub width: uint,
+    pub height: uint,
+}
+
+impl<'a> Canvas<'a> {
+    pub fn new(pixels: &'a mut [u8], pitch: uint, width: uint, height: uint) -> Canvas<'a> {
+        Canvas {
+            pixels: pixels,
+            pitch: pitch,
+            width: width,
+            height: height,
+        }
+    }
+
+    pub fn put(&mut self, x: uint, y: uint, color: (u8, u8, u8)) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let (r, g, b) = color;
+        let offset = y * self.pitch + x * 3;
+        self.pixels[offset] = b;
+        self.pixels[offset + 1] = g;
+        self.pixels[offset + 2] = r;
+    }
+
+    pub fn get(&self, x: uint, y: uint) -> (u8, u8, u8) {
+        let offset = y * self.pitch + x * 3;
+        (self.pixels[offset + 2], self.pixels[offset + 1], self.pixels[offset])
+    }
+
+    pub fn fill_rect(&mut self, x0: uint, y0: uint, w: uint, h: uint, color: (u8, u8, u8)) {
+        for y in range(y0, y0 + h) {
+            for x in range(x0, x0 + w) {
+                self.put(x, y, color);
+            }
+        }
+    }
+
+    // Outlines a rectangle one pixel wide, inside (x0, y0, w, h).
+    pub fn draw_rect(&mut self, x0: uint, y0: uint, w: uint, h: uint, color: (u8, u8, u8)) {
+        if w == 0 || h == 0 {
+            return;
+        }
+        self.fill_rect(x0, y0, w, 1, color);
+        self.fill_rect(x0, y0 + h - 1, w, 1, color);
+        self.fill_rect(x0, y0, 1, h, color);
+        self.fill_rect(x0 + w - 1, y0, 1, h, color);
+    }
+
+    // Darkens a rectangle so scopes and text drawn over it stay readable.
+    pub fn shade_rect(&mut self, x0: uint, y0: uint, w: uint, h: uint) {
+        for y in range(y0, y0 + h) {
+            for x in range(x0, x0 + w) {
+                if x < self.width && y < self.height {
+                    let (r, g, b) = self.get(x, y);
+                    self.put(x, y, (r / 4, g / 4, b / 4));
+                }
+            }
+        }
+    }
+
+    pub fn draw_text(&mut self, text: &str, x: uint, y: uint, scale: uint, color: (u8, u8, u8)) {
+        font::draw_text(text, x, y, scale, |px, py| {
+            self.put(px, py, color);
+        });
+    }
+
+    // Draws `lines` in white on a black box with its top left corner at
+    // (x, y).
+    pub fn text_box(&mut self, lines: &[~str], x: uint, y: uint, scale: uint) {
+        let (w, h) = text_box_size(lines, scale);
+        let margin = 2 * scale;
+        let line_height = font::text_height(scale) + margin;
+        self.fill_rect(x, y, w, h, BLACK);
+        for (i, line) in lines.iter().enumerate() {
+            self.draw_text(line.as_slice(), x + margin, y + margin + i * line_height, scale, WHITE);
+        }
+    }
+}
+
+pub fn text_box_size(lines: &[~str], scale: uint) -> (uint, uint) {
+    let margin = 2 * scale;
+    let line_height = font::text_height(scale) + margin;
+    let width = lines.iter().fold(0, |w, line| {
+        let line_width = font::text_width(line.as_slice(), scale);
+        if line_width > w { line_width } else { w }
+    });
+    (width + 2 * margin, lines.len() * line_height + margin)
+}
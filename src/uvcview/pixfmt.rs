@@ -0,0 +1,378 @@
+use std::cmp;
+use std::str;
+use v4l2;
+
+pub struct Plane<'a> {
+    pub data: &'a [u8],
+    pub bytesperline: uint,
+}
+
+// A captured image with its planes resolved, independent of where the
+// memory came from (mmap buffer, test pattern, ...).
+pub struct Frame<'a> {
+    pub fourcc: u32,
+    pub width: uint,
+    pub height: uint,
+    pub planes: Vec<Plane<'a>>,
+}
+
+pub fn fourcc_to_str(fourcc: u32) -> ~str {
+    let bytes = [(fourcc & 0xff) as u8,
+                 ((fourcc >> 8) & 0xff) as u8,
+                 ((fourcc >> 16) & 0xff) as u8,
+                 ((fourcc >> 24) & 0xff) as u8];
+    match str::from_utf8(bytes) {
+        Some(s) => s.to_owned(),
+        None => format!("0x{:08x}", fourcc)
+    }
+}
+
+// Accepts either a literal fourcc ("YUYV", "NV12") or one of the common
+// format names ("YUV420", "RGB24").
+pub fn fourcc_from_str(s: &str) -> Option<u32> {
+    match s.to_ascii_upper().as_slice() {
+        "YUV420" | "I420" => return Some(v4l2::V4L2_PIX_FMT_YUV420),
+        "YVU420" => return Some(v4l2::V4L2_PIX_FMT_YVU420),
+        "RGB24" => return Some(v4l2::V4L2_PIX_FMT_RGB24),
+        "BGR24" => return Some(v4l2::V4L2_PIX_FMT_BGR24),
+        _ => {}
+    }
+
+    let bytes = s.as_bytes();
+    if bytes.len() == 0 || bytes.len() > 4 {
+        return None;
+    }
+    let mut fourcc = 0u32;
+    for i in range(0u, 4) {
+        let b = if i < bytes.len() { bytes[i] } else { ' ' as u8 };
+        fourcc |= (b as u32) << (i * 8);
+    }
+    Some(fourcc)
+}
+
+pub fn is_supported(fourcc: u32) -> bool {
+    match fourcc {
+        v4l2::V4L2_PIX_FMT_YUYV | v4l2::V4L2_PIX_FMT_YVYU |
+        v4l2::V4L2_PIX_FMT_UYVY | v4l2::V4L2_PIX_FMT_VYUY |
+        v4l2::V4L2_PIX_FMT_GREY |
+        v4l2::V4L2_PIX_FMT_RGB24 | v4l2::V4L2_PIX_FMT_BGR24 |
+        v4l2::V4L2_PIX_FMT_NV12 | v4l2::V4L2_PIX_FMT_NV21 |
+        v4l2::V4L2_PIX_FMT_YUV420 | v4l2::V4L2_PIX_FMT_YVU420 |
+        v4l2::V4L2_PIX_FMT_NV12M | v4l2::V4L2_PIX_FMT_NV21M |
+        v4l2::V4L2_PIX_FMT_YUV420M | v4l2::V4L2_PIX_FMT_YVU420M => true,
+        _ => false
+    }
+}
+
+// The single-buffer equivalent of a multi-planar fourcc; the sample layout
+// within each plane is the same.
+pub fn contiguous_fourcc(fourcc: u32) -> u32 {
+    match fourcc {
+        v4l2::V4L2_PIX_FMT_NV12M => v4l2::V4L2_PIX_FMT_NV12,
+        v4l2::V4L2_PIX_FMT_NV21M => v4l2::V4L2_PIX_FMT_NV21,
+        v4l2::V4L2_PIX_FMT_YUV420M => v4l2::V4L2_PIX_FMT_YUV420,
+        v4l2::V4L2_PIX_FMT_YVU420M => v4l2::V4L2_PIX_FMT_YVU420,
+        _ => fourcc
+    }
+}
+
+// Number of separately allocated memory planes.
+pub fn num_planes(fourcc: u32) -> uint {
+    match fourcc {
+        v4l2::V4L2_PIX_FMT_NV12M | v4l2::V4L2_PIX_FMT_NV21M => 2,
+        v4l2::V4L2_PIX_FMT_YUV420M | v4l2::V4L2_PIX_FMT_YVU420M => 3,
+        _ => 1
+    }
+}
+
+// Minimum bytesperline of the first plane.
+pub fn min_bytesperline(fourcc: u32, width: uint) -> uint {
+    match contiguous_fourcc(fourcc) {
+        v4l2::V4L2_PIX_FMT_RGB24 | v4l2::V4L2_PIX_FMT_BGR24 => width * 3,
+        v4l2::V4L2_PIX_FMT_GREY |
+        v4l2::V4L2_PIX_FMT_NV12 | v4l2::V4L2_PIX_FMT_NV21 |
+        v4l2::V4L2_PIX_FMT_YUV420 | v4l2::V4L2_PIX_FMT_YVU420 => width,
+        _ => width * 2
+    }
+}
+
+// Size of a contiguous image with the given first-plane bytesperline.
+pub fn image_size(fourcc: u32, bytesperline: uint, height: uint) -> uint {
+    match contiguous_fourcc(fourcc) {
+        v4l2::V4L2_PIX_FMT_NV12 | v4l2::V4L2_PIX_FMT_NV21 |
+        v4l2::V4L2_PIX_FMT_YUV420 | v4l2::V4L2_PIX_FMT_YVU420 => {
+            bytesperline * height + bytesperline * ((height + 1) / 2)
+        }
+        _ => bytesperline * height
+    }
+}
+
+// Minimum bytesperline of memory plane `plane`.
+pub fn plane_min_bytesperline(fourcc: u32, plane: uint, width: uint) -> uint {
+    match (fourcc, plane) {
+        (_, 0) => min_bytesperline(fourcc, width),
+        (v4l2::V4L2_PIX_FMT_YUV420M, _) | (v4l2::V4L2_PIX_FMT_YVU420M, _) => (width + 1) / 2,
+        _ => (width + 1) & !1
+    }
+}
+
+// Size of memory plane `plane` with the given bytesperline.
+pub fn plane_size(fourcc: u32, plane: uint, bytesperline: uint, height: uint) -> uint {
+    if num_planes(fourcc) == 1 {
+        image_size(fourcc, bytesperline, height)
+    } else if plane == 0 {
+        bytesperline * height
+    } else {
+        bytesperline * ((height + 1) / 2)
+    }
+}
+
+fn clamp(v: f64) -> u8 {
+    if v < 0.0 {
+        0
+    } else if v > 255.0 {
+        255
+    } else {
+        v as u8
+    }
+}
+
+pub fn ycbcr_to_rgb(y: u8, cb: u8, cr: u8) -> (u8, u8, u8) {
+    let y = y as f64;
+    let cb = cb as f64 - 128.0;
+    let cr = cr as f64 - 128.0;
+    (clamp(y + 1.40200 * cr),
+     clamp(y - 0.34414 * cb - 0.71414 * cr),
+     clamp(y + 1.77200 * cb))
+}
+
+pub fn rgb_to_ycbcr(r: u8, g: u8, b: u8) -> (u8, u8, u8) {
+    let r = r as f64;
+    let g = g as f64;
+    let b = b as f64;
+    (clamp(0.29900 * r + 0.58700 * g + 0.11400 * b),
+     clamp(128.0 - 0.168736 * r - 0.331264 * g + 0.5 * b),
+     clamp(128.0 + 0.5 * r - 0.418688 * g - 0.081312 * b))
+}
+
+// Byte offsets of Y0, Cb, Y1, Cr within a packed 4:2:2 macropixel.
+fn packed422_offsets(fourcc: u32) -> (uint, uint, uint, uint) {
+    match fourcc {
+        v4l2::V4L2_PIX_FMT_YVYU => (0, 3, 2, 1),
+        v4l2::V4L2_PIX_FMT_UYVY => (1, 0, 3, 2),
+        v4l2::V4L2_PIX_FMT_VYUY => (1, 2, 3, 0),
+        _ => (0, 1, 2, 3)
+    }
+}
+
+// `length` bytes of `data` from `start`, cut short at its end.
+fn part<'a>(data: &'a [u8], start: uint, length: uint) -> &'a [u8] {
+    let start = cmp::min(start, data.len());
+    data.slice(start, cmp::min(start + length, data.len()))
+}
+
+impl<'a> Frame<'a> {
+    // Splits a single contiguous buffer into the planes of `fourcc`.
+    pub fn new(fourcc: u32, width: uint, height: uint,
+               bytesperline: uint, data: &'a [u8]) -> Frame<'a> {
+        let fourcc = contiguous_fourcc(fourcc);
+        let luma_size = bytesperline * height;
+        let chroma_height = (height + 1) / 2;
+        let planes = match fourcc {
+            v4l2::V4L2_PIX_FMT_NV12 | v4l2::V4L2_PIX_FMT_NV21 => {
+                vec!(Plane { data: part(data, 0, luma_size), bytesperline: bytesperline },
+                     Plane {
+                         data: part(data, luma_size, bytesperline * chroma_height),
+                         bytesperline: bytesperline,
+                     })
+            }
+            v4l2::V4L2_PIX_FMT_YUV420 | v4l2::V4L2_PIX_FMT_YVU420 => {
+                let chroma_bpl = bytesperline / 2;
+                let chroma_size = chroma_bpl * chroma_height;
+                vec!(Plane { data: part(data, 0, luma_size), bytesperline: bytesperline },
+                     Plane { data: part(data, luma_size, chroma_size), bytesperline: chroma_bpl },
+                     Plane {
+                         data: part(data, luma_size + chroma_size, chroma_size),
+                         bytesperline: chroma_bpl,
+                     })
+            }
+            _ => vec!(Plane { data: data, bytesperline: bytesperline })
+        };
+        Frame {
+            fourcc: fourcc,
+            width: width,
+            height: height,
+            planes: planes,
+        }
+    }
+
+    // Wraps the memory planes of a multi-planar format such as NV12M.
+    pub fn from_planes(fourcc: u32, width: uint, height: uint, planes: Vec<Plane<'a>>) -> Frame<'a> {
+        if planes.len() == 1 {
+            let plane = planes.get(0);
+            return Frame::new(fourcc, width, height, plane.bytesperline, plane.data);
+        }
+        Frame {
+            fourcc: contiguous_fourcc(fourcc),
+            width: width,
+            height: height,
+            planes: planes,
+        }
+    }
+
+    fn sample(&self, plane: uint, offset: uint) -> u8 {
+        let p = self.planes.get(plane);
+        if offset >= p.data.len() {
+            return 0;
+        }
+        p.data[offset]
+    }
+
+    pub fn is_rgb(&self) -> bool {
+        match self.fourcc {
+            v4l2::V4L2_PIX_FMT_RGB24 | v4l2::V4L2_PIX_FMT_BGR24 => true,
+            _ => false
+        }
+    }
+
+    pub fn ycbcr_at(&self, x: uint, y: uint) -> (u8, u8, u8) {
+        match self.fourcc {
+            v4l2::V4L2_PIX_FMT_GREY => {
+                (self.sample(0, y * self.planes.get(0).bytesperline + x), 128, 128)
+            }
+            v4l2::V4L2_PIX_FMT_RGB24 | v4l2::V4L2_PIX_FMT_BGR24 => {
+                let (r, g, b) = self.rgb_at(x, y);
+                rgb_to_ycbcr(r, g, b)
+            }
+            v4l2::V4L2_PIX_FMT_NV12 | v4l2::V4L2_PIX_FMT_NV21 => {
+                let luma = self.sample(0, y * self.planes.get(0).bytesperline + x);
+                let base = (y / 2) * self.planes.get(1).bytesperline + (x & !1);
+                let first = self.sample(1, base);
+                let second = self.sample(1, base + 1);
+                if self.fourcc == v4l2::V4L2_PIX_FMT_NV12 {
+                    (luma, first, second)
+                } else {
+                    (luma, second, first)
+                }
+            }
+            v4l2::V4L2_PIX_FMT_YUV420 | v4l2::V4L2_PIX_FMT_YVU420 => {
+                let luma = self.sample(0, y * self.planes.get(0).bytesperline + x);
+                let (cb_plane, cr_plane) = if self.fourcc == v4l2::V4L2_PIX_FMT_YUV420 {
+                    (1, 2)
+                } else {
+                    (2, 1)
+                };
+                let cb = self.sample(cb_plane, (y / 2) * self.planes.get(cb_plane).bytesperline + x / 2);
+                let cr = self.sample(cr_plane, (y / 2) * self.planes.get(cr_plane).bytesperline + x / 2);
+                (luma, cb, cr)
+            }
+            _ => {
+                let (y0, cb, y1, cr) = packed422_offsets(self.fourcc);
+                let base = y * self.planes.get(0).bytesperline + (x & !1) * 2;
+                let luma = if x & 1 == 0 { y0 } else { y1 };
+                (self.sample(0, base + luma), self.sample(0, base + cb), self.sample(0, base + cr))
+            }
+        }
+    }
+
+    pub fn rgb_at(&self, x: uint, y: uint) -> (u8, u8, u8) {
+        match self.fourcc {
+            v4l2::V4L2_PIX_FMT_RGB24 | v4l2::V4L2_PIX_FMT_BGR24 => {
+                let base = y * self.planes.get(0).bytesperline + x * 3;
+                let (a, b, c) = (self.sample(0, base), self.sample(0, base + 1), self.sample(0, base + 2));
+                if self.fourcc == v4l2::V4L2_PIX_FMT_RGB24 { (a, b, c) } else { (c, b, a) }
+            }
+            _ => {
+                let (luma, cb, cr) = self.ycbcr_at(x, y);
+                ycbcr_to_rgb(luma, cb, cr)
+            }
+        }
+    }
+}
+
+// Packs an RGB image into `dest` laid out as a contiguous `fourcc` image.
+pub fn pack(fourcc: u32, width: uint, height: uint, bytesperline: uint,
+            image: &[(u8, u8, u8)], dest: &mut [u8]) {
+    let fourcc = contiguous_fourcc(fourcc);
+    let ycbcr = |x: uint, y: uint| -> (u8, u8, u8) {
+        let (r, g, b) = image[y * width + x];
+        rgb_to_ycbcr(r, g, b)
+    };
+    let luma_size = bytesperline * height;
+
+    match fourcc {
+        v4l2::V4L2_PIX_FMT_RGB24 | v4l2::V4L2_PIX_FMT_BGR24 => {
+            for y in range(0, height) {
+                for x in range(0, width) {
+                    let (r, g, b) = image[y * width + x];
+                    let base = y * bytesperline + x * 3;
+                    if fourcc == v4l2::V4L2_PIX_FMT_RGB24 {
+                        dest[base] = r; dest[base + 1] = g; dest[base + 2] = b;
+                    } else {
+                        dest[base] = b; dest[base + 1] = g; dest[base + 2] = r;
+                    }
+                }
+            }
+        }
+        v4l2::V4L2_PIX_FMT_GREY => {
+            for y in range(0, height) {
+                for x in range(0, width) {
+                    let (luma, _, _) = ycbcr(x, y);
+                    dest[y * bytesperline + x] = luma;
+                }
+            }
+        }
+        v4l2::V4L2_PIX_FMT_NV12 | v4l2::V4L2_PIX_FMT_NV21 => {
+            for y in range(0, height) {
+                for x in range(0, width) {
+                    let (luma, cb, cr) = ycbcr(x, y);
+                    dest[y * bytesperline + x] = luma;
+                    if x & 1 == 0 && y & 1 == 0 {
+                        let base = luma_size + (y / 2) * bytesperline + x;
+                        if fourcc == v4l2::V4L2_PIX_FMT_NV12 {
+                            dest[base] = cb; dest[base + 1] = cr;
+                        } else {
+                            dest[base] = cr; dest[base + 1] = cb;
+                        }
+                    }
+                }
+            }
+        }
+        v4l2::V4L2_PIX_FMT_YUV420 | v4l2::V4L2_PIX_FMT_YVU420 => {
+            let chroma_bpl = bytesperline / 2;
+            let chroma_size = chroma_bpl * ((height + 1) / 2);
+            let (cb_base, cr_base) = if fourcc == v4l2::V4L2_PIX_FMT_YUV420 {
+                (luma_size, luma_size + chroma_size)
+            } else {
+                (luma_size + chroma_size, luma_size)
+            };
+            for y in range(0, height) {
+                for x in range(0, width) {
+                    let (luma, cb, cr) = ycbcr(x, y);
+                    dest[y * bytesperline + x] = luma;
+                    if x & 1 == 0 && y & 1 == 0 {
+                        let offset = (y / 2) * chroma_bpl + x / 2;
+                        dest[cb_base + offset] = cb;
+                        dest[cr_base + offset] = cr;
+                    }
+                }
+            }
+        }
+        _ => {
+            let (y0, cb_offset, y1, cr_offset) = packed422_offsets(fourcc);
+            for y in range(0, height) {
+                let mut x = 0;
+                while x + 1 < width {
+                    let (l0, cb, cr) = ycbcr(x, y);
+                    let (l1, _, _) = ycbcr(x + 1, y);
+                    let base = y * bytesperline + x * 2;
+                    dest[base + y0] = l0;
+                    dest[base + y1] = l1;
+                    dest[base + cb_offset] = cb;
+                    dest[base + cr_offset] = cr;
+                    x += 2;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,175 @@
+// Scaling from the captured frame to the window.
+//
+// Conversion walks the window rather than the frame: each window pixel
+// inside the destination rectangle samples the frame at the matching
+// position, so a downscaled stream never converts pixels that are thrown
+// away. Whatever the image does not cover is letterboxed in black.
+
+use overlay::{Canvas,BLACK};
+use pixfmt::Frame;
+
+#[deriving(Eq, Show)]
+pub enum Filter {
+    Nearest,
+    Bilinear,
+}
+
+pub fn filter_from_str(s: &str) -> Option<Filter> {
+    match s {
+        "nearest" => Some(Nearest),
+        "bilinear" => Some(Bilinear),
+        _ => None
+    }
+}
+
+#[deriving(Eq, Show)]
+pub enum ScaleMode {
+    // Largest size that fits the window at the frame's aspect ratio.
+    Fit,
+    // Like Fit, rounded down to a whole multiple or fraction of the frame
+    // size so pixels stay evenly sized.
+    Integer,
+}
+
+pub fn scale_mode_from_str(s: &str) -> Option<ScaleMode> {
+    match s {
+        "fit" => Some(Fit),
+        "integer" => Some(Integer),
+        _ => None
+    }
+}
+
+#[deriving(Eq, Show, Clone)]
+pub struct Rect {
+    pub x: uint,
+    pub y: uint,
+    pub width: uint,
+    pub height: uint,
+}
+
+impl Rect {
+    pub fn contains(&self, x: uint, y: uint) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+// Part of the frame being shown, in frame pixels. Zooming in shrinks it.
+#[deriving(Eq, Show, Clone)]
+pub struct Region {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Region {
+    pub fn whole(width: uint, height: uint) -> Region {
+        Region { x: 0.0, y: 0.0, width: width as f64, height: height as f64 }
+    }
+}
+
+// Where a `src_width` x `src_height` image lands, centred, in a
+// `dst_width` x `dst_height` window.
+pub fn fit(mode: ScaleMode, src_width: uint, src_height: uint,
+           dst_width: uint, dst_height: uint) -> Rect {
+    if src_width == 0 || src_height == 0 || dst_width == 0 || dst_height == 0 {
+        return Rect { x: 0, y: 0, width: 0, height: 0 };
+    }
+
+    let scale_x = dst_width as f64 / src_width as f64;
+    let scale_y = dst_height as f64 / src_height as f64;
+    let mut scale = if scale_x < scale_y { scale_x } else { scale_y };
+    if mode == Integer {
+        scale = if scale >= 1.0 {
+            scale.floor()
+        } else {
+            1.0 / (1.0 / scale).ceil()
+        };
+    }
+
+    let width = clamp((src_width as f64 * scale).round() as uint, 1, dst_width);
+    let height = clamp((src_height as f64 * scale).round() as uint, 1, dst_height);
+    Rect {
+        x: (dst_width - width) / 2,
+        y: (dst_height - height) / 2,
+        width: width,
+        height: height,
+    }
+}
+
+fn clamp(v: uint, lo: uint, hi: uint) -> uint {
+    if v < lo { lo } else if v > hi { hi } else { v }
+}
+
+// Converts the `src` region of `frame` into `dest` on `canvas`, scaled with
+// `filter`, and blacks out the rest of the canvas.
+pub fn convert(frame: &Frame, canvas: &mut Canvas, dest: Rect, src: Region, filter: Filter) {
+    if frame.width == 0 || frame.height == 0 {
+        return;
+    }
+    for y in range(0, canvas.height) {
+        for x in range(0, canvas.width) {
+            if !dest.contains(x, y) {
+                canvas.put(x, y, BLACK);
+                continue;
+            }
+            let (fx, fy) = position(dest, src, x, y);
+            let color = match filter {
+                Nearest => {
+                    frame.rgb_at(nearest(fx, frame.width), nearest(fy, frame.height))
+                }
+                Bilinear => bilinear(frame, fx, fy)
+            };
+            canvas.put(x, y, color);
+        }
+    }
+}
+
+// Centre of window pixel (x, y) in frame coordinates.
+fn position(dest: Rect, src: Region, x: uint, y: uint) -> (f64, f64) {
+    let step_x = src.width / dest.width as f64;
+    let step_y = src.height / dest.height as f64;
+    (src.x + ((x - dest.x) as f64 + 0.5) * step_x - 0.5,
+     src.y + ((y - dest.y) as f64 + 0.5) * step_y - 0.5)
+}
+
+// The frame pixel nearest to window pixel (x, y) inside `dest`.
+pub fn frame_pixel(frame: &Frame, dest: Rect, src: Region, x: uint, y: uint) -> (uint, uint) {
+    let (fx, fy) = position(dest, src, x, y);
+    (nearest(fx, frame.width), nearest(fy, frame.height))
+}
+
+fn nearest(v: f64, size: uint) -> uint {
+    let i = (v + 0.5).floor();
+    if i < 0.0 {
+        0
+    } else if i as uint >= size {
+        size - 1
+    } else {
+        i as uint
+    }
+}
+
+fn bilinear(frame: &Frame, fx: f64, fy: f64) -> (u8, u8, u8) {
+    let fx = if fx < 0.0 { 0.0 } else { fx };
+    let fy = if fy < 0.0 { 0.0 } else { fy };
+    let x0 = clamp(fx.floor() as uint, 0, frame.width - 1);
+    let y0 = clamp(fy.floor() as uint, 0, frame.height - 1);
+    let x1 = clamp(x0 + 1, 0, frame.width - 1);
+    let y1 = clamp(y0 + 1, 0, frame.height - 1);
+    let tx = fx - fx.floor();
+    let ty = fy - fy.floor();
+
+    let lerp = |a: (u8, u8, u8), b: (u8, u8, u8), t: f64| -> (f64, f64, f64) {
+        let (ar, ag, ab) = a;
+        let (br, bg, bb) = b;
+        (ar as f64 + (br as f64 - ar as f64) * t,
+         ag as f64 + (bg as f64 - ag as f64) * t,
+         ab as f64 + (bb as f64 - ab as f64) * t)
+    };
+    let (tr, tg, tb) = lerp(frame.rgb_at(x0, y0), frame.rgb_at(x1, y0), tx);
+    let (br, bg, bb) = lerp(frame.rgb_at(x0, y1), frame.rgb_at(x1, y1), tx);
+    ((tr + (br - tr) * ty + 0.5) as u8,
+     (tg + (bg - tg) * ty + 0.5) as u8,
+     (tb + (bb - tb) * ty + 0.5) as u8)
+}
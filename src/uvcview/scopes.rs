@@ -0,0 +1,164 @@
+// Histogram, waveform monitor and vectorscope.
+//
+// Levels are read from the frame's own samples, so for YUV formats the
+// scopes show what the camera delivered rather than the converted RGB.
+
+use overlay::Canvas;
+use pixfmt;
+use pixfmt::Frame;
+
+// Frames wider than this are sampled every few pixels and rows.
+static SAMPLE_WIDTH: uint = 640;
+
+// Horizontal resolution of the waveform.
+pub static WAVEFORM_COLUMNS: uint = 256;
+
+pub struct Scopes {
+    pub luma: Vec<u32>,
+    pub red: Vec<u32>,
+    pub green: Vec<u32>,
+    pub blue: Vec<u32>,
+    // Luma counts per column, WAVEFORM_COLUMNS x 256, column major.
+    pub waveform: Vec<u32>,
+    // Counts over Cb (x) and Cr (y), 256 x 256, row major.
+    pub vectorscope: Vec<u32>,
+}
+
+impl Scopes {
+    pub fn compute(frame: &Frame) -> Scopes {
+        let mut scopes = Scopes {
+            luma: Vec::from_elem(256, 0u32),
+            red: Vec::from_elem(256, 0u32),
+            green: Vec::from_elem(256, 0u32),
+            blue: Vec::from_elem(256, 0u32),
+            waveform: Vec::from_elem(WAVEFORM_COLUMNS * 256, 0u32),
+            vectorscope: Vec::from_elem(256 * 256, 0u32),
+        };
+        if frame.width == 0 || frame.height == 0 {
+            return scopes;
+        }
+
+        let step = if frame.width > SAMPLE_WIDTH { frame.width / SAMPLE_WIDTH } else { 1 };
+        let rgb = frame.is_rgb();
+        let mut y = 0;
+        while y < frame.height {
+            let mut x = 0;
+            while x < frame.width {
+                let ((luma, cb, cr), (r, g, b)) = if rgb {
+                    let (r, g, b) = frame.rgb_at(x, y);
+                    (pixfmt::rgb_to_ycbcr(r, g, b), (r, g, b))
+                } else {
+                    let (luma, cb, cr) = frame.ycbcr_at(x, y);
+                    ((luma, cb, cr), pixfmt::ycbcr_to_rgb(luma, cb, cr))
+                };
+                *scopes.luma.get_mut(luma as uint) += 1;
+                *scopes.red.get_mut(r as uint) += 1;
+                *scopes.green.get_mut(g as uint) += 1;
+                *scopes.blue.get_mut(b as uint) += 1;
+                let column = x * WAVEFORM_COLUMNS / frame.width;
+                *scopes.waveform.get_mut(column * 256 + luma as uint) += 1;
+                *scopes.vectorscope.get_mut(cr as uint * 256 + cb as uint) += 1;
+                x += step;
+            }
+            y += step;
+        }
+        scopes
+    }
+
+    // Draws the histogram, 256 pixels wide, with its top left at (x0, y0).
+    // Luma is drawn in grey with the red, green and blue outlines on top.
+    pub fn draw_histogram(&self, canvas: &mut Canvas, x0: uint, y0: uint, height: uint) {
+        canvas.shade_rect(x0, y0, 256, height);
+        let max = [&self.luma, &self.red, &self.green, &self.blue].iter().fold(1, |max, counts| {
+            counts.iter().fold(max, |max, &count| if count > max { count } else { max })
+        });
+        let level = |count: u32| -> uint {
+            // Square root keeps small counts visible next to a large peak.
+            ((count as f64 / max as f64).sqrt() * height as f64) as uint
+        };
+
+        for i in range(0u, 256) {
+            let luma = level(*self.luma.get(i));
+            for dy in range(0, luma) {
+                canvas.put(x0 + i, y0 + height - 1 - dy, (128, 128, 128));
+            }
+            let r = level(*self.red.get(i));
+            let g = level(*self.green.get(i));
+            let b = level(*self.blue.get(i));
+            if r > 0 {
+                canvas.put(x0 + i, y0 + height - r, (255, 0, 0));
+            }
+            if g > 0 {
+                canvas.put(x0 + i, y0 + height - g, (0, 255, 0));
+            }
+            if b > 0 {
+                canvas.put(x0 + i, y0 + height - b, (0, 0, 255));
+            }
+        }
+    }
+
+    // Draws the waveform, WAVEFORM_COLUMNS pixels wide, with its top left at
+    // (x0, y0). Luma 255 is at the top.
+    pub fn draw_waveform(&self, canvas: &mut Canvas, x0: uint, y0: uint, height: uint) {
+        canvas.shade_rect(x0, y0, WAVEFORM_COLUMNS, height);
+        let max = self.waveform.iter().fold(1, |max, &count| if count > max { count } else { max });
+
+        for column in range(0, WAVEFORM_COLUMNS) {
+            for luma in range(0u, 256) {
+                let count = *self.waveform.get(column * 256 + luma);
+                if count == 0 {
+                    continue;
+                }
+                let y = y0 + height - 1 - luma * height / 256;
+                canvas.put(x0 + column, y, (0, intensity(count, max), 0));
+            }
+        }
+    }
+
+    // Draws the vectorscope, `size` pixels square, with its top left at
+    // (x0, y0). Cb grows to the right and Cr upwards; the boxes mark 75%
+    // colour bars.
+    pub fn draw_vectorscope(&self, canvas: &mut Canvas, x0: uint, y0: uint, size: uint) {
+        canvas.shade_rect(x0, y0, size, size);
+        let max = self.vectorscope.iter().fold(1, |max, &count| if count > max { count } else { max });
+        let to_screen = |cb: uint, cr: uint| -> (uint, uint) {
+            (x0 + cb * size / 256, y0 + size - 1 - cr * size / 256)
+        };
+
+        for cr in range(0u, 256) {
+            for cb in range(0u, 256) {
+                let count = *self.vectorscope.get(cr * 256 + cb);
+                if count == 0 {
+                    continue;
+                }
+                let (x, y) = to_screen(cb, cr);
+                let v = intensity(count, max);
+                canvas.put(x, y, (v, v, v));
+            }
+        }
+
+        // Graticule: centre cross and the 75% primaries and secondaries.
+        let (cx, cy) = to_screen(128, 128);
+        for d in range(0u, 5) {
+            canvas.put(cx - 2 + d, cy, (96, 96, 96));
+            canvas.put(cx, cy - 2 + d, (96, 96, 96));
+        }
+        let targets = [(191u8, 0u8, 0u8), (191, 191, 0), (0, 191, 0),
+                       (0, 191, 191), (0, 0, 191), (191, 0, 191)];
+        for &(r, g, b) in targets.iter() {
+            let (_, cb, cr) = pixfmt::rgb_to_ycbcr(r, g, b);
+            let (x, y) = to_screen(cb as uint, cr as uint);
+            let color = (r, g, b);
+            for d in range(0u, 5) {
+                canvas.put(x - 2 + d, y - 2, color);
+                canvas.put(x - 2 + d, y + 2, color);
+                canvas.put(x - 2, y - 2 + d, color);
+                canvas.put(x + 2, y - 2 + d, color);
+            }
+        }
+    }
+}
+
+fn intensity(count: u32, max: u32) -> u8 {
+    (64.0 + 191.0 * (count as f64 / max as f64).sqrt()) as u8
+}
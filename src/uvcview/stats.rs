@@ -0,0 +1,130 @@
+// Frame delivery statistics derived from v4l2_buffer sequence numbers,
+// timestamps and flags.
+//
+// Gaps in the sequence are frames the driver dropped, usually because the
+// USB link could not keep up; V4L2_BUF_FLAG_ERROR marks frames that arrived
+// damaged.
+
+use std::fmt;
+use std::num::Float;
+
+// Number of recent frame intervals kept for the jitter estimate.
+static WINDOW: uint = 120;
+
+// Nanoseconds between periodic log lines.
+static LOG_INTERVAL_NS: u64 = 5000000000;
+
+pub struct FrameStats {
+    pub frames: u64,
+    pub dropped: u64,
+    pub errors: u64,
+    // Frame rate from the last interval, and over the whole stream.
+    pub fps: f64,
+    pub avg_fps: f64,
+    // Standard deviation of recent frame intervals in milliseconds.
+    pub jitter_ms: f64,
+    // Sequence number and timestamp of the latest frame.
+    pub last_sequence: Option<u32>,
+    pub last_timestamp_ns: u64,
+    first_timestamp_ns: u64,
+    intervals: Vec<f64>,
+    next_log_ns: u64,
+}
+
+impl FrameStats {
+    pub fn new() -> FrameStats {
+        FrameStats {
+            frames: 0,
+            dropped: 0,
+            errors: 0,
+            fps: 0.0,
+            avg_fps: 0.0,
+            jitter_ms: 0.0,
+            last_sequence: None,
+            last_timestamp_ns: 0,
+            first_timestamp_ns: 0,
+            intervals: vec!(),
+            next_log_ns: 0,
+        }
+    }
+
+    // Forgets the stream so far, e.g. after STREAMOFF resets the sequence.
+    pub fn reset(&mut self) {
+        *self = FrameStats::new();
+    }
+
+    // Records one dequeued frame.
+    pub fn update(&mut self, sequence: u32, timestamp_ns: u64, error: bool) {
+        match self.last_sequence {
+            Some(last) => {
+                // Sequence numbers wrap; a huge gap means the driver
+                // restarted counting rather than dropped frames.
+                let gap = sequence - last - 1;
+                if gap < 0x80000000 {
+                    self.dropped += gap as u64;
+                }
+            }
+            None => {}
+        }
+        self.last_sequence = Some(sequence);
+
+        if error {
+            self.errors += 1;
+        }
+
+        if self.frames == 0 {
+            self.first_timestamp_ns = timestamp_ns;
+        } else if timestamp_ns > self.last_timestamp_ns {
+            let interval = (timestamp_ns - self.last_timestamp_ns) as f64;
+            self.fps = 1e9 / interval;
+            self.avg_fps = self.frames as f64 * 1e9 /
+                (timestamp_ns - self.first_timestamp_ns) as f64;
+
+            if self.intervals.len() == WINDOW {
+                self.intervals.shift();
+            }
+            self.intervals.push(interval);
+            self.jitter_ms = stddev(self.intervals.as_slice()) / 1e6;
+        }
+        self.last_timestamp_ns = timestamp_ns;
+        self.frames += 1;
+    }
+
+    // True once every LOG_INTERVAL_NS, for the periodic log line.
+    pub fn log_due(&mut self, now_ns: u64) -> bool {
+        if self.next_log_ns == 0 {
+            self.next_log_ns = now_ns + LOG_INTERVAL_NS;
+            return false;
+        }
+        if now_ns < self.next_log_ns {
+            return false;
+        }
+        self.next_log_ns = now_ns + LOG_INTERVAL_NS;
+        true
+    }
+
+    // Lines for the on-screen overlay.
+    pub fn lines(&self) -> Vec<~str> {
+        vec!(format!("FPS {:.2} AVG {:.2}", self.fps, self.avg_fps),
+             format!("JITTER {:.2} MS", self.jitter_ms),
+             format!("DROPPED {} ERRORS {}", self.dropped, self.errors),
+             format!("FRAMES {}", self.frames))
+    }
+}
+
+impl fmt::Show for FrameStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "frames {} dropped {} errors {} fps {:.2} (avg {:.2}) jitter {:.2} ms",
+               self.frames, self.dropped, self.errors, self.fps, self.avg_fps, self.jitter_ms)
+    }
+}
+
+fn stddev(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().fold(0.0, |sum, &v| sum + v) / n;
+    let variance = values.iter().fold(0.0, |sum, &v| sum + (v - mean) * (v - mean)) / n;
+    variance.sqrt()
+}
@@ -0,0 +1,53 @@
+// Test crate root. Built by test.sh with `rustc --test`; pulls in the
+// viewer's modules alongside the fake device used to drive them.
+
+#![feature(phase, globs)]
+
+extern crate sdl;
+extern crate libc;
+extern crate time;
+#[phase(syntax, link)]
+extern crate log;
+
+mod analog;
+mod assist;
+mod backend;
+mod controls;
+mod deinterlace;
+mod device;
+mod dv;
+mod events;
+mod fakedev;
+mod font;
+mod guides;
+mod ioctl;
+mod latency;
+mod meta;
+mod overlay;
+mod pixfmt;
+mod scale;
+mod scopes;
+mod stats;
+mod testsrc;
+mod uvc;
+mod v4l2;
+mod uvcview;
+mod xu;
+
+mod test_analog;
+mod test_assist;
+mod test_common;
+mod test_deinterlace;
+mod test_device;
+mod test_dv;
+mod test_events;
+mod test_guides;
+mod test_init;
+mod test_layout;
+mod test_latency;
+mod test_meta;
+mod test_scale;
+mod test_scopes;
+mod test_stats;
+mod test_testsrc;
+mod test_xu;
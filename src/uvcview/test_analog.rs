@@ -0,0 +1,22 @@
+use analog;
+use v4l2;
+
+#[test]
+fn standard_names() {
+    assert_eq!(analog::std_from_str("PAL"), Some(v4l2::V4L2_STD_PAL));
+    assert_eq!(analog::std_from_str("ntsc-jp"), Some(v4l2::V4L2_STD_NTSC_M_JP));
+    assert_eq!(analog::std_from_str("auto"), None);
+    assert_eq!(analog::std_to_str(v4l2::V4L2_STD_PAL_BG).as_slice(), "PAL");
+    assert_eq!(analog::std_to_str(v4l2::V4L2_STD_NTSC_M).as_slice(), "NTSC");
+    assert_eq!(analog::std_to_str(v4l2::V4L2_STD_PAL_M).as_slice(), "PAL-M");
+    assert_eq!(analog::std_to_str(v4l2::V4L2_STD_PAL_B | v4l2::V4L2_STD_SECAM_B).as_slice(), "625/50");
+    assert_eq!(analog::std_to_str(v4l2::V4L2_STD_UNKNOWN).as_slice(), "UNKNOWN");
+}
+
+#[test]
+fn tuner_units() {
+    assert_eq!(analog::to_tuner_units(175.25, 0), 2804);
+    assert_eq!(analog::to_tuner_units(175.25, v4l2::V4L2_TUNER_CAP_LOW), 2804000);
+    assert_eq!(analog::from_tuner_units(2804, 0), 175.25);
+    assert_eq!(analog::from_tuner_units(2804000, v4l2::V4L2_TUNER_CAP_LOW), 175.25);
+}
@@ -0,0 +1,81 @@
+use assist;
+use assist::Assist;
+use overlay::{Canvas,BLACK};
+use pixfmt::Frame;
+use scale;
+use scale::{Nearest,Rect,Region};
+use std::default::Default;
+use test_common::{grey_frame,grey_frame_of};
+use uvcview::UvcView;
+
+static FLAT: [u8, ..4] = [90, 90, 90, 90];
+
+// Converts `frame` 1:1 onto a 2x2 canvas and applies `assist` on top.
+fn render(frame: &Frame, assist: &Assist, pixels: &mut [u8]) {
+    let dest = Rect { x: 0, y: 0, width: 2, height: 2 };
+    let src = Region::whole(2, 2);
+    let mut canvas = Canvas::new(pixels, 2 * 3, 2, 2);
+    scale::convert(frame, &mut canvas, dest, src, Nearest);
+    assist.apply(frame, &mut canvas, dest, src);
+}
+
+fn pixel(pixels: &[u8], x: uint, y: uint) -> (u8, u8, u8) {
+    let offset = y * 2 * 3 + x * 3;
+    (pixels[offset + 2], pixels[offset + 1], pixels[offset])
+}
+
+#[test]
+fn false_color_bands() {
+    assert_eq!(assist::false_color(16), (128, 0, 160));
+    assert_eq!(assist::false_color(80), (80, 80, 80));
+    assert_eq!(assist::false_color(115), (0, 200, 0));
+    assert_eq!(assist::false_color(240), (255, 0, 0));
+}
+
+#[test]
+fn zebra_marks_only_bright_pixels() {
+    let frame = grey_frame();
+    let mut assist = Assist::new();
+    assist.zebra = true;
+    assist.zebra_level = 200;
+    let mut pixels = Vec::from_elem(2 * 2 * 3, 7u8);
+    render(&frame, &assist, pixels.as_mut_slice());
+    assert_eq!(pixel(pixels.as_slice(), 1, 0), (100, 100, 100));
+    assert_eq!(pixel(pixels.as_slice(), 0, 1), BLACK);
+    assert_eq!(pixel(pixels.as_slice(), 1, 1), BLACK);
+}
+
+#[test]
+fn peaking_follows_gradient() {
+    assert_eq!(assist::gradient(&grey_frame_of(FLAT.as_slice()), 0, 0), 0);
+    assert_eq!(assist::gradient(&grey_frame(), 0, 0), 300);
+
+    let mut assist = Assist::new();
+    assist.peaking = true;
+    assist.peaking_level = 10;
+    let mut pixels = Vec::from_elem(2 * 2 * 3, 7u8);
+    render(&grey_frame_of(FLAT.as_slice()), &assist, pixels.as_mut_slice());
+    assert_eq!(pixel(pixels.as_slice(), 0, 0), (90, 90, 90));
+    render(&grey_frame(), &assist, pixels.as_mut_slice());
+    assert_eq!(pixel(pixels.as_slice(), 0, 0), (255, 0, 255));
+}
+
+#[test]
+fn inspector_reads_under_the_zebra() {
+    let frame = grey_frame();
+    let mut uvcview: UvcView = Default::default();
+    uvcview.width = 2;
+    uvcview.height = 2;
+    uvcview.show_inspector = true;
+    uvcview.cursor = Some((1, 1));
+    uvcview.assist.zebra = true;
+    uvcview.assist.zebra_level = 200;
+
+    let dest = Rect { x: 0, y: 0, width: 2, height: 2 };
+    let src = uvcview.source_region();
+    let mut pixels = Vec::from_elem(2 * 2 * 3, 7u8);
+    let mut canvas = Canvas::new(pixels.as_mut_slice(), 2 * 3, 2, 2);
+    scale::convert(&frame, &mut canvas, dest, src, Nearest);
+    let lines = uvcview.draw_over(&frame, &mut canvas, dest, src).unwrap();
+    assert_eq!(lines, vec!("POS 1,1".to_owned(), "Y 255".to_owned(), "RGB 255 255 255".to_owned()));
+}
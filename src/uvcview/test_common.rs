@@ -0,0 +1,28 @@
+// Fixtures shared by the tests.
+
+use backend::IoctlBackend;
+use device::Device;
+use fakedev::FakeDevice;
+use pixfmt::Frame;
+use std::default::Default;
+use uvcview::UvcView;
+use v4l2;
+
+// A 2x2 grey frame: 0, 100 on top, 200, 255 below.
+pub static GREY: [u8, ..4] = [0, 100, 200, 255];
+
+pub fn grey_frame() -> Frame<'static> {
+    grey_frame_of(GREY.as_slice())
+}
+
+// A 2x2 grey frame of other samples.
+pub fn grey_frame_of<'a>(data: &'a [u8]) -> Frame<'a> {
+    Frame::new(v4l2::V4L2_PIX_FMT_GREY, 2, 2, 2, data)
+}
+
+// A viewer on a scripted device, not yet initialised.
+pub fn viewer(device: FakeDevice) -> UvcView {
+    let mut uvcview: UvcView = Default::default();
+    uvcview.device = Device::new(3, ~device as ~IoctlBackend);
+    uvcview
+}
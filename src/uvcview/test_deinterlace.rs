@@ -0,0 +1,75 @@
+use deinterlace;
+use device;
+use device::Field;
+use deinterlace::{Deinterlacer,Weave,Bob,Adaptive};
+use pixfmt::Frame;
+use v4l2;
+
+// A GREY frame two pixels wide with one value per line.
+fn frame(lines: &[u8]) -> (Vec<u8>, uint) {
+    let mut data = vec!();
+    for &v in lines.iter() {
+        data.push(v);
+        data.push(v);
+    }
+    (data, lines.len())
+}
+
+fn process(deinterlacer: &mut Deinterlacer, lines: &[u8], field: Field) -> Vec<u8> {
+    let (data, height) = frame(lines);
+    let input = Frame::new(v4l2::V4L2_PIX_FMT_GREY, 2, height, 2, data.as_slice());
+    deinterlacer.process(&input, field);
+    let output = deinterlacer.frame();
+    let bytes = output.planes.get(0).data;
+    range(0, output.height).map(|y| bytes[y * 2]).collect()
+}
+
+fn deinterlacer(method: deinterlace::Method) -> Deinterlacer {
+    let mut deinterlacer = Deinterlacer::new();
+    deinterlacer.method = method;
+    deinterlacer
+}
+
+#[test]
+fn weave_keeps_interleaved_lines() {
+    let mut d = deinterlacer(Weave);
+    assert_eq!(process(&mut d, &[10, 20, 30, 40], device::Interlaced), vec!(10, 20, 30, 40));
+}
+
+#[test]
+fn weave_interleaves_sequential_fields() {
+    let mut d = deinterlacer(Weave);
+    assert_eq!(process(&mut d, &[10, 30, 20, 40], device::SeqTb), vec!(10, 20, 30, 40));
+    let mut d = deinterlacer(Weave);
+    assert_eq!(process(&mut d, &[20, 40, 10, 30], device::SeqBt), vec!(10, 20, 30, 40));
+}
+
+#[test]
+fn bob_interpolates_older_field() {
+    let mut d = deinterlacer(Bob);
+    // Top field first, so the bottom lines are kept.
+    assert_eq!(process(&mut d, &[0, 20, 0, 40], device::InterlacedTb), vec!(20, 20, 30, 40));
+    // Bottom field first, so the top lines are kept.
+    assert_eq!(process(&mut d, &[10, 0, 30, 0], device::InterlacedBt), vec!(10, 20, 30, 30));
+}
+
+#[test]
+fn alternate_fields_are_paired() {
+    let mut d = deinterlacer(Weave);
+    assert_eq!(process(&mut d, &[10, 30], device::Top), vec!(10, 10, 30, 30));
+    assert_eq!(process(&mut d, &[20, 40], device::Bottom), vec!(10, 20, 30, 40));
+    assert_eq!(process(&mut d, &[50, 70], device::Top), vec!(50, 20, 70, 40));
+}
+
+#[test]
+fn adaptive_weaves_still_and_bobs_motion() {
+    let mut d = deinterlacer(Adaptive);
+    process(&mut d, &[10, 200, 30, 200], device::InterlacedTb);
+    assert_eq!(process(&mut d, &[10, 200, 30, 200], device::InterlacedTb),
+               vec!(10, 200, 30, 200));
+    assert_eq!(process(&mut d, &[10, 100, 30, 100], device::InterlacedTb),
+               vec!(100, 100, 100, 100));
+    // Changes below the threshold count as still.
+    assert_eq!(process(&mut d, &[10, 110, 30, 110], device::InterlacedTb),
+               vec!(10, 110, 30, 110));
+}
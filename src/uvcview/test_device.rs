@@ -0,0 +1,124 @@
+use backend::IoctlBackend;
+use device;
+use device::{Device,BufType,Field,Fourcc,Memory,PixFormat};
+use fakedev::FakeDevice;
+use libc::consts::os::posix88::{EAGAIN,EINVAL};
+use v4l2;
+
+fn open(fake: FakeDevice) -> Device {
+    Device::new(3, ~fake as ~IoctlBackend)
+}
+
+#[test]
+fn fourcc_display() {
+    assert_eq!(format!("{}", Fourcc(v4l2::V4L2_PIX_FMT_YUYV)).as_slice(), "YUYV");
+    assert_eq!(format!("{}", Fourcc(v4l2::V4L2_PIX_FMT_NV12M)).as_slice(), "NM12");
+    assert_eq!(format!("{}", Fourcc(0xff000000)).as_slice(), "0xff000000");
+    assert_eq!(Fourcc::from_str("yuyv"), Some(Fourcc(v4l2::V4L2_PIX_FMT_YUYV)));
+}
+
+#[test]
+fn enum_round_trips() {
+    for &buf_type in [device::VideoCapture, device::VideoCaptureMplane].iter() {
+        assert_eq!(BufType::from_v4l2(buf_type.to_v4l2()), Some(buf_type));
+    }
+    assert_eq!(BufType::from_v4l2(v4l2::V4L2_BUF_TYPE_VIDEO_CAPTURE_MPLANE),
+               Some(device::VideoCaptureMplane));
+    assert!(device::VideoCaptureMplane.is_mplane());
+
+    for &memory in [device::Mmap, device::UserPtr, device::DmaBuf].iter() {
+        assert_eq!(Memory::from_v4l2(memory.to_v4l2()), Some(memory));
+    }
+    assert_eq!(Memory::from_v4l2(0), None);
+
+    for value in range(v4l2::V4L2_FIELD_ANY, v4l2::V4L2_FIELD_INTERLACED_BT + 1) {
+        assert_eq!(Field::from_v4l2(value).map(|field| field.to_v4l2()), Some(value));
+    }
+    assert_eq!(Field::from_v4l2(v4l2::V4L2_FIELD_ALTERNATE), Some(device::Alternate));
+    assert_eq!(Field::from_v4l2(v4l2::V4L2_FIELD_INTERLACED_BT + 1), None);
+}
+
+#[test]
+fn field_names() {
+    assert_eq!(Field::from_str("seq-bt"), Some(device::SeqBt));
+    assert_eq!(Field::from_str("upper"), None);
+    assert_eq!(device::SeqBt.name(), "SEQ_BT");
+    assert!(device::Alternate.is_interlaced());
+    assert!(!device::Progressive.is_interlaced());
+    assert!(!device::Top.is_interlaced());
+}
+
+#[test]
+fn query_cap() {
+    let cap = open(FakeDevice::new()).query_cap().unwrap();
+    assert!(cap.has(v4l2::V4L2_CAP_STREAMING));
+    assert!(!cap.has(v4l2::V4L2_CAP_READWRITE));
+}
+
+#[test]
+fn errors_are_errno() {
+    let mut device = open(FakeDevice::new().fail(v4l2::VIDIOC_QUERYCAP, EINVAL));
+    assert_eq!(device.query_cap().err(), Some(EINVAL));
+}
+
+#[test]
+fn format_round_trip() {
+    let mut fake = FakeDevice::new();
+    fake.field = Some(v4l2::V4L2_FIELD_INTERLACED);
+    let mut device = open(fake);
+
+    let request = PixFormat::new(device::VideoCapture, 640, 480, Fourcc(v4l2::V4L2_PIX_FMT_YUYV),
+                                 device::AnyField);
+    let format = device.set_format(&request).unwrap();
+    assert_eq!((format.width, format.height), (640, 480));
+    assert_eq!(format.pixelformat, Fourcc(v4l2::V4L2_PIX_FMT_YUYV));
+    assert_eq!(format.field, device::Interlaced);
+    assert_eq!(format.planes.len(), 1);
+    assert_eq!(format.planes.get(0).bytesperline, 1280);
+    assert_eq!(format.planes.get(0).sizeimage, 1280 * 480);
+
+    let current = device.get_format(device::VideoCapture).unwrap();
+    assert_eq!(current.planes.get(0).sizeimage, 1280 * 480);
+}
+
+#[test]
+fn mplane_format() {
+    let mut device = open(FakeDevice::new());
+    let request = PixFormat::new(device::VideoCaptureMplane, 640, 480, Fourcc(v4l2::V4L2_PIX_FMT_NV12M),
+                                 device::Progressive);
+    let format = device.try_format(&request).unwrap();
+    assert_eq!(format.planes.len(), 2);
+    assert_eq!(format.planes.get(0).sizeimage, 640 * 480);
+    assert_eq!(format.planes.get(1).sizeimage, 640 * 240);
+}
+
+#[test]
+fn buffer_round_trip() {
+    let mut device = open(FakeDevice::new());
+    let request = PixFormat::new(device::VideoCapture, 640, 480, Fourcc(v4l2::V4L2_PIX_FMT_YUYV),
+                                 device::Progressive);
+    device.set_format(&request).unwrap();
+    assert_eq!(device.req_bufs(device::VideoCapture, device::Mmap, 4).unwrap(), 4);
+
+    let buffer = device.query_buf(device::VideoCapture, device::Mmap, 2, 1).unwrap();
+    assert_eq!(buffer.planes.get(0).length, 1280 * 480);
+    assert_eq!(buffer.planes.get(0).location, device::MmapOffset(0x200000));
+
+    device.qbuf(&buffer).unwrap();
+    assert_eq!(device.dqbuf(device::VideoCapture, device::Mmap, 1).err(), Some(EINVAL));
+    device.stream_on(device::VideoCapture).unwrap();
+
+    let done = device.dqbuf(device::VideoCapture, device::Mmap, 1).unwrap();
+    assert_eq!(done.index, 2);
+    assert_eq!(done.sequence, 0);
+    assert_eq!(done.field, device::Progressive);
+    assert_eq!(done.planes.get(0).bytesused, 1280 * 480);
+    assert_eq!(device.dqbuf(device::VideoCapture, device::Mmap, 1).err(), Some(EAGAIN));
+}
+
+#[test]
+fn too_many_planes() {
+    let mut device = open(FakeDevice::new());
+    let planes = v4l2::VIDEO_MAX_PLANES as uint + 1;
+    assert_eq!(device.query_buf(device::VideoCaptureMplane, device::Mmap, 0, planes).err(), Some(EINVAL));
+}
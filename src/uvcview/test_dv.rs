@@ -0,0 +1,63 @@
+use dv;
+use dv::{Capability,Timings};
+use fakedev;
+use v4l2;
+
+fn capability() -> Capability {
+    Capability {
+        min_width: 640,
+        max_width: 1920,
+        min_height: 480,
+        max_height: 1080,
+        min_pixelclock: 25000000,
+        max_pixelclock: 165000000,
+        capabilities: v4l2::V4L2_DV_BT_CAP_PROGRESSIVE,
+    }
+}
+
+#[test]
+fn requests() {
+    assert_eq!(dv::request_from_str("auto"), Some(dv::Detect));
+    assert_eq!(dv::request_from_str("3"), Some(dv::Index(3)));
+    assert_eq!(dv::request_from_str("1080p"), None);
+}
+
+#[test]
+fn cea_1080p60() {
+    let timings = Timings::from_v4l2(fakedev::bt_timings(1920, 1080)).unwrap();
+    assert_eq!((timings.width, timings.height), (1920, 1080));
+    assert_eq!((timings.total_width, timings.total_height), (2200, 1125));
+    assert_eq!(timings.pixelclock, 148500000);
+    assert_eq!(timings.frame_rate(), 60.0);
+    assert_eq!(format!("{}", timings).as_slice(), "1920x1080p60.00");
+}
+
+#[test]
+fn unknown_timing_type() {
+    let mut raw = fakedev::bt_timings(1920, 1080);
+    raw._type = 1;
+    assert!(Timings::from_v4l2(raw).is_none());
+}
+
+#[test]
+fn same_mode() {
+    let a = Timings::from_v4l2(fakedev::bt_timings(1280, 720)).unwrap();
+    let b = Timings::from_v4l2(fakedev::bt_timings(1280, 720)).unwrap();
+    let c = Timings::from_v4l2(fakedev::bt_timings(1920, 1080)).unwrap();
+    assert!(a.same_mode(&b));
+    assert!(!a.same_mode(&c));
+}
+
+#[test]
+fn capability_limits() {
+    let cap = capability();
+    assert!(cap.supports(&Timings::from_v4l2(fakedev::bt_timings(1920, 1080)).unwrap()));
+    assert!(!cap.supports(&Timings::from_v4l2(fakedev::bt_timings(3840, 2160)).unwrap()));
+    assert!(!cap.supports(&Timings::from_v4l2(fakedev::bt_timings(320, 240)).unwrap()));
+
+    let mut raw = fakedev::bt_timings(1920, 1080);
+    unsafe {
+        (*raw.bt()).interlaced = v4l2::V4L2_DV_INTERLACED;
+    }
+    assert!(!cap.supports(&Timings::from_v4l2(raw).unwrap()));
+}
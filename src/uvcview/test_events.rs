@@ -0,0 +1,91 @@
+use controls::Control;
+use events;
+use std::default::Default;
+use v4l2;
+
+fn ctrl_event(id: u32, kind: u32, changes: u32) -> v4l2::v4l2_event {
+    let mut event: v4l2::v4l2_event = Default::default();
+    event._type = v4l2::V4L2_EVENT_CTRL;
+    event.id = id;
+    let ctrl = unsafe { &mut *event.u.ctrl() };
+    ctrl._type = kind;
+    ctrl.changes = changes;
+    ctrl.minimum = 1;
+    ctrl.maximum = 100;
+    ctrl.step = 1;
+    ctrl.default_value = 50;
+    event
+}
+
+#[test]
+fn control_event() {
+    let mut event = ctrl_event(v4l2::V4L2_CID_GAIN, v4l2::V4L2_CTRL_TYPE_INTEGER, v4l2::V4L2_EVENT_CTRL_CH_VALUE);
+    unsafe {
+        *(*event.u.ctrl()).value() = -7;
+    }
+    match events::from_v4l2(&event) {
+        Some(events::Control(change)) => {
+            assert_eq!(change.id, v4l2::V4L2_CID_GAIN);
+            assert_eq!(change.value, -7);
+            assert_eq!((change.minimum, change.maximum), (1, 100));
+        }
+        other => fail!("{}", other)
+    }
+
+    let mut event = ctrl_event(v4l2::V4L2_CID_GAIN, v4l2::V4L2_CTRL_TYPE_INTEGER64, v4l2::V4L2_EVENT_CTRL_CH_VALUE);
+    unsafe {
+        *(*event.u.ctrl()).value64() = 1 << 40;
+    }
+    match events::from_v4l2(&event) {
+        Some(events::Control(change)) => assert_eq!(change.value, 1 << 40),
+        other => fail!("{}", other)
+    }
+}
+
+#[test]
+fn stream_events() {
+    let mut event: v4l2::v4l2_event = Default::default();
+    event._type = v4l2::V4L2_EVENT_FRAME_SYNC;
+    unsafe {
+        (*event.u.frame_sync()).frame_sequence = 42;
+    }
+    assert_eq!(events::from_v4l2(&event), Some(events::FrameSync(42)));
+
+    event._type = v4l2::V4L2_EVENT_EOS;
+    assert_eq!(events::from_v4l2(&event), Some(events::EndOfStream));
+
+    event._type = v4l2::V4L2_EVENT_SOURCE_CHANGE;
+    event.id = 1;
+    unsafe {
+        (*event.u.src_change()).changes = v4l2::V4L2_EVENT_SRC_CH_RESOLUTION;
+    }
+    assert_eq!(events::from_v4l2(&event), Some(events::SourceChange(1, v4l2::V4L2_EVENT_SRC_CH_RESOLUTION)));
+
+    event._type = v4l2::V4L2_EVENT_VSYNC;
+    assert_eq!(events::from_v4l2(&event), None);
+}
+
+#[test]
+fn control_update() {
+    let mut control = Control {
+        id: v4l2::V4L2_CID_GAIN,
+        name: "Gain".to_owned(),
+        kind: v4l2::V4L2_CTRL_TYPE_INTEGER,
+        minimum: 0,
+        maximum: 10,
+        step: 1,
+        default_value: 5,
+        flags: 0,
+        value: 5,
+    };
+    let event = ctrl_event(v4l2::V4L2_CID_GAIN, v4l2::V4L2_CTRL_TYPE_INTEGER,
+                           v4l2::V4L2_EVENT_CTRL_CH_RANGE | v4l2::V4L2_EVENT_CTRL_CH_FLAGS);
+    let change = match events::from_v4l2(&event) {
+        Some(events::Control(change)) => change,
+        other => fail!("{}", other)
+    };
+    control.update(&change);
+    assert_eq!((control.minimum, control.maximum, control.default_value), (1, 100, 50));
+    // The value did not change, whatever the event carries.
+    assert_eq!(control.value, 5);
+}
@@ -0,0 +1,49 @@
+use guides;
+use guides::Guides;
+use overlay::Canvas;
+use pixfmt::Frame;
+use scale::Rect;
+use test_common::grey_frame;
+use v4l2;
+
+#[test]
+fn roi_covers_whole_frame() {
+    let stats = guides::measure(&grey_frame(), Rect { x: 0, y: 0, width: 2, height: 2 }).unwrap();
+    assert_eq!(stats.pixels, 4);
+    assert_eq!(stats.luma.mean, 138.75);
+    assert_eq!(stats.luma.min, 0);
+    assert_eq!(stats.luma.max, 255);
+}
+
+#[test]
+fn roi_is_clipped_to_frame() {
+    let stats = guides::measure(&grey_frame(), Rect { x: 1, y: 0, width: 5, height: 5 }).unwrap();
+    assert_eq!(stats.pixels, 2);
+    assert_eq!(stats.luma.mean, 177.5);
+    assert_eq!(stats.luma.stddev, 77.5);
+    assert!(guides::measure(&grey_frame(), Rect { x: 2, y: 0, width: 1, height: 1 }).is_none());
+}
+
+#[test]
+fn roi_measures_each_channel() {
+    static RGB: [u8, ..6] = [200, 10, 0, 100, 30, 0];
+    let frame = Frame::new(v4l2::V4L2_PIX_FMT_RGB24, 2, 1, 6, RGB.as_slice());
+    let stats = guides::measure(&frame, Rect { x: 0, y: 0, width: 2, height: 1 }).unwrap();
+    assert_eq!((stats.red.mean, stats.red.min, stats.red.max), (150.0, 100, 200));
+    assert_eq!((stats.green.mean, stats.green.stddev), (20.0, 10.0));
+    assert_eq!((stats.blue.min, stats.blue.max, stats.blue.stddev), (0, 0, 0.0));
+}
+
+#[test]
+fn thirds_split_the_image() {
+    let mut pixels = Vec::from_elem(8 * 7 * 3, 0u8);
+    let mut canvas = Canvas::new(pixels.as_mut_slice(), 8 * 3, 8, 7);
+    let mut guides = Guides::new();
+    guides.thirds = true;
+    guides.draw(&mut canvas, Rect { x: 1, y: 0, width: 6, height: 6 });
+    assert_eq!(canvas.get(3, 1), (200, 200, 200));
+    assert_eq!(canvas.get(5, 5), (200, 200, 200));
+    assert_eq!(canvas.get(2, 2), (200, 200, 200));
+    assert_eq!(canvas.get(2, 1), (0, 0, 0));
+    assert_eq!(canvas.get(0, 2), (0, 0, 0));
+}
@@ -0,0 +1,856 @@
+use fakedev;
+use fakedev::FakeDevice;
+use libc::c_int;
+use libc::consts::os::posix88::{EINVAL,EIO,EAGAIN,EBUSY,ENOTTY};
+use std::io;
+use std::io::IoError;
+use std::os;
+use device;
+use device::Fourcc;
+use dv;
+use events;
+use scale::Rect;
+use test_common::viewer;
+use uvcview::{UvcView,IoRead,IoUserPtr,IoDmaBuf};
+use v4l2;
+
+fn init(uvcview: &mut UvcView) -> Result<(), IoError> {
+    uvcview.init().map(|_| ())
+}
+
+fn init_err(uvcview: &mut UvcView) -> IoError {
+    match init(uvcview) {
+        Ok(_) => fail!("init() succeeded"),
+        Err(e) => e
+    }
+}
+
+#[test]
+fn init_maps_requested_buffers() {
+    let mut uvcview = viewer(FakeDevice::new());
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.buffers.len(), 4);
+    assert_eq!(uvcview.width, 1280);
+    assert_eq!(uvcview.height, 720);
+    assert_eq!(uvcview.bytesperline, 1280 * 2);
+    assert_eq!(uvcview.sizeimage, 1280 * 2 * 720);
+}
+
+#[test]
+fn init_reports_card_and_frame_rate() {
+    let mut uvcview = viewer(FakeDevice::new());
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.card.as_slice(), fakedev::CARD);
+    assert_eq!(uvcview.frame_interval, Some((1, 30)));
+}
+
+#[test]
+fn g_parm_errors_are_ignored() {
+    let mut uvcview = viewer(FakeDevice::new().fail(v4l2::VIDIOC_G_PARM, EINVAL));
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.frame_interval, None);
+}
+
+#[test]
+fn querycap_einval_is_not_v4l2() {
+    let mut uvcview = viewer(FakeDevice::new().fail(v4l2::VIDIOC_QUERYCAP, EINVAL));
+    let e = init_err(&mut uvcview);
+    assert_eq!(e.desc, "init(): VIDIOC_QUERYCAP not supported");
+}
+
+#[test]
+fn querycap_other_error() {
+    let mut uvcview = viewer(FakeDevice::new().fail(v4l2::VIDIOC_QUERYCAP, EIO));
+    let e = init_err(&mut uvcview);
+    assert_eq!(e.desc, "init(): ioctl() returns -1");
+}
+
+#[test]
+fn requires_video_capture() {
+    let mut device = FakeDevice::new();
+    device.capabilities = v4l2::V4L2_CAP_STREAMING;
+    let mut uvcview = viewer(device);
+    let e = init_err(&mut uvcview);
+    assert_eq!(e.desc, "init(): V4L2_CAP_VIDEO_CAPTURE not supported");
+}
+
+#[test]
+fn requires_streaming() {
+    let mut device = FakeDevice::new();
+    device.capabilities = v4l2::V4L2_CAP_VIDEO_CAPTURE;
+    let mut uvcview = viewer(device);
+    let e = init_err(&mut uvcview);
+    assert_eq!(e.desc, "init(): V4L2_CAP_STREAMING not supported");
+}
+
+#[test]
+fn cropcap_einval_is_ignored() {
+    let mut uvcview = viewer(FakeDevice::new().fail(v4l2::VIDIOC_CROPCAP, EINVAL));
+    assert!(init(&mut uvcview).is_ok());
+}
+
+#[test]
+fn s_crop_errors_are_ignored() {
+    let mut uvcview = viewer(FakeDevice::new().fail(v4l2::VIDIOC_S_CROP, EINVAL));
+    assert!(init(&mut uvcview).is_ok());
+    let mut uvcview = viewer(FakeDevice::new().fail(v4l2::VIDIOC_S_CROP, EBUSY));
+    assert!(init(&mut uvcview).is_ok());
+}
+
+#[test]
+fn init_resets_crop_to_default() {
+    let mut uvcview = viewer(FakeDevice::new());
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.crop_rect, Some(Rect { x: 0, y: 0, width: 1280, height: 720 }));
+    assert_eq!(uvcview.pixel_aspect, Some((1, 1)));
+}
+
+#[test]
+fn crop_option_is_adjusted_by_driver() {
+    let mut uvcview = viewer(FakeDevice::new());
+    uvcview.crop = Some(Rect { x: 101, y: 50, width: 641, height: 360 });
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.crop_rect, Some(Rect { x: 101, y: 50, width: 640, height: 360 }));
+}
+
+#[test]
+fn crop_falls_back_to_s_crop() {
+    let mut uvcview = viewer(FakeDevice::new().fail(v4l2::VIDIOC_S_SELECTION, ENOTTY));
+    uvcview.crop = Some(Rect { x: 1200, y: 0, width: 200, height: 99 });
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.crop_rect, Some(Rect { x: 1200, y: 0, width: 80, height: 98 }));
+}
+
+#[test]
+fn requested_crop_failure() {
+    let mut uvcview = viewer(FakeDevice::new().fail(v4l2::VIDIOC_S_SELECTION, EBUSY));
+    uvcview.crop = Some(Rect { x: 0, y: 0, width: 640, height: 360 });
+    let e = init_err(&mut uvcview);
+    assert_eq!(e.desc, "set_crop(): cropping failed");
+
+    let mut uvcview = viewer(FakeDevice::new().fail(v4l2::VIDIOC_S_SELECTION, EBUSY));
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.crop_rect, None);
+}
+
+#[test]
+fn rubber_band_crops_visible_area() {
+    let mut uvcview = viewer(FakeDevice::new());
+    assert!(init(&mut uvcview).is_ok());
+    uvcview.begin_selection(300, 200);
+    uvcview.update_selection(100, 100);
+    assert!(uvcview.end_selection().is_ok());
+    assert_eq!(uvcview.selection, None);
+    assert_eq!(uvcview.crop_rect, Some(Rect { x: 100, y: 100, width: 200, height: 100 }));
+
+    // The frame now shows the crop rectangle scaled up to 1280x720.
+    uvcview.begin_selection(0, 0);
+    uvcview.update_selection(640, 360);
+    assert!(uvcview.end_selection().is_ok());
+    assert_eq!(uvcview.crop_rect, Some(Rect { x: 100, y: 100, width: 100, height: 50 }));
+
+    // A click is not a selection.
+    uvcview.begin_selection(10, 10);
+    assert!(uvcview.end_selection().is_ok());
+    assert_eq!(uvcview.crop_rect, Some(Rect { x: 100, y: 100, width: 100, height: 50 }));
+
+    assert!(uvcview.reset_crop().is_ok());
+    assert_eq!(uvcview.crop_rect, Some(Rect { x: 0, y: 0, width: 1280, height: 720 }));
+}
+
+#[test]
+fn crop_renegotiates_stream() {
+    let mut device = FakeDevice::new();
+    device.fixed_scale = true;
+    let mut uvcview = viewer(device);
+    assert!(init(&mut uvcview).is_ok());
+    uvcview.start_capturing();
+    assert!(uvcview.read_frame());
+
+    uvcview.zoom = 2.0;
+    let rect = Rect { x: 100, y: 100, width: 640, height: 360 };
+    assert_eq!(uvcview.set_crop(rect).unwrap(), rect);
+    assert_eq!((uvcview.width, uvcview.height), (640, 360));
+    assert_eq!(uvcview.sizeimage, 640 * 2 * 360);
+    assert_eq!(uvcview.buffers.get(0).planes.get(0).length, 640 * 2 * 360);
+    assert_eq!(uvcview.zoom, 1.0);
+    assert!(uvcview.streaming);
+    assert!(uvcview.read_frame());
+
+    assert!(uvcview.reset_crop().is_ok());
+    assert_eq!(uvcview.crop, None);
+    assert_eq!((uvcview.width, uvcview.height), (1280, 720));
+    assert!(uvcview.read_frame());
+}
+
+#[test]
+fn s_fmt_failure() {
+    let mut uvcview = viewer(FakeDevice::new().fail(v4l2::VIDIOC_S_FMT, EBUSY));
+    let e = init_err(&mut uvcview);
+    assert_eq!(e.desc, "init(): ioctl() returns -1");
+}
+
+#[test]
+fn s_fmt_changes_size() {
+    let mut device = FakeDevice::new();
+    device.size = Some((640, 480));
+    let mut uvcview = viewer(device);
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.width, 640);
+    assert_eq!(uvcview.height, 480);
+    assert_eq!(uvcview.sizeimage, 640 * 2 * 480);
+}
+
+#[test]
+fn field_order_is_negotiated() {
+    let mut uvcview = viewer(FakeDevice::new());
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.field, device::Progressive);
+
+    let mut device = FakeDevice::new();
+    device.field = Some(v4l2::V4L2_FIELD_SEQ_BT);
+    let mut uvcview = viewer(device);
+    uvcview.field = device::Interlaced;
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.field, device::SeqBt);
+}
+
+// An analogue grabber with a composite and a tuner input.
+fn grabber() -> FakeDevice {
+    let mut device = FakeDevice::new();
+    device.inputs = vec!(("Composite", false), ("Television", true));
+    device.standards = vec!((v4l2::V4L2_STD_PAL, "PAL"), (v4l2::V4L2_STD_NTSC, "NTSC"));
+    device.detected_std = v4l2::V4L2_STD_NTSC_M;
+    device
+}
+
+#[test]
+fn webcam_has_no_standard() {
+    let mut uvcview = viewer(FakeDevice::new());
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.input, Some(0));
+    assert_eq!(uvcview.std, None);
+    assert_eq!(uvcview.enum_standards().len(), 0);
+}
+
+#[test]
+fn input_is_selected() {
+    let mut uvcview = viewer(grabber());
+    uvcview.input = Some(1);
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.input, Some(1));
+    let inputs = uvcview.enum_inputs();
+    assert_eq!(inputs.len(), 2);
+    assert_eq!(inputs.get(1).name.as_slice(), "Television");
+    assert_eq!(inputs.get(1).tuner, Some(0));
+    assert_eq!(inputs.get(0).tuner, None);
+
+    let mut uvcview = viewer(grabber());
+    uvcview.input = Some(2);
+    let e = init_err(&mut uvcview);
+    assert_eq!(e.desc, "set_input(): VIDIOC_S_INPUT failed");
+}
+
+#[test]
+fn standard_is_detected() {
+    let mut uvcview = viewer(grabber());
+    uvcview.std = Some(v4l2::V4L2_STD_UNKNOWN);
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.std, Some(v4l2::V4L2_STD_NTSC));
+    assert_eq!(uvcview.height, 480);
+
+    let mut device = grabber();
+    device.detected_std = v4l2::V4L2_STD_UNKNOWN;
+    let mut uvcview = viewer(device);
+    uvcview.std = Some(v4l2::V4L2_STD_UNKNOWN);
+    let e = init_err(&mut uvcview);
+    assert_eq!(e.desc, "query_std(): no standard detected");
+}
+
+#[test]
+fn standard_change_reconfigures_stream() {
+    let mut uvcview = viewer(grabber());
+    uvcview.std = Some(v4l2::V4L2_STD_PAL_B);
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.std, Some(v4l2::V4L2_STD_PAL));
+    assert_eq!((uvcview.width, uvcview.height), (720, 576));
+    assert_eq!(uvcview.enum_standards().get(1).frame_lines, 525);
+
+    uvcview.start_capturing();
+    assert!(uvcview.read_frame());
+    uvcview.zoom = 2.0;
+    uvcview.pan = (100.0, 100.0);
+    assert!(uvcview.set_std(v4l2::V4L2_STD_NTSC).is_ok());
+    assert_eq!((uvcview.width, uvcview.height), (720, 480));
+    assert_eq!(uvcview.zoom, 1.0);
+    assert_eq!(uvcview.pan, (0.0, 0.0));
+    assert_eq!(uvcview.sizeimage, 720 * 2 * 480);
+    assert_eq!(uvcview.buffers.len(), 4);
+    assert!(uvcview.streaming);
+    assert!(uvcview.read_frame());
+
+    assert!(uvcview.set_std(v4l2::V4L2_STD_SECAM).is_err());
+    assert_eq!(uvcview.std, Some(v4l2::V4L2_STD_NTSC));
+    assert!(uvcview.streaming);
+    assert!(uvcview.read_frame());
+}
+
+#[test]
+fn frequency_is_tuned() {
+    let mut uvcview = viewer(grabber());
+    uvcview.input = Some(1);
+    uvcview.frequency = Some(175.25);
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.frequency, Some(175.25));
+
+    let e = uvcview.set_frequency(1000.0).unwrap_err();
+    assert_eq!(e.desc, "set_frequency(): frequency out of range");
+    assert_eq!(uvcview.frequency, Some(175.25));
+
+    assert!(uvcview.set_input(0).is_ok());
+    let e = uvcview.set_frequency(175.25).unwrap_err();
+    assert_eq!(e.desc, "set_frequency(): input has no tuner");
+}
+
+// An HDMI receiver with a 720p source.
+fn receiver() -> FakeDevice {
+    let mut device = FakeDevice::new();
+    device.dv_timings = vec!((1280, 720), (1920, 1080));
+    device.dv_signal = Some((1280, 720));
+    device
+}
+
+#[test]
+fn dv_timings_are_kept_by_default() {
+    let mut device = receiver();
+    device.dv_timings = vec!((1920, 1080));
+    let mut uvcview = viewer(device);
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!((uvcview.width, uvcview.height), (1920, 1080));
+    assert_eq!(uvcview.dv_timings.get_ref().frame_rate(), 60.0);
+}
+
+#[test]
+fn dv_timings_are_detected() {
+    let mut uvcview = viewer(receiver());
+    uvcview.width = 640;
+    uvcview.height = 480;
+    uvcview.dv_request = Some(dv::Detect);
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!((uvcview.width, uvcview.height), (1280, 720));
+    assert_eq!(uvcview.sizeimage, 1280 * 2 * 720);
+
+    let mut device = receiver();
+    device.dv_signal = None;
+    let mut uvcview = viewer(device);
+    uvcview.dv_request = Some(dv::Detect);
+    let e = init_err(&mut uvcview);
+    assert_eq!(e.desc, "query_dv_timings(): no signal");
+
+    let mut device = receiver();
+    device.dv_signal = Some((3840, 2160));
+    let mut uvcview = viewer(device);
+    uvcview.dv_request = Some(dv::Detect);
+    let e = init_err(&mut uvcview);
+    assert_eq!(e.desc, "set_dv_timings(): timings not supported");
+}
+
+#[test]
+fn dv_timings_are_picked_from_list() {
+    let mut uvcview = viewer(receiver());
+    uvcview.dv_request = Some(dv::Index(1));
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!((uvcview.width, uvcview.height), (1920, 1080));
+    assert_eq!(uvcview.enum_dv_timings().len(), 2);
+
+    let mut uvcview = viewer(receiver());
+    uvcview.dv_request = Some(dv::Index(2));
+    let e = init_err(&mut uvcview);
+    assert_eq!(e.desc, "set_dv_timings(): no such timings");
+}
+
+#[test]
+fn source_change_reconfigures_stream() {
+    let mut device = receiver();
+    device.source_change = Some((3, (1920, 1080)));
+    let mut uvcview = viewer(device);
+    uvcview.dv_request = Some(dv::Detect);
+    assert!(init(&mut uvcview).is_ok());
+    uvcview.start_capturing();
+    for _ in range(0, 3) {
+        assert!(uvcview.read_frame());
+        assert_eq!(uvcview.width, 1280);
+    }
+    let mut changed = None;
+    assert!(uvcview.dequeue_events(|uvcview, event| {
+        match *event {
+            events::SourceChange(input, changes) => changed = Some((input, changes, uvcview.width)),
+            _ => {}
+        }
+    }).is_ok());
+    assert_eq!(changed, Some((0, v4l2::V4L2_EVENT_SRC_CH_RESOLUTION, 1920)));
+    assert_eq!((uvcview.width, uvcview.height), (1920, 1080));
+    assert_eq!(uvcview.sizeimage, 1920 * 2 * 1080);
+    assert_eq!(uvcview.buffers.len(), 4);
+    assert!(uvcview.streaming);
+    assert!(uvcview.read_frame());
+    assert!(uvcview.dequeue_events(|_, _| {}).is_ok());
+}
+
+#[test]
+fn webcam_has_no_dv_timings() {
+    let mut uvcview = viewer(FakeDevice::new());
+    uvcview.dv_request = Some(dv::Detect);
+    assert!(init(&mut uvcview).is_ok());
+    assert!(uvcview.dv_timings.is_none());
+    assert!(uvcview.detect_dv_timings().is_err());
+}
+
+#[test]
+fn controls_are_enumerated() {
+    let mut uvcview = viewer(FakeDevice::new());
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.controls.len(), 2);
+    let exposure = uvcview.controls.get(1);
+    assert_eq!(exposure.id, v4l2::V4L2_CID_EXPOSURE_ABSOLUTE);
+    assert_eq!(exposure.name.as_slice(), "Exposure (Absolute)");
+    assert_eq!((exposure.minimum, exposure.maximum, exposure.value), (3, 2047, 250));
+}
+
+#[test]
+fn control_changes_are_delivered() {
+    let mut device = FakeDevice::new();
+    device.control_changes = vec!((2, v4l2::V4L2_CID_EXPOSURE_ABSOLUTE, 500));
+    let mut uvcview = viewer(device);
+    assert!(init(&mut uvcview).is_ok());
+    uvcview.start_capturing();
+    assert!(uvcview.read_frame());
+    assert!(uvcview.read_frame());
+
+    let mut changes = vec!();
+    assert!(uvcview.dequeue_events(|uvcview, event| {
+        match *event {
+            events::Control(ref change) => {
+                changes.push((change.id, change.value, uvcview.controls.get(1).value));
+            }
+            _ => {}
+        }
+    }).is_ok());
+    assert_eq!(changes, vec!((v4l2::V4L2_CID_EXPOSURE_ABSOLUTE, 500, 500)));
+}
+
+#[test]
+fn stream_events_are_delivered() {
+    let mut device = FakeDevice::new();
+    device.eos_after = Some(2);
+    let mut uvcview = viewer(device);
+    assert!(init(&mut uvcview).is_ok());
+    uvcview.start_capturing();
+    assert!(uvcview.read_frame());
+    assert!(uvcview.read_frame());
+
+    let mut received = vec!();
+    assert!(uvcview.dequeue_events(|_, event| received.push(event.clone())).is_ok());
+    assert_eq!(received, vec!(events::FrameSync(0), events::FrameSync(1), events::EndOfStream));
+
+    let mut received = vec!();
+    assert!(uvcview.dequeue_events(|_, event| received.push(event.clone())).is_ok());
+    assert!(received.is_empty());
+}
+
+#[test]
+fn controls_are_set() {
+    let mut uvcview = viewer(FakeDevice::new());
+    assert!(init(&mut uvcview).is_ok());
+    assert!(uvcview.set_control(v4l2::V4L2_CID_BRIGHTNESS, 300).is_ok());
+    assert_eq!(uvcview.controls.get(0).value, 255);
+    assert!(uvcview.set_control(0, 1).is_err());
+}
+
+#[test]
+fn busy_device_is_reported() {
+    let mut device = FakeDevice::new();
+    device.other_streaming = true;
+    let mut uvcview = viewer(device);
+    let e = init_err(&mut uvcview);
+    assert_eq!(e.kind, io::ResourceUnavailable);
+    let detail = e.detail.unwrap();
+    assert!(detail.as_slice().contains("/dev/video0 is busy: another application is streaming"));
+    assert!(!detail.as_slice().contains("--monitor"));
+
+    let mut device = FakeDevice::new();
+    device.other_priority = v4l2::V4L2_PRIORITY_RECORD;
+    let mut uvcview = viewer(device);
+    let e = init_err(&mut uvcview);
+    assert_eq!(e.kind, io::ResourceUnavailable);
+    assert_eq!(e.desc, "init(): ioctl() returns -1");
+    assert!(e.detail.unwrap().as_slice().contains("holds record priority (try --monitor)"));
+
+    let mut uvcview = viewer(FakeDevice::new().fail(v4l2::VIDIOC_S_FMT, EIO));
+    assert_eq!(init_err(&mut uvcview).kind, io::OtherIoError);
+}
+
+#[test]
+fn priority_is_claimed() {
+    let mut uvcview = viewer(FakeDevice::new());
+    uvcview.priority = Some(v4l2::V4L2_PRIORITY_RECORD);
+    assert!(init(&mut uvcview).is_ok());
+
+    let mut device = FakeDevice::new();
+    device.other_priority = v4l2::V4L2_PRIORITY_RECORD;
+    let mut uvcview = viewer(device);
+    uvcview.priority = Some(v4l2::V4L2_PRIORITY_RECORD);
+    let e = init_err(&mut uvcview);
+    assert_eq!(e.desc, "set_priority(): VIDIOC_S_PRIORITY failed");
+    assert_eq!(e.kind, io::ResourceUnavailable);
+}
+
+#[test]
+fn monitor_changes_nothing() {
+    let mut device = FakeDevice::new();
+    device.other_priority = v4l2::V4L2_PRIORITY_RECORD;
+    let mut uvcview = viewer(device);
+    uvcview.monitor = true;
+    uvcview.width = 320;
+    uvcview.height = 240;
+    uvcview.pixelformat = Fourcc(v4l2::V4L2_PIX_FMT_MJPEG);
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.priority, Some(v4l2::V4L2_PRIORITY_BACKGROUND));
+    assert_eq!((uvcview.width, uvcview.height), (1280, 720));
+    assert_eq!(uvcview.pixelformat, Fourcc(v4l2::V4L2_PIX_FMT_YUYV));
+    assert_eq!(uvcview.crop_rect, Some(Rect { x: 0, y: 0, width: 1280, height: 720 }));
+
+    uvcview.start_capturing();
+    assert!(uvcview.read_frame());
+    assert!(uvcview.set_crop(Rect { x: 0, y: 0, width: 640, height: 360 }).is_err());
+    let e = uvcview.set_control(v4l2::V4L2_CID_BRIGHTNESS, 10).unwrap_err();
+    assert_eq!(e.kind, io::PermissionDenied);
+    assert!(uvcview.set_input(0).is_err());
+    assert_eq!(uvcview.controls.get(0).value, 128);
+}
+
+#[test]
+fn s_fmt_unsupported_format() {
+    let mut device = FakeDevice::new();
+    device.pixelformat = Some(v4l2::V4L2_PIX_FMT_MJPEG);
+    let mut uvcview = viewer(device);
+    let e = init_err(&mut uvcview);
+    assert_eq!(e.desc, "init(): unsupported pixel format");
+}
+
+#[test]
+fn undersized_bytesperline_is_fixed() {
+    let mut device = FakeDevice::new();
+    device.bytesperline = Some(100);
+    device.sizeimage = Some(0);
+    let mut uvcview = viewer(device);
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.bytesperline, 1280 * 2);
+    assert_eq!(uvcview.sizeimage, 1280 * 2 * 720);
+}
+
+#[test]
+fn padded_bytesperline_is_kept() {
+    let mut device = FakeDevice::new();
+    device.bytesperline = Some(4096);
+    let mut uvcview = viewer(device);
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.bytesperline, 4096);
+    assert_eq!(uvcview.sizeimage, 4096 * 720);
+}
+
+#[test]
+fn reqbufs_einval_is_no_mmap() {
+    let mut uvcview = viewer(FakeDevice::new().fail(v4l2::VIDIOC_REQBUFS, EINVAL));
+    let e = init_err(&mut uvcview);
+    assert!(e.detail.unwrap().contains("does not support memory mapping"));
+}
+
+#[test]
+fn reqbufs_other_error() {
+    let mut uvcview = viewer(FakeDevice::new().fail(v4l2::VIDIOC_REQBUFS, EBUSY));
+    let e = init_err(&mut uvcview);
+    assert!(!e.detail.unwrap().contains("does not support memory mapping"));
+}
+
+#[test]
+fn reqbufs_too_few_buffers() {
+    let mut device = FakeDevice::new();
+    device.max_buffers = 1;
+    let mut uvcview = viewer(device);
+    let e = init_err(&mut uvcview);
+    assert!(e.detail.unwrap().contains("Insufficient buffer memory"));
+}
+
+#[test]
+fn reqbufs_two_buffers_is_enough() {
+    let mut device = FakeDevice::new();
+    device.max_buffers = 2;
+    let mut uvcview = viewer(device);
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.buffers.len(), 2);
+}
+
+#[test]
+fn buffer_count_is_requested() {
+    let mut uvcview = viewer(FakeDevice::new());
+    uvcview.buffer_count = 8;
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.buffers.len(), 8);
+}
+
+#[test]
+fn create_buffers_adds_larger_buffers() {
+    let mut uvcview = viewer(FakeDevice::new());
+    assert!(init(&mut uvcview).is_ok());
+    uvcview.start_capturing();
+    assert_eq!(uvcview.create_buffers(2, 1920, 1080).unwrap(), 2);
+    assert_eq!(uvcview.buffers.len(), 6);
+    assert_eq!(uvcview.buffers.get(4).planes.get(0).length, 1920 * 2 * 1080);
+    assert_eq!(uvcview.buffers.get(0).planes.get(0).length, 1280 * 2 * 720);
+    for _ in range(0, 10) {
+        assert!(uvcview.read_frame());
+    }
+}
+
+#[test]
+fn create_buffers_beyond_the_maximum() {
+    let mut device = FakeDevice::new();
+    device.max_buffers = 4;
+    let mut uvcview = viewer(device);
+    uvcview.buffer_count = 8;
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.buffers.len(), 4);
+    match uvcview.create_buffers(2, 1920, 1080) {
+        Ok(_) => fail!("create_buffers() succeeded"),
+        Err(e) => assert_eq!(e.desc, "VIDIOC_CREATE_BUFS failed")
+    }
+    assert_eq!(uvcview.buffers.len(), 4);
+}
+
+#[test]
+fn create_buffers_prepare_failure() {
+    let mut uvcview = viewer(FakeDevice::new().fail(v4l2::VIDIOC_PREPARE_BUF, EINVAL));
+    assert!(init(&mut uvcview).is_ok());
+    match uvcview.create_buffers(2, 1920, 1080) {
+        Ok(_) => fail!("create_buffers() succeeded"),
+        Err(e) => assert_eq!(e.desc, "VIDIOC_PREPARE_BUF failed")
+    }
+}
+
+#[test]
+fn querybuf_failure() {
+    let mut uvcview = viewer(FakeDevice::new().fail(v4l2::VIDIOC_QUERYBUF, EINVAL));
+    let e = init_err(&mut uvcview);
+    assert_eq!(e.desc, "init() error");
+}
+
+#[test]
+fn read_frame_dequeues_and_requeues() {
+    let mut uvcview = viewer(FakeDevice::new());
+    assert!(init(&mut uvcview).is_ok());
+    uvcview.start_capturing();
+    for _ in range(0, 10) {
+        assert!(uvcview.read_frame());
+    }
+    uvcview.stop_capturing();
+}
+
+#[test]
+fn read_frame_eagain() {
+    let mut uvcview = viewer(FakeDevice::new().fail(v4l2::VIDIOC_DQBUF, EAGAIN));
+    assert!(init(&mut uvcview).is_ok());
+    uvcview.start_capturing();
+    assert!(!uvcview.read_frame());
+}
+
+#[test]
+#[should_fail]
+fn read_frame_dqbuf_error() {
+    let mut uvcview = viewer(FakeDevice::new().fail(v4l2::VIDIOC_DQBUF, EIO));
+    assert!(init(&mut uvcview).is_ok());
+    uvcview.start_capturing();
+    uvcview.read_frame();
+}
+
+#[test]
+#[should_fail]
+fn streamon_failure() {
+    let mut uvcview = viewer(FakeDevice::new().fail(v4l2::VIDIOC_STREAMON, EIO));
+    assert!(init(&mut uvcview).is_ok());
+    uvcview.start_capturing();
+}
+
+#[test]
+fn userptr_buffers_are_page_aligned() {
+    let mut uvcview = viewer(FakeDevice::new());
+    uvcview.io_method = IoUserPtr;
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.buffers.len(), 4);
+    for buffer in uvcview.buffers.iter() {
+        let plane = buffer.planes.get(0);
+        assert_eq!(plane.memory_map.data as uint % os::page_size(), 0);
+        assert_eq!(plane.length, uvcview.sizeimage);
+    }
+    uvcview.start_capturing();
+    assert!(uvcview.read_frame());
+}
+
+#[test]
+fn userptr_not_supported() {
+    let mut uvcview = viewer(FakeDevice::new().fail(v4l2::VIDIOC_REQBUFS, EINVAL));
+    uvcview.io_method = IoUserPtr;
+    let e = init_err(&mut uvcview);
+    assert!(e.detail.unwrap().contains("does not support user pointer i/o"));
+}
+
+#[test]
+fn dmabuf_requires_expbuf() {
+    let mut uvcview = viewer(FakeDevice::new().fail(v4l2::VIDIOC_EXPBUF, ENOTTY));
+    uvcview.io_method = IoDmaBuf;
+    let e = init_err(&mut uvcview);
+    assert_eq!(e.desc, "VIDIOC_EXPBUF failed");
+}
+
+#[test]
+fn dmabuf_queues_exported_fds() {
+    let mut uvcview = viewer(FakeDevice::new());
+    uvcview.io_method = IoDmaBuf;
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.buffers.len(), 4);
+    let fds: Vec<c_int> = uvcview.buffers.iter().map(|buffer| buffer.planes.get(0).dmabuf_fd).collect();
+    assert!(fds.iter().all(|&fd| fd >= 0));
+
+    // The driver hands back the dma-buf each buffer was queued with.
+    uvcview.start_capturing();
+    let buffer = uvcview.device.dqbuf(device::VideoCapture, device::DmaBuf, 1).unwrap();
+    assert_eq!(buffer.memory, device::DmaBuf);
+    assert_eq!(buffer.planes.get(0).location, device::DmaBufFd(*fds.get(buffer.index as uint)));
+    uvcview.device.qbuf(&buffer).unwrap();
+    assert!(uvcview.read_frame());
+}
+
+#[test]
+fn read_fallback_without_streaming() {
+    let mut device = FakeDevice::new();
+    device.capabilities = v4l2::V4L2_CAP_VIDEO_CAPTURE | v4l2::V4L2_CAP_READWRITE;
+    let mut uvcview = viewer(device);
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.io_method, IoRead);
+    assert_eq!(uvcview.buffers.len(), 1);
+    assert_eq!(uvcview.buffers.get(0).planes.get(0).length, uvcview.sizeimage);
+    uvcview.start_capturing();
+    assert!(uvcview.read_frame());
+}
+
+#[test]
+fn short_reads_are_errors() {
+    let mut device = FakeDevice::new();
+    device.capabilities = v4l2::V4L2_CAP_VIDEO_CAPTURE | v4l2::V4L2_CAP_READWRITE;
+    device.read_length = Some(1000);
+    let mut uvcview = viewer(device);
+    assert!(init(&mut uvcview).is_ok());
+    uvcview.start_capturing();
+    assert!(uvcview.read_frame());
+    assert_eq!(uvcview.stats.frames, 1);
+    assert_eq!(uvcview.stats.errors, 1);
+}
+
+#[test]
+fn padded_reads_are_complete() {
+    let mut device = FakeDevice::new();
+    device.capabilities = v4l2::V4L2_CAP_VIDEO_CAPTURE | v4l2::V4L2_CAP_READWRITE;
+    device.sizeimage = Some(1280 * 2 * 720 + 4096);
+    device.read_length = Some(1280 * 2 * 720);
+    let mut uvcview = viewer(device);
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.sizeimage, 1280 * 2 * 720 + 4096);
+    uvcview.start_capturing();
+    assert!(uvcview.read_frame());
+    assert_eq!(uvcview.stats.frames, 1);
+    assert_eq!(uvcview.stats.errors, 0);
+}
+
+#[test]
+fn read_requires_readwrite() {
+    let mut uvcview = viewer(FakeDevice::new());
+    uvcview.io_method = IoRead;
+    let e = init_err(&mut uvcview);
+    assert_eq!(e.desc, "init(): V4L2_CAP_READWRITE not supported");
+}
+
+#[test]
+fn read_eagain() {
+    let mut device = FakeDevice::new().fail(fakedev::READ, EAGAIN);
+    device.capabilities = v4l2::V4L2_CAP_VIDEO_CAPTURE | v4l2::V4L2_CAP_READWRITE;
+    let mut uvcview = viewer(device);
+    assert!(init(&mut uvcview).is_ok());
+    assert!(!uvcview.read_frame());
+}
+
+fn mplane_device(pixelformat: u32) -> FakeDevice {
+    let mut device = FakeDevice::new();
+    device.capabilities = v4l2::V4L2_CAP_VIDEO_CAPTURE_MPLANE | v4l2::V4L2_CAP_STREAMING;
+    device.pixelformat = Some(pixelformat);
+    device
+}
+
+#[test]
+fn mplane_nv12m_maps_each_plane() {
+    let mut uvcview = viewer(mplane_device(v4l2::V4L2_PIX_FMT_NV12M));
+    assert!(init(&mut uvcview).is_ok());
+    assert!(uvcview.mplane);
+    assert_eq!(uvcview.plane_fmt.len(), 2);
+    assert_eq!(uvcview.sizeimage, 1280 * 720 * 3 / 2);
+    for buffer in uvcview.buffers.iter() {
+        assert_eq!(buffer.planes.len(), 2);
+        assert_eq!(buffer.planes.get(0).length, 1280 * 720);
+        assert_eq!(buffer.planes.get(1).length, 1280 * 360);
+    }
+    uvcview.start_capturing();
+    for _ in range(0, 10) {
+        assert!(uvcview.read_frame());
+    }
+    uvcview.stop_capturing();
+}
+
+#[test]
+fn mplane_yuv420m_userptr() {
+    let mut uvcview = viewer(mplane_device(v4l2::V4L2_PIX_FMT_YUV420M));
+    uvcview.io_method = IoUserPtr;
+    assert!(init(&mut uvcview).is_ok());
+    for buffer in uvcview.buffers.iter() {
+        assert_eq!(buffer.planes.len(), 3);
+        assert_eq!(buffer.planes.get(1).length, 640 * 360);
+        assert_eq!(buffer.planes.get(2).length, 640 * 360);
+    }
+    uvcview.start_capturing();
+    assert!(uvcview.read_frame());
+}
+
+#[test]
+fn mplane_single_plane_format() {
+    let mut uvcview = viewer(mplane_device(v4l2::V4L2_PIX_FMT_YUYV));
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.buffers.get(0).planes.len(), 1);
+    uvcview.start_capturing();
+    assert!(uvcview.read_frame());
+}
+
+#[test]
+fn mplane_create_buffers() {
+    let mut uvcview = viewer(mplane_device(v4l2::V4L2_PIX_FMT_NV12M));
+    assert!(init(&mut uvcview).is_ok());
+    assert_eq!(uvcview.create_buffers(1, 1920, 1080).unwrap(), 1);
+    assert_eq!(uvcview.buffers.get(4).planes.get(1).length, 1920 * 540);
+}
+
+#[test]
+fn mplane_read_not_supported() {
+    let mut device = mplane_device(v4l2::V4L2_PIX_FMT_NV12M);
+    device.capabilities |= v4l2::V4L2_CAP_READWRITE;
+    let mut uvcview = viewer(device);
+    uvcview.io_method = IoRead;
+    let e = init_err(&mut uvcview);
+    assert_eq!(e.desc, "init(): read i/o not supported on multi-planar devices");
+}
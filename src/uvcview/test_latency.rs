@@ -0,0 +1,169 @@
+use fakedev;
+use fakedev::FakeDevice;
+use latency;
+use latency::{Clearing,Dark,Distribution,Latency,Lit};
+use pixfmt::Frame;
+use scale::Rect;
+use test_common;
+use uvcview::UvcView;
+use v4l2;
+
+static MS: u64 = 1000000;
+
+// One frame of the flash test: dequeued at `ns` showing `luma` where the
+// camera sees the window, and flipped 1 ms later.
+fn frame(latency: &mut Latency, ns: u64, luma: f64) {
+    latency.dequeued(0, 0, ns);
+    latency.observe(luma);
+    latency.flipped(ns + MS);
+}
+
+fn viewer(fake: FakeDevice) -> UvcView {
+    let mut uvcview = test_common::viewer(fake);
+    uvcview.latency = Some(Latency::new(false));
+    uvcview
+}
+
+#[test]
+fn distribution() {
+    let mut distribution = Distribution::new();
+    assert_eq!(distribution.quantile(0.5), None);
+    assert_eq!(format!("{}", distribution).as_slice(), "no samples");
+
+    for i in range(1u64, 101) {
+        distribution.add(i * MS);
+    }
+    assert_eq!(distribution.quantile(0.0), Some(MS));
+    assert_eq!(distribution.quantile(0.5), Some(51 * MS));
+    assert_eq!(distribution.quantile(0.95), Some(95 * MS));
+    assert_eq!(distribution.quantile(1.0), Some(100 * MS));
+    assert_eq!(distribution.mean(), Some(50.5 * MS as f64));
+    assert_eq!(format!("{}", distribution).as_slice(),
+               "min 1.00 med 51.00 p95 95.00 max 100.00 mean 50.50 ms (100)");
+
+    // Only the latest are kept.
+    for i in range(101u64, 701) {
+        distribution.add(i * MS);
+    }
+    assert_eq!(distribution.count, 700);
+    assert_eq!(distribution.quantile(0.0), Some(101 * MS));
+}
+
+#[test]
+fn stages() {
+    let mut latency = Latency::new(false);
+    latency.dequeued(1000, v4l2::V4L2_BUF_FLAG_TIMESTAMP_MONOTONIC, 5000);
+    latency.converted(7000);
+    latency.flipped(9000);
+    assert_eq!(latency.dequeue.quantile(0.5), Some(4000));
+    assert_eq!(latency.convert.quantile(0.5), Some(6000));
+    assert_eq!(latency.flip.quantile(0.5), Some(8000));
+
+    // Timestamps of other clocks say nothing.
+    latency.dequeued(1000, v4l2::V4L2_BUF_FLAG_TIMESTAMP_COPY, 5000);
+    latency.converted(7000);
+    latency.flipped(9000);
+    assert_eq!(latency.dequeue.count, 1);
+    assert_eq!(latency.flip.count, 1);
+    assert_eq!(latency.incomparable, 1);
+
+    let lines = latency.lines();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines.get(0).as_slice(), "DQBUF MIN 0.00 MED 0.00 P95 0.00 MAX 0.00 MEAN 0.00 MS (1)");
+    assert_eq!(lines.get(3).as_slice(), "1 FRAMES WITHOUT MONOTONIC TIMESTAMPS");
+    assert!(latency.flash.is_none() && !latency.flash_lit());
+}
+
+#[test]
+fn flash() {
+    let mut latency = Latency::new(true);
+    // A second of dark window, then the flash.
+    frame(&mut latency, 1000 * MS, 16.0);
+    assert_eq!(latency.flash, Some(Lit(1001 * MS)));
+    assert!(latency.flash_lit());
+
+    frame(&mut latency, 1033 * MS, 16.0);
+    assert!(latency.flash_lit());
+    frame(&mut latency, 1066 * MS, 200.0);
+    assert_eq!(latency.flash, Some(Clearing(1066 * MS)));
+    assert!(!latency.flash_lit());
+    assert_eq!(latency.glass.quantile(0.5), Some(66 * MS));
+
+    // The camera still sees the flash on the screen it films.
+    frame(&mut latency, 1100 * MS, 200.0);
+    assert_eq!(latency.flash, Some(Clearing(1066 * MS)));
+    frame(&mut latency, 1133 * MS, 16.0);
+    assert_eq!(latency.flash, Some(Dark(1133 * MS)));
+    assert_eq!(latency.glass.count, 1);
+
+    // A flash the camera never sees is given up on.
+    frame(&mut latency, 2133 * MS, 16.0);
+    assert_eq!(latency.flash, Some(Lit(2134 * MS)));
+    frame(&mut latency, 3000 * MS, 16.0);
+    frame(&mut latency, 4134 * MS, 16.0);
+    assert_eq!(latency.flash, Some(Clearing(4134 * MS)));
+    assert_eq!(latency.missed, 1);
+    assert_eq!(latency.glass.count, 1);
+
+    latency.reset();
+    assert_eq!(latency.flash, Some(Dark(0)));
+    assert_eq!(latency.missed, 0);
+}
+
+#[test]
+fn mean_luma() {
+    let mut grey = [0u8, ..256];
+    for (i, pixel) in grey.mut_iter().enumerate() {
+        *pixel = if i % 16 < 8 { 0 } else { 200 };
+    }
+    let frame = Frame::new(v4l2::V4L2_PIX_FMT_GREY, 16, 16, 16, grey.as_slice());
+    assert_eq!(latency::mean_luma(&frame, Rect { x: 0, y: 0, width: 16, height: 16 }), Some(100.0));
+    assert_eq!(latency::mean_luma(&frame, Rect { x: 8, y: 0, width: 100, height: 100 }), Some(200.0));
+    assert_eq!(latency::mean_luma(&frame, Rect { x: 16, y: 0, width: 8, height: 8 }), None);
+}
+
+#[test]
+fn stream() {
+    let mut fake = FakeDevice::new();
+    fake.monotonic = true;
+    let mut uvcview = viewer(fake);
+    uvcview.init().unwrap();
+    uvcview.start_capturing();
+    for _ in range(0, 3) {
+        assert!(uvcview.read_frame());
+    }
+    {
+        let latency = uvcview.latency.get_ref();
+        assert_eq!(latency.dequeue.count, 3);
+        let least = latency.dequeue.quantile(0.0).unwrap();
+        assert!(least >= fakedev::CAPTURE_NS && least < fakedev::CAPTURE_NS + 1000 * MS);
+        assert_eq!(latency.incomparable, 0);
+        // There is no window to convert for.
+        assert!(latency.convert.is_empty());
+    }
+    uvcview.stop_capturing();
+
+    let mut uvcview = viewer(FakeDevice::new());
+    uvcview.init().unwrap();
+    uvcview.start_capturing();
+    assert!(uvcview.read_frame());
+    let latency = uvcview.latency.get_ref();
+    assert!(latency.dequeue.is_empty());
+    assert_eq!(latency.incomparable, 1);
+}
+
+#[test]
+fn read_has_no_capture_time() {
+    let mut fake = FakeDevice::new();
+    fake.monotonic = true;
+    fake.capabilities = v4l2::V4L2_CAP_VIDEO_CAPTURE | v4l2::V4L2_CAP_READWRITE;
+    let mut uvcview = viewer(fake);
+    uvcview.init().unwrap();
+    uvcview.start_capturing();
+    for _ in range(0, 2) {
+        assert!(uvcview.read_frame());
+    }
+    let latency = uvcview.latency.get_ref();
+    assert!(latency.dequeue.is_empty());
+    assert_eq!(latency.incomparable, 2);
+}
@@ -0,0 +1,516 @@
+// Checks the structs in v4l2.rs against the layout of <linux/videodev2.h>,
+// and the ioctl numbers against the structs they pass. The expected values
+// come from the kernel header as compiled for each ABI.
+
+use ioctl;
+use std::mem;
+use uvc;
+use uvc::{uvc_meta_buf,uvc_xu_control_query};
+use v4l2;
+use v4l2::*;
+
+// The expected value on 64 bit targets, on ARM and on i386.
+#[cfg(target_word_size = "64")]
+fn abi(lp64: uint, _arm: uint, _i386: uint) -> uint { lp64 }
+#[cfg(target_arch = "arm")]
+fn abi(_lp64: uint, arm: uint, _i386: uint) -> uint { arm }
+#[cfg(target_arch = "x86")]
+fn abi(_lp64: uint, _arm: uint, i386: uint) -> uint { i386 }
+
+macro_rules! offset_of(
+    ($ty:ty, $field:ident) => (unsafe {
+        let value: $ty = mem::init();
+        let base: uint = mem::transmute(&value);
+        let field: uint = mem::transmute(&value.$field);
+        field - base
+    })
+)
+
+macro_rules! check_size(
+    ($ty:ty, $size:expr, $align:expr) => ({
+        assert!(mem::size_of::<$ty>() == $size, "sizeof({}) is {}, not {}",
+                stringify!($ty), mem::size_of::<$ty>(), $size);
+        assert!(mem::min_align_of::<$ty>() == $align, "alignof({}) is {}, not {}",
+                stringify!($ty), mem::min_align_of::<$ty>(), $align);
+    })
+)
+
+macro_rules! check_offset(
+    ($ty:ty, $field:ident, $offset:expr) => (
+        assert!(offset_of!($ty, $field) == $offset, "offsetof({}, {}) is {}, not {}",
+                stringify!($ty), stringify!($field), offset_of!($ty, $field), $offset)
+    )
+)
+
+macro_rules! check_ioctl(
+    ($req:ident, $ty:ty) => ({
+        assert!(ioctl::size(v4l2::$req) == mem::size_of::<$ty>(), "{} encodes {} bytes, not {}",
+                stringify!($req), ioctl::size(v4l2::$req), mem::size_of::<$ty>());
+        assert_eq!(ioctl::ioc_type(v4l2::$req), 'V' as u32);
+    })
+)
+
+#[test]
+fn basic_types() {
+    check_size!(v4l2_rect, 16, 4);
+    check_size!(v4l2_fract, 8, 4);
+    check_size!(v4l2_std_id, 8, abi(8, 8, 4));
+    check_size!(v4l2_timecode, 16, 4);
+}
+
+#[test]
+fn capability() {
+    check_size!(v4l2_capability, 104, 4);
+    check_offset!(v4l2_capability, card, 16);
+    check_offset!(v4l2_capability, bus_info, 48);
+    check_offset!(v4l2_capability, version, 80);
+    check_offset!(v4l2_capability, capabilities, 84);
+    check_offset!(v4l2_capability, device_caps, 88);
+    check_offset!(v4l2_capability, reserved, 92);
+}
+
+#[test]
+fn formats() {
+    check_size!(v4l2_pix_format, 48, 4);
+    check_offset!(v4l2_pix_format, bytesperline, 16);
+    check_offset!(v4l2_pix_format, _priv, 28);
+    check_offset!(v4l2_pix_format, xfer_func, 44);
+
+    check_size!(v4l2_plane_pix_format, 20, 4);
+    check_offset!(v4l2_plane_pix_format, bytesperline, 4);
+    check_size!(v4l2_pix_format_mplane, 192, 4);
+    check_offset!(v4l2_pix_format_mplane, plane_fmt, 20);
+    check_offset!(v4l2_pix_format_mplane, num_planes, 180);
+    check_offset!(v4l2_pix_format_mplane, xfer_func, 184);
+    check_offset!(v4l2_pix_format_mplane, reserved, 185);
+    check_size!(v4l2_meta_format, 8, 4);
+
+    // The union holds a v4l2_window, which has pointers.
+    check_size!(union16, 200, abi(8, 4, 4));
+    check_size!(v4l2_format, abi(208, 204, 204), abi(8, 4, 4));
+    check_offset!(v4l2_format, fmt, abi(8, 4, 4));
+
+    check_size!(v4l2_fmtdesc, 64, 4);
+    check_offset!(v4l2_fmtdesc, description, 12);
+    check_offset!(v4l2_fmtdesc, pixelformat, 44);
+    check_offset!(v4l2_fmtdesc, mbus_code, 48);
+    check_size!(v4l2_frmsizeenum, 44, 4);
+    check_offset!(v4l2_frmsizeenum, _union, 12);
+    check_offset!(v4l2_frmsizeenum, reserved, 36);
+    check_size!(v4l2_frmivalenum, 52, 4);
+    check_offset!(v4l2_frmivalenum, _type, 16);
+    check_offset!(v4l2_frmivalenum, reserved, 44);
+
+    check_size!(v4l2_framebuffer_fmt, 32, 4);
+    check_size!(v4l2_framebuffer, abi(48, 44, 44), abi(8, 4, 4));
+    check_offset!(v4l2_framebuffer, base, 8);
+    check_offset!(v4l2_framebuffer, fmt, abi(16, 12, 12));
+
+    check_size!(v4l2_captureparm, 40, 4);
+    check_offset!(v4l2_captureparm, timeperframe, 8);
+    check_offset!(v4l2_captureparm, readbuffers, 20);
+    check_size!(union17, 200, 4);
+    check_size!(v4l2_streamparm, 204, 4);
+    check_offset!(v4l2_streamparm, parm, 4);
+}
+
+#[test]
+fn buffers() {
+    check_size!(v4l2_requestbuffers, 20, 4);
+    check_offset!(v4l2_requestbuffers, capabilities, 12);
+    check_offset!(v4l2_requestbuffers, flags, 16);
+
+    // The union holds an unsigned long and a pointer.
+    check_size!(union3, abi(8, 4, 4), abi(8, 4, 4));
+    check_size!(v4l2_plane, abi(64, 60, 60), abi(8, 4, 4));
+    check_offset!(v4l2_plane, m, 8);
+    check_offset!(v4l2_plane, data_offset, abi(16, 12, 12));
+    check_offset!(v4l2_plane, reserved, abi(20, 16, 16));
+
+    check_size!(union4, abi(8, 4, 4), abi(8, 4, 4));
+    check_size!(v4l2_buffer, abi(88, 68, 68), abi(8, 4, 4));
+    check_offset!(v4l2_buffer, field, 16);
+    check_offset!(v4l2_buffer, timestamp, abi(24, 20, 20));
+    check_offset!(v4l2_buffer, timecode, abi(40, 28, 28));
+    check_offset!(v4l2_buffer, sequence, abi(56, 44, 44));
+    check_offset!(v4l2_buffer, memory, abi(60, 48, 48));
+    check_offset!(v4l2_buffer, m, abi(64, 52, 52));
+    check_offset!(v4l2_buffer, length, abi(72, 56, 56));
+    check_offset!(v4l2_buffer, reserved2, abi(76, 60, 60));
+    check_offset!(v4l2_buffer, reserved, abi(80, 64, 64));
+
+    check_size!(v4l2_exportbuffer, 64, 4);
+    check_offset!(v4l2_exportbuffer, fd, 16);
+
+    check_size!(v4l2_create_buffers, abi(256, 248, 248), abi(8, 4, 4));
+    check_offset!(v4l2_create_buffers, format, abi(16, 12, 12));
+    check_offset!(v4l2_create_buffers, capabilities, abi(224, 216, 216));
+    check_offset!(v4l2_create_buffers, reserved, abi(236, 228, 228));
+    check_size!(v4l2_remove_buffers, 64, 4);
+}
+
+#[test]
+fn crop_and_selection() {
+    check_size!(v4l2_cropcap, 44, 4);
+    check_offset!(v4l2_cropcap, bounds, 4);
+    check_offset!(v4l2_cropcap, defrect, 20);
+    check_offset!(v4l2_cropcap, pixelaspect, 36);
+    check_size!(v4l2_crop, 20, 4);
+    check_offset!(v4l2_crop, c, 4);
+    check_size!(v4l2_selection, 64, 4);
+    check_offset!(v4l2_selection, r, 12);
+    check_offset!(v4l2_selection, reserved, 28);
+}
+
+#[test]
+fn overlay() {
+    // Both carry user pointers.
+    check_size!(v4l2_clip, abi(24, 20, 20), abi(8, 4, 4));
+    check_offset!(v4l2_clip, next, 16);
+    check_size!(v4l2_window, abi(56, 40, 40), abi(8, 4, 4));
+    check_offset!(v4l2_window, field, 16);
+    check_offset!(v4l2_window, chromakey, 20);
+    check_offset!(v4l2_window, clips, 24);
+    check_offset!(v4l2_window, clipcount, abi(32, 28, 28));
+    check_offset!(v4l2_window, bitmap, abi(40, 32, 32));
+    check_offset!(v4l2_window, global_alpha, abi(48, 36, 36));
+}
+
+#[test]
+fn frame_sizes_and_intervals() {
+    check_size!(v4l2_frmsize_discrete, 8, 4);
+    check_size!(v4l2_frmsize_stepwise, 24, 4);
+    check_offset!(v4l2_frmsize_stepwise, min_height, 12);
+    check_offset!(v4l2_frmsize_stepwise, step_height, 20);
+    check_size!(v4l2_frmival_stepwise, 24, 4);
+    check_offset!(v4l2_frmival_stepwise, max, 8);
+    check_offset!(v4l2_frmival_stepwise, step, 16);
+
+    check_size!(v4l2_outputparm, 40, 4);
+    check_offset!(v4l2_outputparm, timeperframe, 8);
+    check_offset!(v4l2_outputparm, writebuffers, 20);
+    check_offset!(v4l2_outputparm, reserved, 24);
+}
+
+#[test]
+fn inputs_and_standards() {
+    // i386 aligns the 64 bit std fields to 4 bytes, ARM to 8.
+    check_size!(v4l2_standard, abi(72, 72, 64), abi(8, 8, 4));
+    check_offset!(v4l2_standard, id, abi(8, 8, 4));
+    check_offset!(v4l2_standard, name, abi(16, 16, 12));
+    check_offset!(v4l2_standard, frameperiod, abi(40, 40, 36));
+    check_offset!(v4l2_standard, framelines, abi(48, 48, 44));
+
+    check_size!(v4l2_input, abi(80, 80, 76), abi(8, 8, 4));
+    check_offset!(v4l2_input, _type, 36);
+    check_offset!(v4l2_input, std, 48);
+    check_offset!(v4l2_input, status, 56);
+    check_offset!(v4l2_input, capabilities, 60);
+    check_offset!(v4l2_input, reserved, 64);
+
+    check_size!(v4l2_output, 72, abi(8, 8, 4));
+    check_offset!(v4l2_output, std, 48);
+    check_offset!(v4l2_output, capabilities, 56);
+
+    check_size!(v4l2_tuner, 84, 4);
+    check_offset!(v4l2_tuner, _type, 36);
+    check_offset!(v4l2_tuner, rangelow, 44);
+    check_offset!(v4l2_tuner, signal, 60);
+    check_offset!(v4l2_tuner, reserved, 68);
+    check_size!(v4l2_modulator, 68, 4);
+    check_size!(v4l2_frequency, 44, 4);
+    check_offset!(v4l2_frequency, frequency, 8);
+    check_size!(v4l2_frequency_band, 64, 4);
+    check_size!(v4l2_hw_freq_seek, 48, 4);
+    check_size!(v4l2_audio, 52, 4);
+    check_size!(v4l2_audioout, 52, 4);
+}
+
+#[test]
+fn dv_timings() {
+    // Packed in the header, so pixelclock need not be aligned.
+    check_size!(v4l2_bt_timings, 124, 1);
+    check_offset!(v4l2_bt_timings, pixelclock, 16);
+    check_offset!(v4l2_bt_timings, hfrontporch, 24);
+    check_offset!(v4l2_bt_timings, il_vbackporch, 56);
+    check_offset!(v4l2_bt_timings, standards, 60);
+    check_offset!(v4l2_bt_timings, flags, 64);
+    check_offset!(v4l2_bt_timings, picture_aspect, 68);
+    check_offset!(v4l2_bt_timings, cea861_vic, 76);
+    check_offset!(v4l2_bt_timings, hdmi_vic, 77);
+    check_offset!(v4l2_bt_timings, reserved, 78);
+
+    check_size!(v4l2_dv_timings, 132, 1);
+    check_size!(v4l2_enum_dv_timings, 148, 4);
+    check_offset!(v4l2_enum_dv_timings, pad, 4);
+    check_offset!(v4l2_enum_dv_timings, timings, 16);
+
+    check_size!(v4l2_bt_timings_cap, 104, 1);
+    check_offset!(v4l2_bt_timings_cap, min_pixelclock, 16);
+    check_offset!(v4l2_bt_timings_cap, standards, 32);
+    check_offset!(v4l2_bt_timings_cap, capabilities, 36);
+    check_offset!(v4l2_bt_timings_cap, reserved, 40);
+    check_size!(v4l2_dv_timings_cap, 144, 4);
+}
+
+#[test]
+fn controls() {
+    check_size!(v4l2_control, 8, 4);
+    check_size!(v4l2_queryctrl, 68, 4);
+    check_offset!(v4l2_queryctrl, name, 8);
+    check_offset!(v4l2_queryctrl, minimum, 40);
+    check_offset!(v4l2_queryctrl, default_value, 52);
+    check_offset!(v4l2_queryctrl, flags, 56);
+
+    check_size!(v4l2_query_ext_ctrl, 232, abi(8, 8, 4));
+    check_offset!(v4l2_query_ext_ctrl, minimum, 40);
+    check_offset!(v4l2_query_ext_ctrl, step, 56);
+    check_offset!(v4l2_query_ext_ctrl, default_value, 64);
+    check_offset!(v4l2_query_ext_ctrl, flags, 72);
+    check_offset!(v4l2_query_ext_ctrl, dims, 88);
+    check_offset!(v4l2_query_ext_ctrl, reserved, 104);
+
+    // Both packed in the header.
+    check_size!(v4l2_querymenu, 44, 1);
+    check_offset!(v4l2_querymenu, reserved, 40);
+    check_size!(v4l2_ext_control, 20, 1);
+
+    check_size!(v4l2_ext_controls, abi(32, 24, 24), abi(8, 4, 4));
+    check_offset!(v4l2_ext_controls, error_idx, 8);
+    check_offset!(v4l2_ext_controls, request_fd, 12);
+    check_offset!(v4l2_ext_controls, controls, abi(24, 20, 20));
+}
+
+#[test]
+fn events() {
+    check_size!(v4l2_event_ctrl, abi(40, 40, 36), abi(8, 8, 4));
+    check_offset!(v4l2_event_ctrl, flags, 16);
+    check_offset!(v4l2_event_ctrl, default_value, 32);
+    check_size!(union19, 64, abi(8, 8, 4));
+
+    check_size!(v4l2_event, abi(136, 128, 120), abi(8, 8, 4));
+    check_offset!(v4l2_event, u, abi(8, 8, 4));
+    check_offset!(v4l2_event, pending, abi(72, 72, 68));
+    check_offset!(v4l2_event, sequence, abi(76, 76, 72));
+    check_offset!(v4l2_event, timestamp, abi(80, 80, 76));
+    check_offset!(v4l2_event, id, abi(96, 88, 84));
+    check_offset!(v4l2_event, reserved, abi(100, 92, 88));
+
+    check_size!(v4l2_event_vsync, 1, 1);
+    check_size!(v4l2_event_frame_sync, 4, 4);
+    check_size!(v4l2_event_src_change, 4, 4);
+
+    check_size!(v4l2_event_subscription, 32, 4);
+    check_offset!(v4l2_event_subscription, flags, 8);
+}
+
+#[test]
+fn codec_and_debug() {
+    check_size!(v4l2_jpegcompression, 140, 4);
+    check_offset!(v4l2_jpegcompression, APP_data, 12);
+    check_offset!(v4l2_jpegcompression, COM_len, 72);
+    check_offset!(v4l2_jpegcompression, jpeg_markers, 136);
+    check_size!(v4l2_enc_idx_entry, 32, abi(8, 8, 4));
+    check_size!(v4l2_enc_idx, 2072, abi(8, 8, 4));
+    check_offset!(v4l2_enc_idx, entry, 24);
+    check_size!(v4l2_encoder_cmd, 40, 4);
+    check_size!(v4l2_decoder_cmd, 72, abi(8, 8, 4));
+    check_size!(v4l2_sliced_vbi_cap, 116, 4);
+    check_offset!(v4l2_sliced_vbi_cap, _type, 100);
+
+    check_size!(v4l2_dbg_match, 36, 4);
+    check_size!(v4l2_dbg_register, 56, 1);
+    check_offset!(v4l2_dbg_register, size, 36);
+    check_offset!(v4l2_dbg_register, reg, 40);
+    check_offset!(v4l2_dbg_register, val, 48);
+    check_size!(v4l2_dbg_chip_info, 200, 1);
+    check_offset!(v4l2_dbg_chip_info, name, 36);
+    check_offset!(v4l2_dbg_chip_info, flags, 68);
+
+    check_size!(v4l2_edid, abi(40, 36, 36), abi(8, 4, 4));
+    check_offset!(v4l2_edid, edid, 32);
+}
+
+#[test]
+fn vbi() {
+    check_size!(v4l2_vbi_format, 44, 4);
+    check_offset!(v4l2_vbi_format, start, 16);
+    check_offset!(v4l2_vbi_format, count, 24);
+    check_offset!(v4l2_vbi_format, flags, 32);
+    check_offset!(v4l2_vbi_format, reserved, 36);
+
+    check_size!(v4l2_sliced_vbi_format, 112, 4);
+    check_offset!(v4l2_sliced_vbi_format, service_lines, 2);
+    check_offset!(v4l2_sliced_vbi_format, io_size, 100);
+    check_offset!(v4l2_sliced_vbi_format, reserved, 104);
+    check_size!(v4l2_sliced_vbi_data, 64, 4);
+    check_offset!(v4l2_sliced_vbi_data, line, 8);
+    check_offset!(v4l2_sliced_vbi_data, data, 16);
+
+    // All packed in the header.
+    check_size!(v4l2_rds_data, 3, 1);
+    check_offset!(v4l2_rds_data, block, 2);
+    check_size!(v4l2_mpeg_vbi_itv0_line, 43, 1);
+    check_offset!(v4l2_mpeg_vbi_itv0_line, data, 1);
+    check_size!(v4l2_mpeg_vbi_itv0, 1513, 1);
+    check_offset!(v4l2_mpeg_vbi_itv0, line, 8);
+    check_size!(v4l2_mpeg_vbi_ITV0, 1548, 1);
+    check_size!(v4l2_mpeg_vbi_fmt_ivtv, 1552, 1);
+}
+
+#[test]
+fn ioctl_sizes() {
+    check_ioctl!(VIDIOC_QUERYCAP, v4l2_capability);
+    check_ioctl!(VIDIOC_ENUM_FMT, v4l2_fmtdesc);
+    check_ioctl!(VIDIOC_G_FMT, v4l2_format);
+    check_ioctl!(VIDIOC_S_FMT, v4l2_format);
+    check_ioctl!(VIDIOC_TRY_FMT, v4l2_format);
+    check_ioctl!(VIDIOC_REQBUFS, v4l2_requestbuffers);
+    check_ioctl!(VIDIOC_QUERYBUF, v4l2_buffer);
+    check_ioctl!(VIDIOC_G_FBUF, v4l2_framebuffer);
+    check_ioctl!(VIDIOC_S_FBUF, v4l2_framebuffer);
+    check_ioctl!(VIDIOC_OVERLAY, i32);
+    check_ioctl!(VIDIOC_QBUF, v4l2_buffer);
+    check_ioctl!(VIDIOC_EXPBUF, v4l2_exportbuffer);
+    check_ioctl!(VIDIOC_DQBUF, v4l2_buffer);
+    check_ioctl!(VIDIOC_STREAMON, i32);
+    check_ioctl!(VIDIOC_STREAMOFF, i32);
+    check_ioctl!(VIDIOC_G_PARM, v4l2_streamparm);
+    check_ioctl!(VIDIOC_S_PARM, v4l2_streamparm);
+    check_ioctl!(VIDIOC_G_STD, v4l2_std_id);
+    check_ioctl!(VIDIOC_S_STD, v4l2_std_id);
+    check_ioctl!(VIDIOC_ENUMSTD, v4l2_standard);
+    check_ioctl!(VIDIOC_ENUMINPUT, v4l2_input);
+    check_ioctl!(VIDIOC_G_CTRL, v4l2_control);
+    check_ioctl!(VIDIOC_S_CTRL, v4l2_control);
+    check_ioctl!(VIDIOC_G_TUNER, v4l2_tuner);
+    check_ioctl!(VIDIOC_S_TUNER, v4l2_tuner);
+    check_ioctl!(VIDIOC_G_AUDIO, v4l2_audio);
+    check_ioctl!(VIDIOC_S_AUDIO, v4l2_audio);
+    check_ioctl!(VIDIOC_QUERYCTRL, v4l2_queryctrl);
+    check_ioctl!(VIDIOC_QUERYMENU, v4l2_querymenu);
+    check_ioctl!(VIDIOC_G_INPUT, i32);
+    check_ioctl!(VIDIOC_S_INPUT, i32);
+    check_ioctl!(VIDIOC_G_EDID, v4l2_edid);
+    check_ioctl!(VIDIOC_S_EDID, v4l2_edid);
+    check_ioctl!(VIDIOC_G_OUTPUT, i32);
+    check_ioctl!(VIDIOC_S_OUTPUT, i32);
+    check_ioctl!(VIDIOC_ENUMOUTPUT, v4l2_output);
+    check_ioctl!(VIDIOC_G_AUDOUT, v4l2_audioout);
+    check_ioctl!(VIDIOC_S_AUDOUT, v4l2_audioout);
+    check_ioctl!(VIDIOC_G_MODULATOR, v4l2_modulator);
+    check_ioctl!(VIDIOC_S_MODULATOR, v4l2_modulator);
+    check_ioctl!(VIDIOC_G_FREQUENCY, v4l2_frequency);
+    check_ioctl!(VIDIOC_S_FREQUENCY, v4l2_frequency);
+    check_ioctl!(VIDIOC_CROPCAP, v4l2_cropcap);
+    check_ioctl!(VIDIOC_G_CROP, v4l2_crop);
+    check_ioctl!(VIDIOC_S_CROP, v4l2_crop);
+    check_ioctl!(VIDIOC_G_JPEGCOMP, v4l2_jpegcompression);
+    check_ioctl!(VIDIOC_S_JPEGCOMP, v4l2_jpegcompression);
+    check_ioctl!(VIDIOC_QUERYSTD, v4l2_std_id);
+    check_ioctl!(VIDIOC_ENUMAUDIO, v4l2_audio);
+    check_ioctl!(VIDIOC_ENUMAUDOUT, v4l2_audioout);
+    check_ioctl!(VIDIOC_G_PRIORITY, u32);
+    check_ioctl!(VIDIOC_S_PRIORITY, u32);
+    check_ioctl!(VIDIOC_G_SLICED_VBI_CAP, v4l2_sliced_vbi_cap);
+    check_ioctl!(VIDIOC_G_EXT_CTRLS, v4l2_ext_controls);
+    check_ioctl!(VIDIOC_S_EXT_CTRLS, v4l2_ext_controls);
+    check_ioctl!(VIDIOC_TRY_EXT_CTRLS, v4l2_ext_controls);
+    check_ioctl!(VIDIOC_ENUM_FRAMESIZES, v4l2_frmsizeenum);
+    check_ioctl!(VIDIOC_ENUM_FRAMEINTERVALS, v4l2_frmivalenum);
+    check_ioctl!(VIDIOC_G_ENC_INDEX, v4l2_enc_idx);
+    check_ioctl!(VIDIOC_ENCODER_CMD, v4l2_encoder_cmd);
+    check_ioctl!(VIDIOC_TRY_ENCODER_CMD, v4l2_encoder_cmd);
+    check_ioctl!(VIDIOC_DBG_S_REGISTER, v4l2_dbg_register);
+    check_ioctl!(VIDIOC_DBG_G_REGISTER, v4l2_dbg_register);
+    check_ioctl!(VIDIOC_S_HW_FREQ_SEEK, v4l2_hw_freq_seek);
+    check_ioctl!(VIDIOC_S_DV_TIMINGS, v4l2_dv_timings);
+    check_ioctl!(VIDIOC_G_DV_TIMINGS, v4l2_dv_timings);
+    check_ioctl!(VIDIOC_DQEVENT, v4l2_event);
+    check_ioctl!(VIDIOC_SUBSCRIBE_EVENT, v4l2_event_subscription);
+    check_ioctl!(VIDIOC_UNSUBSCRIBE_EVENT, v4l2_event_subscription);
+    check_ioctl!(VIDIOC_CREATE_BUFS, v4l2_create_buffers);
+    check_ioctl!(VIDIOC_PREPARE_BUF, v4l2_buffer);
+    check_ioctl!(VIDIOC_G_SELECTION, v4l2_selection);
+    check_ioctl!(VIDIOC_S_SELECTION, v4l2_selection);
+    check_ioctl!(VIDIOC_DECODER_CMD, v4l2_decoder_cmd);
+    check_ioctl!(VIDIOC_TRY_DECODER_CMD, v4l2_decoder_cmd);
+    check_ioctl!(VIDIOC_ENUM_DV_TIMINGS, v4l2_enum_dv_timings);
+    check_ioctl!(VIDIOC_QUERY_DV_TIMINGS, v4l2_dv_timings);
+    check_ioctl!(VIDIOC_DV_TIMINGS_CAP, v4l2_dv_timings_cap);
+    check_ioctl!(VIDIOC_ENUM_FREQ_BANDS, v4l2_frequency_band);
+    check_ioctl!(VIDIOC_DBG_G_CHIP_INFO, v4l2_dbg_chip_info);
+    check_ioctl!(VIDIOC_QUERY_EXT_CTRL, v4l2_query_ext_ctrl);
+    check_ioctl!(VIDIOC_REMOVE_BUFS, v4l2_remove_buffers);
+    check_ioctl!(VIDIOC_LOG_STATUS, ());
+}
+
+#[test]
+fn uvc_xu_query() {
+    check_size!(uvc_xu_control_query, abi(16, 12, 12), abi(8, 4, 4));
+    check_offset!(uvc_xu_control_query, query, 2);
+    check_offset!(uvc_xu_control_query, size, 4);
+    check_offset!(uvc_xu_control_query, data, 8);
+    assert_eq!(ioctl::size(uvc::UVCIOC_CTRL_QUERY), mem::size_of::<uvc_xu_control_query>());
+    assert_eq!(uvc::size::uvc_xu_control_query as uint, mem::size_of::<uvc_xu_control_query>());
+    assert_eq!(ioctl::ioc_type(uvc::UVCIOC_CTRL_QUERY), 'u' as u32);
+    assert_eq!(ioctl::nr(uvc::UVCIOC_CTRL_QUERY), 0x21);
+}
+
+#[test]
+fn uvc_meta() {
+    check_size!(uvc_meta_buf, 12, 1);
+    check_offset!(uvc_meta_buf, sof, 8);
+    check_offset!(uvc_meta_buf, length, 10);
+    check_offset!(uvc_meta_buf, flags, 11);
+}
+
+#[test]
+fn ioctl_directions() {
+    assert_eq!(ioctl::dir(VIDIOC_QUERYCAP), ioctl::IOC_READ);
+    assert_eq!(ioctl::dir(VIDIOC_STREAMON), ioctl::IOC_WRITE);
+    assert_eq!(ioctl::dir(VIDIOC_DQBUF), ioctl::IOC_READ | ioctl::IOC_WRITE);
+    assert_eq!(ioctl::dir(VIDIOC_LOG_STATUS), ioctl::IOC_NONE);
+    assert_eq!(ioctl::nr(VIDIOC_QUERYCAP), 0);
+    assert_eq!(ioctl::nr(VIDIOC_DQBUF), 17);
+    assert_eq!(ioctl::nr(VIDIOC_REMOVE_BUFS), 104);
+}
+
+// The numbers a C program gets from the kernel headers.
+#[test]
+#[cfg(target_word_size = "64")]
+fn ioctl_numbers() {
+    assert_eq!(VIDIOC_QUERYCAP, 0x80685600);
+    assert_eq!(VIDIOC_S_FMT, 0xc0d05605);
+    assert_eq!(VIDIOC_QBUF, 0xc058560f);
+    assert_eq!(VIDIOC_EXPBUF, 0xc0405610);
+    assert_eq!(VIDIOC_ENUMSTD, 0xc0485619);
+    assert_eq!(VIDIOC_ENUMINPUT, 0xc050561a);
+    assert_eq!(VIDIOC_G_EXT_CTRLS, 0xc0205647);
+    assert_eq!(VIDIOC_DQEVENT, 0x80885659);
+    assert_eq!(VIDIOC_CREATE_BUFS, 0xc100565c);
+    assert_eq!(VIDIOC_QUERY_EXT_CTRL, 0xc0e85667);
+}
+
+#[test]
+#[cfg(target_arch = "arm")]
+fn ioctl_numbers() {
+    assert_eq!(VIDIOC_QUERYCAP, 0x80685600);
+    assert_eq!(VIDIOC_S_FMT, 0xc0cc5605);
+    assert_eq!(VIDIOC_QBUF, 0xc044560f);
+    assert_eq!(VIDIOC_EXPBUF, 0xc0405610);
+    assert_eq!(VIDIOC_ENUMSTD, 0xc0485619);
+    assert_eq!(VIDIOC_ENUMINPUT, 0xc050561a);
+    assert_eq!(VIDIOC_G_EXT_CTRLS, 0xc0185647);
+    assert_eq!(VIDIOC_DQEVENT, 0x80805659);
+    assert_eq!(VIDIOC_CREATE_BUFS, 0xc0f8565c);
+    assert_eq!(VIDIOC_QUERY_EXT_CTRL, 0xc0e85667);
+}
+
+#[test]
+fn size_table() {
+    assert_eq!(v4l2::size::v4l2_buffer as uint, mem::size_of::<v4l2_buffer>());
+    assert_eq!(v4l2::size::v4l2_format as uint, mem::size_of::<v4l2_format>());
+    assert_eq!(v4l2::size::v4l2_event as uint, mem::size_of::<v4l2_event>());
+    assert_eq!(v4l2::size::v4l2_create_buffers as uint, mem::size_of::<v4l2_create_buffers>());
+}
@@ -0,0 +1,146 @@
+use backend::IoctlBackend;
+use device::Device;
+use fakedev;
+use fakedev::FakeDevice;
+use libc::consts::os::posix88::EBUSY;
+use meta;
+use meta::{Block,Clock,FrameMeta,MetaNode};
+use test_common;
+use uvc;
+use uvcview::UvcView;
+use v4l2;
+
+fn block(ns: u64, flags: u8, pts: Option<u32>, scr: Option<(u32, u16)>) -> Block {
+    Block { ns: ns, sof: 0, flags: flags, pts: pts, scr: scr, vendor: vec!() }
+}
+
+// A camera whose metadata node is a second fake device.
+fn viewer() -> UvcView {
+    let mut node = FakeDevice::new();
+    node.meta = true;
+    let mut uvcview = test_common::viewer(FakeDevice::new());
+    uvcview.meta = Some(MetaNode::new(Path::new("/dev/video1"), Device::new(4, ~node as ~IoctlBackend)));
+    uvcview
+}
+
+#[test]
+fn parse() {
+    let mut data = fakedev::meta_block(2);
+    // A header without PTS and SCR, carrying two vendor bytes.
+    data.push_all(&[0, 0, 0, 0, 0, 0, 0, 0, 0x12, 0, 4, uvc::UVC_STREAM_EOH | uvc::UVC_STREAM_ERR,
+                    0xaa, 0xbb]);
+    // A block cut short.
+    data.push_all(fakedev::meta_block(3).slice_to(16));
+
+    let blocks = meta::parse(data.as_slice());
+    assert_eq!(blocks.len(), 2);
+    assert_eq!(*blocks.get(0), Block {
+        ns: 1072666666,
+        sof: 1072,
+        flags: uvc::UVC_STREAM_EOH | uvc::UVC_STREAM_SCR | uvc::UVC_STREAM_PTS,
+        pts: Some(3200000),
+        scr: Some((3440000, 1072)),
+        vendor: vec!(),
+    });
+    assert_eq!(blocks.get(1).sof, 0x12);
+    assert_eq!(blocks.get(1).pts, None);
+    assert_eq!(blocks.get(1).scr, None);
+    assert_eq!(blocks.get(1).vendor, vec!(0xaa, 0xbb));
+
+    assert!(meta::parse(&[]).is_empty());
+}
+
+#[test]
+fn frame_meta() {
+    let blocks = [block(10, uvc::UVC_STREAM_PTS, Some(100), None),
+                  block(20, uvc::UVC_STREAM_SCR, None, Some((150, 7))),
+                  block(30, uvc::UVC_STREAM_SCR | uvc::UVC_STREAM_ERR, None, Some((150, 7)))];
+    assert_eq!(FrameMeta::from_blocks(5, blocks.as_slice()), Some(FrameMeta {
+        sequence: 5,
+        pts: Some(100),
+        scr: Some((150, 7)),
+        ns: 20,
+        sof: 0,
+        headers: 3,
+        error: true,
+    }));
+    assert_eq!(FrameMeta::from_blocks(5, blocks.slice_to(1)).unwrap().ns, 10);
+    assert_eq!(FrameMeta::from_blocks(5, &[]), None);
+}
+
+#[test]
+fn clock() {
+    let mut clock = Clock::new();
+    // 48 MHz, starting just before the 32 bit clock wraps.
+    let start = 0xfff00000u32;
+    clock.update(1000000000, start);
+    assert_eq!(clock.frequency(), None);
+    clock.update(1500000000, start + 24000000);
+    // A repeated SCR arriving later is no new sample.
+    clock.update(1600000000, start + 24000000);
+    assert_eq!(clock.frequency(), None);
+    clock.update(2000000000, start + 48000000);
+    assert_eq!(clock.frequency(), Some(48000000.0));
+    assert_eq!(clock.drift_ppm(), None);
+
+    clock.nominal_hz = Some(48000960.0);
+    assert!((clock.drift_ppm().unwrap() + 20.0).abs() < 0.01);
+
+    // Without a nominal rate the delay is in the measured one.
+    clock.nominal_hz = None;
+    let meta = FrameMeta::from_blocks(0, &[block(0, 0, Some(0), Some((240000, 0)))]).unwrap();
+    assert_eq!(clock.transit_ns(&meta), Some(5000000.0));
+
+    clock.reset();
+    assert_eq!(clock.frequency(), None);
+}
+
+#[test]
+fn video_node() {
+    let mut node = MetaNode::new(Path::new("/dev/video0"), Device::new(4, ~FakeDevice::new() as ~IoctlBackend));
+    assert_eq!(node.init(4, false).unwrap_err().desc, "MetaNode::init(): V4L2_CAP_META_CAPTURE not supported");
+    assert_eq!(node.take(0), None);
+    assert!(node.last.is_none());
+}
+
+#[test]
+fn monitor_keeps_format() {
+    let mut fake = FakeDevice::new().fail(v4l2::VIDIOC_S_FMT, EBUSY);
+    fake.meta = true;
+    let mut node = MetaNode::new(Path::new("/dev/video1"), Device::new(4, ~fake as ~IoctlBackend));
+    assert_eq!(node.init(4, false).unwrap_err().desc, "MetaNode::init(): VIDIOC_S_FMT failed");
+    assert!(node.init(4, true).is_ok());
+    assert_eq!(node.buffersize, fakedev::META_BUFFER_SIZE);
+}
+
+#[test]
+fn stream() {
+    let mut uvcview = viewer();
+    uvcview.init().unwrap();
+    uvcview.start_capturing();
+    for _ in range(0, 3) {
+        assert!(uvcview.read_frame());
+    }
+    {
+        let node = uvcview.meta.get_ref();
+        assert_eq!(node.buffersize, fakedev::META_BUFFER_SIZE);
+        let last = node.last.clone().unwrap();
+        assert_eq!(last.sequence, 2);
+        assert_eq!(last.pts, Some(3200000));
+        assert_eq!(last.scr, Some((3440000, 1072)));
+        assert_eq!(last.headers, 1);
+        assert!(!last.error);
+    }
+
+    // A second of frames tells the clock rate.
+    for _ in range(0, 31) {
+        assert!(uvcview.read_frame());
+    }
+    {
+        let node = uvcview.meta.get_ref();
+        assert_eq!(node.last.get_ref().sequence, 33);
+        let frequency = node.clock.frequency().unwrap();
+        assert!((frequency - fakedev::META_CLOCK_HZ as f64).abs() < 1.0);
+    }
+    uvcview.stop_capturing();
+}
@@ -0,0 +1,120 @@
+use overlay::Canvas;
+use scale;
+use scale::{Rect,Region,Fit,Integer,Nearest,Bilinear};
+use std::default::Default;
+use test_common::grey_frame;
+use uvcview::UvcView;
+
+#[test]
+fn fit_letterboxes_wide_frame() {
+    let rect = scale::fit(Fit, 1920, 1080, 1280, 1024);
+    assert_eq!(rect, Rect { x: 0, y: 152, width: 1280, height: 720 });
+}
+
+#[test]
+fn fit_pillarboxes_tall_window() {
+    let rect = scale::fit(Fit, 640, 480, 1920, 1080);
+    assert_eq!(rect, Rect { x: 240, y: 0, width: 1440, height: 1080 });
+}
+
+#[test]
+fn integer_upscale_rounds_down() {
+    let rect = scale::fit(Integer, 320, 240, 1000, 1000);
+    assert_eq!(rect, Rect { x: 20, y: 140, width: 960, height: 720 });
+}
+
+#[test]
+fn integer_downscale_uses_whole_fractions() {
+    let rect = scale::fit(Integer, 1920, 1080, 1280, 720);
+    assert_eq!(rect, Rect { x: 160, y: 90, width: 960, height: 540 });
+}
+
+#[test]
+fn nearest_upscale_repeats_pixels() {
+    let frame = grey_frame();
+    let mut pixels = Vec::from_elem(6 * 4 * 3, 7u8);
+    let mut canvas = Canvas::new(pixels.as_mut_slice(), 6 * 3, 6, 4);
+    scale::convert(&frame, &mut canvas, Rect { x: 1, y: 0, width: 4, height: 4 },
+                   Region::whole(2, 2), Nearest);
+    assert_eq!(canvas.get(0, 0), (0, 0, 0));
+    assert_eq!(canvas.get(5, 3), (0, 0, 0));
+    assert_eq!(canvas.get(1, 0), (0, 0, 0));
+    assert_eq!(canvas.get(2, 1), (0, 0, 0));
+    assert_eq!(canvas.get(3, 1), (100, 100, 100));
+    assert_eq!(canvas.get(4, 3), (255, 255, 255));
+    assert_eq!(canvas.get(1, 2), (200, 200, 200));
+}
+
+#[test]
+fn bilinear_blends_neighbours() {
+    let frame = grey_frame();
+    let mut pixels = Vec::from_elem(4 * 4 * 3, 0u8);
+    let mut canvas = Canvas::new(pixels.as_mut_slice(), 4 * 3, 4, 4);
+    scale::convert(&frame, &mut canvas, Rect { x: 0, y: 0, width: 4, height: 4 },
+                   Region::whole(2, 2), Bilinear);
+    // Corners stay on the source pixels, the middle columns mix them.
+    assert_eq!(canvas.get(0, 0), (0, 0, 0));
+    assert_eq!(canvas.get(3, 3), (255, 255, 255));
+    assert_eq!(canvas.get(1, 0), (25, 25, 25));
+    assert_eq!(canvas.get(2, 0), (75, 75, 75));
+}
+
+#[test]
+fn zoomed_region_fills_destination() {
+    let frame = grey_frame();
+    let mut pixels = Vec::from_elem(2 * 2 * 3, 0u8);
+    let mut canvas = Canvas::new(pixels.as_mut_slice(), 2 * 3, 2, 2);
+    let src = Region { x: 1.0, y: 1.0, width: 1.0, height: 1.0 };
+    scale::convert(&frame, &mut canvas, Rect { x: 0, y: 0, width: 2, height: 2 }, src, Nearest);
+    for y in range(0u, 2) {
+        for x in range(0u, 2) {
+            assert_eq!(canvas.get(x, y), (255, 255, 255));
+        }
+    }
+}
+
+// Without a surface the window is the capture size, 1280x720 by default.
+fn view() -> UvcView {
+    Default::default()
+}
+
+#[test]
+fn zoom_keeps_point_under_cursor() {
+    let mut uvcview = view();
+    uvcview.zoom_at(640, 360, 2.0);
+    assert_eq!(uvcview.source_region(), Region { x: 320.0, y: 180.0, width: 640.0, height: 360.0 });
+    uvcview.zoom_at(0, 0, 2.0);
+    assert_eq!(uvcview.source_region(), Region { x: 320.0, y: 180.0, width: 320.0, height: 180.0 });
+}
+
+#[test]
+fn zoom_is_clamped() {
+    let mut uvcview = view();
+    uvcview.zoom_at(100, 100, 0.5);
+    assert_eq!(uvcview.zoom, 1.0);
+    assert_eq!(uvcview.source_region(), Region::whole(1280, 720));
+    for _ in range(0, 100) {
+        uvcview.zoom_at(100, 100, 2.0);
+    }
+    assert_eq!(uvcview.zoom, 64.0);
+}
+
+#[test]
+fn pan_follows_drag_and_stays_in_frame() {
+    let mut uvcview = view();
+    uvcview.zoom_at(640, 360, 2.0);
+    uvcview.pan_by(100, -40);
+    assert_eq!(uvcview.source_region(), Region { x: 270.0, y: 200.0, width: 640.0, height: 360.0 });
+    uvcview.pan_by(-10000, 10000);
+    assert_eq!(uvcview.source_region(), Region { x: 640.0, y: 0.0, width: 640.0, height: 360.0 });
+}
+
+#[test]
+fn window_to_frame_follows_zoom() {
+    let mut uvcview = view();
+    assert_eq!(uvcview.window_to_frame(10, 20), Some((10.5, 20.5)));
+    uvcview.zoom_at(0, 0, 4.0);
+    assert_eq!(uvcview.window_to_frame(10, 20), Some((2.625, 5.125)));
+    uvcview.reset_zoom();
+    assert_eq!(uvcview.window_to_frame(1280, 0), None);
+}
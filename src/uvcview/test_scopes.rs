@@ -0,0 +1,55 @@
+use pixfmt;
+use pixfmt::Frame;
+use scopes;
+use scopes::Scopes;
+use v4l2;
+
+// 4x2 YUYV: a black and a white pixel pair, then two mid grey pairs with
+// strong chroma.
+static YUYV: [u8, ..16] = [
+    16, 128, 235, 128,   235, 128, 235, 128,
+    128, 0, 128, 255,    128, 0, 128, 255,
+];
+
+#[test]
+fn levels_come_from_yuv_samples() {
+    let frame = Frame::new(v4l2::V4L2_PIX_FMT_YUYV, 4, 2, 8, YUYV.as_slice());
+    let scopes = Scopes::compute(&frame);
+    assert_eq!(*scopes.luma.get(16), 1);
+    assert_eq!(*scopes.luma.get(235), 3);
+    assert_eq!(*scopes.luma.get(128), 4);
+    assert_eq!(*scopes.vectorscope.get(128 * 256 + 128), 4);
+    assert_eq!(*scopes.vectorscope.get(255 * 256 + 0), 4);
+}
+
+#[test]
+fn waveform_keeps_columns_apart() {
+    let frame = Frame::new(v4l2::V4L2_PIX_FMT_YUYV, 4, 2, 8, YUYV.as_slice());
+    let scopes = Scopes::compute(&frame);
+    let column = |x: uint| x * scopes::WAVEFORM_COLUMNS / 4;
+    assert_eq!(*scopes.waveform.get(column(0) * 256 + 16), 1);
+    assert_eq!(*scopes.waveform.get(column(1) * 256 + 235), 1);
+    assert_eq!(*scopes.waveform.get(column(0) * 256 + 128), 1);
+    assert_eq!(*scopes.waveform.get(column(3) * 256 + 128), 1);
+}
+
+#[test]
+fn rgb_histogram_matches_conversion() {
+    let frame = Frame::new(v4l2::V4L2_PIX_FMT_YUYV, 4, 2, 8, YUYV.as_slice());
+    let scopes = Scopes::compute(&frame);
+    let (r, g, b) = pixfmt::ycbcr_to_rgb(128, 0, 255);
+    assert_eq!(*scopes.red.get(r as uint), 4);
+    assert_eq!(*scopes.green.get(g as uint), 4);
+    assert_eq!(*scopes.blue.get(b as uint), 4);
+}
+
+#[test]
+fn rgb_frames_are_converted_to_ycbcr() {
+    static RGB: [u8, ..6] = [255, 0, 0, 0, 0, 255];
+    let frame = Frame::new(v4l2::V4L2_PIX_FMT_RGB24, 2, 1, 6, RGB.as_slice());
+    let scopes = Scopes::compute(&frame);
+    assert_eq!(*scopes.red.get(255), 1);
+    assert_eq!(*scopes.blue.get(255), 1);
+    let (luma, _, _) = pixfmt::rgb_to_ycbcr(255, 0, 0);
+    assert_eq!(*scopes.luma.get(luma as uint), 1);
+}
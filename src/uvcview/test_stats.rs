@@ -0,0 +1,72 @@
+use stats::FrameStats;
+
+static FRAME_NS: u64 = 33333333;
+
+#[test]
+fn steady_stream() {
+    let mut stats = FrameStats::new();
+    for i in range(0u32, 31) {
+        stats.update(i, i as u64 * FRAME_NS, false);
+    }
+    assert_eq!(stats.frames, 31);
+    assert_eq!(stats.dropped, 0);
+    assert_eq!(stats.errors, 0);
+    assert!((stats.fps - 30.0).abs() < 0.01);
+    assert!((stats.avg_fps - 30.0).abs() < 0.01);
+    assert!(stats.jitter_ms < 0.001);
+}
+
+#[test]
+fn sequence_gaps_are_drops() {
+    let mut stats = FrameStats::new();
+    stats.update(10, 0, false);
+    stats.update(11, FRAME_NS, false);
+    stats.update(14, 4 * FRAME_NS, false);
+    assert_eq!(stats.dropped, 2);
+    assert!((stats.fps - 10.0).abs() < 0.01);
+}
+
+#[test]
+fn sequence_restart_is_not_a_drop() {
+    let mut stats = FrameStats::new();
+    stats.update(100, 0, false);
+    stats.update(0, FRAME_NS, false);
+    assert_eq!(stats.dropped, 0);
+}
+
+#[test]
+fn sequence_wraps() {
+    let mut stats = FrameStats::new();
+    stats.update(0xffffffff, 0, false);
+    stats.update(1, FRAME_NS, false);
+    assert_eq!(stats.dropped, 1);
+}
+
+#[test]
+fn error_flag_is_counted() {
+    let mut stats = FrameStats::new();
+    stats.update(0, 0, true);
+    stats.update(1, FRAME_NS, false);
+    stats.update(2, 2 * FRAME_NS, true);
+    assert_eq!(stats.errors, 2);
+}
+
+#[test]
+fn uneven_intervals_show_jitter() {
+    let mut stats = FrameStats::new();
+    let mut t = 0;
+    for i in range(0u32, 11) {
+        stats.update(i, t, false);
+        t += if i % 2 == 0 { 30000000 } else { 40000000 };
+    }
+    assert!((stats.jitter_ms - 5.0).abs() < 0.01);
+}
+
+#[test]
+fn log_is_periodic() {
+    let mut stats = FrameStats::new();
+    assert!(!stats.log_due(1000));
+    assert!(!stats.log_due(1000 + 4999999999));
+    assert!(stats.log_due(1000 + 5000000000));
+    assert!(!stats.log_due(1000 + 5000000001));
+}
@@ -0,0 +1,112 @@
+use pixfmt;
+use pixfmt::Frame;
+use testsrc;
+use testsrc::{ColorBars,Gradient,MovingBoxes,TestPattern};
+use time;
+use v4l2;
+
+// Four pixels per bar, so bars line up with 2x2 chroma blocks; too small
+// for the burn-in.
+static WIDTH: uint = 28;
+static HEIGHT: uint = 12;
+
+static FORMATS: [u32, ..10] = [
+    v4l2::V4L2_PIX_FMT_YUYV, v4l2::V4L2_PIX_FMT_YVYU, v4l2::V4L2_PIX_FMT_UYVY, v4l2::V4L2_PIX_FMT_VYUY,
+    v4l2::V4L2_PIX_FMT_RGB24, v4l2::V4L2_PIX_FMT_BGR24, v4l2::V4L2_PIX_FMT_NV12, v4l2::V4L2_PIX_FMT_NV21,
+    v4l2::V4L2_PIX_FMT_YUV420, v4l2::V4L2_PIX_FMT_YVU420,
+];
+
+fn render(pattern: &mut TestPattern, fourcc: u32, width: uint, height: uint) -> Vec<u8> {
+    let bytesperline = pixfmt::min_bytesperline(fourcc, width);
+    let mut data = Vec::from_elem(pixfmt::image_size(fourcc, bytesperline, height), 0u8);
+    pattern.render(fourcc, width, height, bytesperline, data.as_mut_slice());
+    data
+}
+
+fn rgb_at(fourcc: u32, data: &[u8], x: uint, y: uint) -> (u8, u8, u8) {
+    let bytesperline = pixfmt::min_bytesperline(fourcc, WIDTH);
+    Frame::new(fourcc, WIDTH, HEIGHT, bytesperline, data).rgb_at(x, y)
+}
+
+// Equal but for YCbCr rounding.
+fn near(a: (u8, u8, u8), b: (u8, u8, u8)) -> bool {
+    let close = |a: u8, b: u8| (a as int - b as int).abs() <= 6;
+    let ((r0, g0, b0), (r1, g1, b1)) = (a, b);
+    close(r0, r1) && close(g0, g1) && close(b0, b1)
+}
+
+#[test]
+fn patterns_by_name() {
+    assert_eq!(testsrc::pattern_from_str("bars"), Some(ColorBars));
+    assert_eq!(testsrc::pattern_from_str("gradient"), Some(Gradient));
+    assert_eq!(testsrc::pattern_from_str("boxes"), Some(MovingBoxes));
+    assert_eq!(testsrc::pattern_from_str("noise"), None);
+}
+
+#[test]
+fn color_bars_in_every_format() {
+    for &fourcc in FORMATS.iter() {
+        let mut pattern = TestPattern::new(ColorBars, 30.0);
+        let data = render(&mut pattern, fourcc, WIDTH, HEIGHT);
+        let name = pixfmt::fourcc_to_str(fourcc);
+        // Grey, yellow and blue on top, the reverse blue bar in the middle
+        // and white below.
+        assert!(near(rgb_at(fourcc, data.as_slice(), 1, 1), (191, 191, 191)), "{}", name);
+        assert!(near(rgb_at(fourcc, data.as_slice(), 5, 1), (191, 191, 0)), "{}", name);
+        assert!(near(rgb_at(fourcc, data.as_slice(), 25, 1), (0, 0, 191)), "{}", name);
+        assert!(near(rgb_at(fourcc, data.as_slice(), 1, 8), (0, 0, 191)), "{}", name);
+        assert!(near(rgb_at(fourcc, data.as_slice(), 7, 10), (255, 255, 255)), "{}", name);
+    }
+
+    let mut pattern = TestPattern::new(ColorBars, 30.0);
+    let data = render(&mut pattern, v4l2::V4L2_PIX_FMT_GREY, WIDTH, HEIGHT);
+    assert!(near(rgb_at(v4l2::V4L2_PIX_FMT_GREY, data.as_slice(), 1, 1), (191, 191, 191)));
+    assert!(near(rgb_at(v4l2::V4L2_PIX_FMT_GREY, data.as_slice(), 7, 10), (255, 255, 255)));
+}
+
+#[test]
+fn gradient_ramps() {
+    let fourcc = v4l2::V4L2_PIX_FMT_RGB24;
+    let mut pattern = TestPattern::new(Gradient, 30.0);
+    let data = render(&mut pattern, fourcc, WIDTH, HEIGHT);
+    assert_eq!(rgb_at(fourcc, data.as_slice(), 0, 0), (0, 0, 0));
+    assert_eq!(rgb_at(fourcc, data.as_slice(), 27, 0), (255, 255, 255));
+    assert_eq!(rgb_at(fourcc, data.as_slice(), 27, 3), (255, 0, 0));
+    assert_eq!(rgb_at(fourcc, data.as_slice(), 27, 6), (0, 255, 0));
+    assert_eq!(rgb_at(fourcc, data.as_slice(), 27, 9), (0, 0, 255));
+}
+
+#[test]
+fn frames_are_counted_and_boxes_move() {
+    let mut pattern = TestPattern::new(MovingBoxes, 30.0);
+    let first = render(&mut pattern, v4l2::V4L2_PIX_FMT_RGB24, WIDTH, HEIGHT);
+    let second = render(&mut pattern, v4l2::V4L2_PIX_FMT_RGB24, WIDTH, HEIGHT);
+    assert_eq!(pattern.frame_count, 2);
+    assert!(first != second);
+}
+
+#[test]
+fn burn_in() {
+    let (width, height) = (640, 120);
+    let fourcc = v4l2::V4L2_PIX_FMT_RGB24;
+    let mut pattern = TestPattern::new(Gradient, 30.0);
+    let data = render(&mut pattern, fourcc, width, height);
+    let frame = Frame::new(fourcc, width, height, width * 3, data.as_slice());
+    // White text on a black box over the top left of the gradient.
+    assert_eq!(frame.rgb_at(0, 0), (0, 0, 0));
+    let text = range(0, 40u).any(|y| range(0, 200u).any(|x| frame.rgb_at(x, y) == (255, 255, 255)));
+    assert!(text);
+}
+
+#[test]
+fn wait_paces_frames() {
+    let mut pattern = TestPattern::new(Gradient, 100.0);
+    let start = time::precise_time_ns();
+    // The first frame is due at once, the next two 10 ms apart.
+    for _ in range(0, 3) {
+        pattern.wait();
+    }
+    let elapsed = time::precise_time_ns() - start;
+    assert!(elapsed >= 18000000, "{} ns", elapsed);
+    assert!(elapsed < 1000000000, "{} ns", elapsed);
+}
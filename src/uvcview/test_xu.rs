@@ -0,0 +1,83 @@
+use fakedev::FakeDevice;
+use libc::consts::os::posix88::ENOENT;
+use std::io;
+use test_common;
+use uvc;
+use uvcview::UvcView;
+use xu;
+use xu::{Definition,Info};
+
+// A camera with a read-write LED mode and a read-only firmware version.
+fn viewer() -> UvcView {
+    let mut fake = FakeDevice::new();
+    fake.xu_controls = vec!((4, 1, uvc::UVC_CONTROL_CAP_GET | uvc::UVC_CONTROL_CAP_SET, vec!(0, 0)),
+                            (4, 3, uvc::UVC_CONTROL_CAP_GET, Vec::from_slice(bytes!("1.2.3", 0))));
+    test_common::viewer(fake)
+}
+
+#[test]
+fn definitions() {
+    let text = "# Vendor controls\n\nled-mode 4 1\nfirmware 0x04 0x03  # read-only\n";
+    let definitions = xu::parse_definitions(text).unwrap();
+    assert_eq!(definitions, vec!(Definition { name: "led-mode".to_owned(), unit: 4, selector: 1 },
+                                 Definition { name: "firmware".to_owned(), unit: 4, selector: 3 }));
+
+    assert_eq!(xu::resolve(definitions.as_slice(), "firmware"), Some((4, 3)));
+    assert_eq!(xu::resolve(definitions.as_slice(), "6:0x10"), Some((6, 16)));
+    assert_eq!(xu::resolve(definitions.as_slice(), "hdr"), None);
+
+    assert_eq!(xu::parse_definitions("led-mode 4\n").err(),
+               Some("line 1: expected <name> <unit> <selector>".to_owned()));
+    assert!(xu::parse_definitions("led 4 1\nhdr 4 256\n").is_err());
+    assert!(xu::parse_definitions("4:1 4 1\n").is_err());
+}
+
+#[test]
+fn values() {
+    assert_eq!(xu::parse_value("01:ff:0a"), Some(vec!(0x01, 0xff, 0x0a)));
+    assert_eq!(xu::parse_value("01ff0A"), Some(vec!(0x01, 0xff, 0x0a)));
+    assert_eq!(xu::parse_value("1ff"), None);
+    assert_eq!(xu::parse_value("0g"), None);
+    assert_eq!(xu::parse_value(""), None);
+
+    assert_eq!(xu::value_to_str(&[0x01, 0xff]).as_slice(), "01:ff");
+    assert_eq!(xu::value_to_str(bytes!("1.2", 0)).as_slice(), "31:2e:32:00 \"1.2\"");
+    assert_eq!(format!("{}", Info(uvc::UVC_CONTROL_CAP_GET | uvc::UVC_CONTROL_CAP_AUTOUPDATE)).as_slice(),
+               "get,autoupdate");
+}
+
+#[test]
+fn get_and_set() {
+    let mut uvcview = viewer();
+    let (info, value) = uvcview.xu_get(4, 1).unwrap();
+    assert!(info.can_get() && info.can_set());
+    assert_eq!(value, vec!(0, 0));
+
+    uvcview.xu_set(4, 1, &[1, 0x20]).unwrap();
+    let (_, value) = uvcview.xu_get(4, 1).unwrap();
+    assert_eq!(value, vec!(1, 0x20));
+
+    let (info, value) = uvcview.xu_get(4, 3).unwrap();
+    assert!(!info.can_set());
+    assert_eq!(value.len(), 6);
+}
+
+#[test]
+fn set_errors() {
+    let mut uvcview = viewer();
+    let e = uvcview.xu_set(4, 1, &[1]).unwrap_err();
+    assert_eq!(e.kind, io::InvalidInput);
+    assert_eq!(e.detail, Some("extension unit 4 selector 1 takes 2 bytes, not 1".to_owned()));
+
+    assert_eq!(uvcview.xu_set(4, 3, &[0, 0, 0, 0, 0, 0]).unwrap_err().desc,
+               "xu_set(): control cannot be set");
+
+    let e = uvcview.xu_get(5, 1).unwrap_err();
+    assert_eq!(e.desc, "xu_get(): GET_INFO failed");
+    assert_eq!(xu::get_len(&mut uvcview.device, 5, 1), Err(ENOENT));
+
+    uvcview.monitor = true;
+    assert_eq!(uvcview.xu_set(4, 1, &[1, 0]).unwrap_err().kind, io::PermissionDenied);
+    let (_, value) = uvcview.xu_get(4, 1).unwrap();
+    assert_eq!(value, vec!(0, 0));
+}
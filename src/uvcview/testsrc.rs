@@ -0,0 +1,207 @@
+// Synthetic frame source, a userspace stand-in for the vivid driver.
+//
+// Frames are drawn as RGB and packed into the requested fourcc, so every
+// format the viewer can display can also be generated.
+
+use std::io::timer::sleep;
+use font;
+use pixfmt;
+use time;
+
+#[deriving(Eq, Show)]
+pub enum Pattern {
+    ColorBars,
+    Gradient,
+    MovingBoxes,
+}
+
+pub fn pattern_from_str(s: &str) -> Option<Pattern> {
+    match s {
+        "bars" => Some(ColorBars),
+        "gradient" => Some(Gradient),
+        "boxes" => Some(MovingBoxes),
+        _ => None
+    }
+}
+
+pub struct TestPattern {
+    pub pattern: Pattern,
+    pub fps: f64,
+    pub frame_count: u32,
+    next_frame_ns: u64,
+    image: Vec<(u8, u8, u8)>,
+}
+
+// 75% SMPTE colour bars: grey, yellow, cyan, green, magenta, red, blue.
+static TOP_BARS: [(u8, u8, u8), ..7] = [
+    (191, 191, 191), (191, 191, 0), (0, 191, 191), (0, 191, 0),
+    (191, 0, 191), (191, 0, 0), (0, 0, 191),
+];
+
+// Reverse blue bars below the main bars.
+static MIDDLE_BARS: [(u8, u8, u8), ..7] = [
+    (0, 0, 191), (19, 19, 19), (191, 0, 191), (19, 19, 19),
+    (0, 191, 191), (19, 19, 19), (191, 191, 191),
+];
+
+// -I, white, +Q and black, followed by the PLUGE below the middle bars.
+static BOTTOM_BARS: [(u8, u8, u8), ..4] = [
+    (0, 33, 76), (255, 255, 255), (50, 0, 106), (19, 19, 19),
+];
+
+static PLUGE: [(u8, u8, u8), ..3] = [
+    (9, 9, 9), (19, 19, 19), (29, 29, 29),
+];
+
+impl TestPattern {
+    pub fn new(pattern: Pattern, fps: f64) -> TestPattern {
+        TestPattern {
+            pattern: pattern,
+            fps: fps,
+            frame_count: 0,
+            next_frame_ns: 0,
+            image: vec!(),
+        }
+    }
+
+    // Blocks until the next frame is due according to `fps`.
+    pub fn wait(&mut self) {
+        let now = time::precise_time_ns();
+        if self.next_frame_ns == 0 {
+            self.next_frame_ns = now;
+        }
+        if self.next_frame_ns > now {
+            sleep((self.next_frame_ns - now) / 1000000);
+        }
+        self.next_frame_ns += (1000000000.0 / self.fps) as u64;
+    }
+
+    // Draws the next frame into `dest` as a contiguous `fourcc` image.
+    pub fn render(&mut self, fourcc: u32, width: uint, height: uint,
+                  bytesperline: uint, dest: &mut [u8]) {
+        self.image.clear();
+        self.image.grow(width * height, &(0u8, 0u8, 0u8));
+
+        match self.pattern {
+            ColorBars => self.draw_color_bars(width, height),
+            Gradient => self.draw_gradient(width, height),
+            MovingBoxes => self.draw_moving_boxes(width, height),
+        }
+        self.draw_burn_in(width, height);
+
+        pixfmt::pack(fourcc, width, height, bytesperline, self.image.as_slice(), dest);
+        self.frame_count += 1;
+    }
+
+    fn fill_rect(&mut self, width: uint, height: uint,
+                 x0: uint, y0: uint, w: uint, h: uint, color: (u8, u8, u8)) {
+        let image = self.image.as_mut_slice();
+        for y in range(y0, y0 + h) {
+            if y >= height {
+                break;
+            }
+            for x in range(x0, x0 + w) {
+                if x >= width {
+                    break;
+                }
+                image[y * width + x] = color;
+            }
+        }
+    }
+
+    fn draw_color_bars(&mut self, width: uint, height: uint) {
+        let top_height = height * 2 / 3;
+        let middle_height = height / 12;
+        let bottom_height = height - top_height - middle_height;
+
+        for i in range(0u, 7) {
+            let x0 = width * i / 7;
+            let x1 = width * (i + 1) / 7;
+            self.fill_rect(width, height, x0, 0, x1 - x0, top_height, TOP_BARS[i]);
+            self.fill_rect(width, height, x0, top_height, x1 - x0, middle_height,
+                           MIDDLE_BARS[i]);
+        }
+
+        // Four blocks of 5/4 bar width, then the PLUGE under the fifth bar
+        // and black under the last two.
+        let y0 = top_height + middle_height;
+        let block = width * 5 / 28;
+        for i in range(0u, 4) {
+            self.fill_rect(width, height, block * i, y0, block, bottom_height, BOTTOM_BARS[i]);
+        }
+        let pluge_x = block * 4;
+        let pluge_w = width * 5 / 7 - pluge_x;
+        for i in range(0u, 3) {
+            let x0 = pluge_x + pluge_w * i / 3;
+            let x1 = pluge_x + pluge_w * (i + 1) / 3;
+            self.fill_rect(width, height, x0, y0, x1 - x0, bottom_height, PLUGE[i]);
+        }
+        let x0 = width * 5 / 7;
+        self.fill_rect(width, height, x0, y0, width - x0, bottom_height, (19, 19, 19));
+    }
+
+    fn draw_gradient(&mut self, width: uint, height: uint) {
+        // Luma ramp on top, then red, green and blue ramps.
+        let band = if height >= 4 { height / 4 } else { 1 };
+        let span = if width > 1 { width - 1 } else { 1 };
+        let image = self.image.as_mut_slice();
+        for y in range(0, height) {
+            for x in range(0, width) {
+                let v = (x * 255 / span) as u8;
+                image[y * width + x] = match y / band {
+                    0 => (v, v, v),
+                    1 => (v, 0, 0),
+                    2 => (0, v, 0),
+                    _ => (0, 0, v)
+                };
+            }
+        }
+    }
+
+    fn draw_moving_boxes(&mut self, width: uint, height: uint) {
+        self.fill_rect(width, height, 0, 0, width, height, (64, 64, 64));
+
+        let size = if width < height { width / 6 } else { height / 6 };
+        if size == 0 {
+            return;
+        }
+        let colors = [(255u8, 255u8, 255u8), (255, 0, 0), (0, 255, 0), (0, 0, 255)];
+        let n = self.frame_count as uint;
+        let x_span = width - size;
+        let y_span = height - size;
+        for i in range(0u, 4) {
+            let speed = 2 * (i + 1);
+            // Bounce back and forth along each axis.
+            let tx = (n * speed + i * x_span / 4) % (2 * x_span);
+            let ty = (n * speed / 2 + i * y_span / 3) % (2 * y_span);
+            let x = if tx < x_span { tx } else { 2 * x_span - tx };
+            let y = if ty < y_span { ty } else { 2 * y_span - ty };
+            self.fill_rect(width, height, x, y, size, size, colors[i]);
+        }
+    }
+
+    fn draw_burn_in(&mut self, width: uint, height: uint) {
+        let now = time::now();
+        let timestamp = format!("{}.{:03d}", now.strftime("%H:%M:%S"),
+                                now.tm_nsec / 1000000);
+        let counter = format!("{:06u}", self.frame_count);
+
+        let scale = if width >= 640 { 3 } else if width >= 320 { 2 } else { 1 };
+        let margin = 2 * scale;
+        let line_height = font::text_height(scale) + margin;
+        let box_width = font::text_width(timestamp.as_slice(), scale) + 2 * margin;
+        let box_height = 2 * line_height + margin;
+        if box_width > width || box_height > height {
+            return;
+        }
+        self.fill_rect(width, height, 0, 0, box_width, box_height, (0, 0, 0));
+
+        let image = self.image.as_mut_slice();
+        font::draw_text(counter.as_slice(), margin, margin, scale, |x, y| {
+            image[y * width + x] = (255, 255, 255);
+        });
+        font::draw_text(timestamp.as_slice(), margin, margin + line_height, scale, |x, y| {
+            image[y * width + x] = (255, 255, 255);
+        });
+    }
+}
@@ -0,0 +1,100 @@
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+// From <linux/uvcvideo.h>, <linux/usb/video.h> and the UVC 1.5
+// specification: the uvcvideo driver's own ioctls, which reach past V4L2
+// to the camera's units, and the payload headers of its metadata nodes.
+
+use std::default::Default;
+
+// Class-specific requests, the `query` of a uvc_xu_control_query.
+//#define UVC_RC_UNDEFINED 0x00
+pub static UVC_RC_UNDEFINED: u8 = 0x00;
+//#define UVC_SET_CUR 0x01
+pub static UVC_SET_CUR: u8 = 0x01;
+//#define UVC_GET_CUR 0x81
+pub static UVC_GET_CUR: u8 = 0x81;
+//#define UVC_GET_MIN 0x82
+pub static UVC_GET_MIN: u8 = 0x82;
+//#define UVC_GET_MAX 0x83
+pub static UVC_GET_MAX: u8 = 0x83;
+//#define UVC_GET_RES 0x84
+pub static UVC_GET_RES: u8 = 0x84;
+//#define UVC_GET_LEN 0x85
+pub static UVC_GET_LEN: u8 = 0x85;
+//#define UVC_GET_INFO 0x86
+pub static UVC_GET_INFO: u8 = 0x86;
+//#define UVC_GET_DEF 0x87
+pub static UVC_GET_DEF: u8 = 0x87;
+
+// Bits of the GET_INFO byte.
+//#define UVC_CONTROL_CAP_GET (1 << 0)
+pub static UVC_CONTROL_CAP_GET: u8 = 0x1;
+//#define UVC_CONTROL_CAP_SET (1 << 1)
+pub static UVC_CONTROL_CAP_SET: u8 = 0x2;
+//#define UVC_CONTROL_CAP_DISABLED (1 << 2)
+pub static UVC_CONTROL_CAP_DISABLED: u8 = 0x4;
+//#define UVC_CONTROL_CAP_AUTOUPDATE (1 << 3)
+pub static UVC_CONTROL_CAP_AUTOUPDATE: u8 = 0x8;
+//#define UVC_CONTROL_CAP_ASYNCHRONOUS (1 << 4)
+pub static UVC_CONTROL_CAP_ASYNCHRONOUS: u8 = 0x10;
+
+// bmHeaderInfo bits of a payload header.
+//#define UVC_STREAM_EOH (1 << 7)
+pub static UVC_STREAM_EOH: u8 = 0x80;
+//#define UVC_STREAM_ERR (1 << 6)
+pub static UVC_STREAM_ERR: u8 = 0x40;
+//#define UVC_STREAM_STI (1 << 5)
+pub static UVC_STREAM_STI: u8 = 0x20;
+//#define UVC_STREAM_RES (1 << 4)
+pub static UVC_STREAM_RES: u8 = 0x10;
+//#define UVC_STREAM_SCR (1 << 3)
+pub static UVC_STREAM_SCR: u8 = 0x8;
+//#define UVC_STREAM_PTS (1 << 2)
+pub static UVC_STREAM_PTS: u8 = 0x4;
+//#define UVC_STREAM_EOF (1 << 1)
+pub static UVC_STREAM_EOF: u8 = 0x2;
+//#define UVC_STREAM_FID (1 << 0)
+pub static UVC_STREAM_FID: u8 = 0x1;
+
+pub struct uvc_xu_control_query {
+    pub unit: u8,
+    pub selector: u8,
+    pub query: u8,
+    pub size: u16,
+    pub data: *mut u8,
+}
+impl Default for uvc_xu_control_query {
+    fn default() -> uvc_xu_control_query {
+        uvc_xu_control_query {
+            unit: Default::default(),
+            selector: Default::default(),
+            query: Default::default(),
+            size: Default::default(),
+            data: 0 as *mut u8,
+        }
+    }
+}
+
+// One block of a V4L2_META_FMT_UVC buffer: the host's CLOCK_MONOTONIC
+// time and USB frame number when a payload header arrived, then the
+// header from bHeaderLength on. `length` - 2 more header bytes follow.
+#[packed]
+pub struct uvc_meta_buf {
+    pub ns: u64,
+    pub sof: u16,
+    pub length: u8,
+    pub flags: u8,
+}
+
+// sizeof() of the ioctl arguments, as in v4l2::size.
+#[allow(non_uppercase_statics)]
+pub mod size {
+    #[cfg(target_word_size = "64")]
+    pub static uvc_xu_control_query: u32 = 16;
+    #[cfg(target_word_size = "32")]
+    pub static uvc_xu_control_query: u32 = 12;
+}
+
+//#define UVCIOC_CTRL_QUERY _IOWR('u', 0x21, struct uvc_xu_control_query)
+pub static UVCIOC_CTRL_QUERY: u32 = iowr!('u', 0x21, size::uvc_xu_control_query);
@@ -1,54 +1,262 @@
-use libc::consts::os::posix88::{EINVAL,MAP_SHARED,EAGAIN};
+use libc::consts::os::posix88::{EINVAL,EAGAIN,EBUSY,ENOTTY,ENOENT,ENOLINK,ENOLCK,ERANGE};
 use libc::{c_int,O_RDWR};
-use libc;
-use std::mem::transmute;
 use std::default::Default;
+use std::cmp::{min,max};
 use std::fmt;
 use std::io::{IoResult,IoError,OtherIoError,TypeUnknown,MismatchedFileTypeForOperation};
 use std::io;
 use std::os::error_string;
 use sdl;
 use std::os;
-use std::os::{MemoryMap,MapReadable,MapWritable,MapFd,MapNonStandardFlags};
+use std::os::{MemoryMap,MapReadable,MapWritable};
+use pixfmt;
+use pixfmt::{Frame,Plane};
+use overlay::Canvas;
+use overlay;
+use scale;
+use analog;
+use analog::{Input,Standard};
+use assist::Assist;
+use controls::Control;
+use deinterlace::Deinterlacer;
+use device;
+use device::{Device,BufType,Field,Fourcc,Memory,PixFormat,PlaneFormat,c_string};
+use dv;
+use dv::Timings;
+use events;
+use events::Event;
+use guides;
+use guides::Guides;
+use latency;
+use latency::Latency;
+use meta::MetaNode;
+use scopes;
+use scopes::Scopes;
+use scale::{Filter,Nearest,ScaleMode,Fit,Rect,Region};
+use stats::FrameStats;
+use testsrc::TestPattern;
+use time;
 use v4l2;
-use v4l2::{v4l2_capability,v4l2_crop,v4l2_cropcap,v4l2_format,v4l2_ioctl};
+use v4l2::{v4l2_rect,v4l2_std_id};
+use xu;
+
+#[deriving(Eq, Show)]
+pub enum IoMethod {
+    IoRead,
+    IoMmap,
+    IoUserPtr,
+    IoDmaBuf,
+}
+
+pub fn priority_from_str(s: &str) -> Option<u32> {
+    match s {
+        "background" => Some(v4l2::V4L2_PRIORITY_BACKGROUND),
+        "interactive" => Some(v4l2::V4L2_PRIORITY_INTERACTIVE),
+        "record" => Some(v4l2::V4L2_PRIORITY_RECORD),
+        _ => None
+    }
+}
+
+pub fn priority_to_str(priority: u32) -> &'static str {
+    match priority {
+        v4l2::V4L2_PRIORITY_UNSET => "unset",
+        v4l2::V4L2_PRIORITY_BACKGROUND => "background",
+        v4l2::V4L2_PRIORITY_INTERACTIVE => "interactive",
+        v4l2::V4L2_PRIORITY_RECORD => "record",
+        _ => "?"
+    }
+}
+
+pub fn io_method_from_str(s: &str) -> Option<IoMethod> {
+    match s {
+        "read" => Some(IoRead),
+        "mmap" => Some(IoMmap),
+        "userptr" => Some(IoUserPtr),
+        "dmabuf" => Some(IoDmaBuf),
+        _ => None
+    }
+}
 
 struct Buffer {
+    // One entry per memory plane; single-planar formats have exactly one.
+    pub planes: Vec<BufferPlane>,
+}
+
+struct BufferPlane {
     pub memory_map: MemoryMap,
     pub length: u32,
+    // dma-buf exported with VIDIOC_EXPBUF, -1 if not exported.
+    pub dmabuf_fd: c_int,
+}
+
+impl Buffer {
+    fn new(memory_map: MemoryMap, length: u32) -> Buffer {
+        Buffer {
+            planes: vec!(BufferPlane {
+                memory_map: memory_map,
+                length: length,
+                dmabuf_fd: -1,
+            }),
+        }
+    }
 }
 
 pub struct UvcView {
     pub device_path: Path,
-    pub fd: c_int,
+    pub device: Device,
+    // Card name reported by VIDIOC_QUERYCAP.
+    pub card: ~str,
     pub width: u32,
     pub height: u32,
+    pub pixelformat: Fourcc,
+    pub bytesperline: u32,
+    pub sizeimage: u32,
+    pub plane_fmt: Vec<PlaneFormat>,
+    // Field order, AnyField to let the driver choose.
+    pub field: Field,
+    pub deinterlacer: Deinterlacer,
+    // Set when the device is driven through the multi-planar API.
+    pub mplane: bool,
+    // Negotiated time per frame as (numerator, denominator) seconds, if the
+    // driver reports one.
+    pub frame_interval: Option<(u32, u32)>,
+    // Access priority to claim with VIDIOC_S_PRIORITY, None to keep the
+    // driver's default.
+    pub priority: Option<u32>,
+    // Read-only monitor: watch the stream and controls as they are, never
+    // changing format, source, crop or controls. Runs at background
+    // priority unless told otherwise.
+    pub monitor: bool,
+    // Video input, TV standard and tuner frequency in MHz to select in
+    // init(), where a standard of V4L2_STD_UNKNOWN asks to detect it. After
+    // init() they hold what is selected, as far as the driver tells.
+    pub input: Option<u32>,
+    pub std: Option<v4l2_std_id>,
+    pub frequency: Option<f64>,
+    // How to pick the timings of an HDMI, DVI or SDI input in init(), and
+    // the timings applied; None for inputs without DV timings.
+    pub dv_request: Option<dv::Request>,
+    pub dv_timings: Option<Timings>,
+    // Controls of the device, kept current by control events, and the
+    // latest one that changed with the time it did.
+    pub controls: Vec<Control>,
+    control_changed: Option<(u32, u64)>,
+    // Crop rectangle to request in init(), in sensor pixels, None for the
+    // driver's default.
+    pub crop: Option<Rect>,
+    // Crop rectangle the driver settled on, and its default, if the driver
+    // supports cropping.
+    pub crop_rect: Option<Rect>,
+    pub crop_default: Option<Rect>,
+    // Pixel aspect (y / x) as (numerator, denominator) from VIDIOC_CROPCAP.
+    pub pixel_aspect: Option<(u32, u32)>,
+    // Corners of the rubber band being dragged out, in window pixels.
+    pub selection: Option<((uint, uint), (uint, uint))>,
+    pub io_method: IoMethod,
+    pub buffer_count: u32,
     pub buffers: Vec<Buffer>,
+    pub streaming: bool,
     pub surface: Option<sdl::video::Surface>,
+    pub filter: Filter,
+    pub scale_mode: ScaleMode,
+    // Digital zoom factor, and top left corner of the visible region in
+    // frame pixels.
+    pub zoom: f64,
+    pub pan: (f64, f64),
+    // Mouse position in the window, and whether to inspect the pixel there.
+    pub cursor: Option<(uint, uint)>,
+    pub show_inspector: bool,
+    pub show_histogram: bool,
+    pub show_waveform: bool,
+    pub show_vectorscope: bool,
+    pub assist: Assist,
+    pub guides: Guides,
+    // Region of interest in frame pixels.
+    pub roi: Option<Rect>,
+    pub show_roi: bool,
+    pub stats: FrameStats,
+    pub show_stats: bool,
+    pub show_hud: bool,
+    pub test_pattern: Option<TestPattern>,
+    // Metadata node of the camera, whose payload headers are matched with
+    // the frames.
+    pub meta: Option<MetaNode>,
+    // Latency measurement, None when not measuring.
+    pub latency: Option<Latency>,
 }
 
 impl Default for UvcView {
     fn default() -> UvcView {
         UvcView {
             device_path: Path::new("/dev/video0"),
-            fd: -1,
+            device: Default::default(),
+            card: "".to_owned(),
             width: 1280,
             height: 720,
+            pixelformat: Fourcc(v4l2::V4L2_PIX_FMT_YUYV),
+            bytesperline: 0,
+            sizeimage: 0,
+            plane_fmt: vec!(),
+            field: device::AnyField,
+            deinterlacer: Deinterlacer::new(),
+            mplane: false,
+            frame_interval: None,
+            priority: None,
+            monitor: false,
+            input: None,
+            std: None,
+            frequency: None,
+            dv_request: None,
+            dv_timings: None,
+            controls: vec!(),
+            control_changed: None,
+            crop: None,
+            crop_rect: None,
+            crop_default: None,
+            pixel_aspect: None,
+            selection: None,
+            io_method: IoMmap,
+            buffer_count: 4,
             buffers: vec!(),
+            streaming: false,
             surface: None,
+            filter: Nearest,
+            scale_mode: Fit,
+            zoom: 1.0,
+            pan: (0.0, 0.0),
+            cursor: None,
+            show_inspector: false,
+            show_histogram: false,
+            show_waveform: false,
+            show_vectorscope: false,
+            assist: Assist::new(),
+            guides: Guides::new(),
+            roi: None,
+            show_roi: false,
+            stats: FrameStats::new(),
+            show_stats: false,
+            show_hud: false,
+            test_pattern: None,
+            meta: None,
+            latency: None,
         }
     }
 }
 
 impl fmt::Show for UvcView {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "device_path : {}\nfd : {}\nwidth : {}\nheight : {}",
-               self.device_path.display(), self.fd, self.width, self.height)
+        write!(f, "device_path : {}\nfd : {}\nwidth : {}\nheight : {}\npixelformat : {}\nio : {}\nbuffers : {}",
+               self.device_path.display(), self.device.fd, self.width, self.height,
+               self.pixelformat, self.io_method, self.buffers.len())
     }
 }
 
 impl UvcView {
     pub fn open<'a>(&'a mut self) -> IoResult<&'a mut UvcView> {
+        if self.test_pattern.is_some() {
+            return Ok(self);
+        }
+
         match self.device_path.stat() {
             Ok(stat) => {
                 if stat.kind != io::TypeUnknown/*TypeCharacter?*/ {
@@ -65,40 +273,65 @@ impl UvcView {
             }
         }
 
-        static O_NONBLOCK: c_int = 04000;
-        match self.device_path.with_c_str(|path| {
-            unsafe { libc::open(path, O_RDWR | O_NONBLOCK, 0) }
-        }) {
-            -1 => {
+        match Device::open(&self.device_path) {
+            Ok(device) => {
+                self.device = device;
+                return Ok(self);
+            }
+            Err(errno) => {
                 return Err(IoError {
                     kind: io::OtherIoError,
                     desc: "open() failed",
-                    detail: Some(error_string(os::errno() as uint))
+                    detail: Some(error_string(errno as uint))
                 });
             }
-            fd => {
-                self.fd = fd;
-                return Ok(self);
-            }
         }
     }
 
     pub fn init<'a>(&'a mut self) -> IoResult<&'a mut UvcView> {
-        let mut cap: v4l2::v4l2_capability = Default::default();
-        match v4l2_ioctl(self.fd, v4l2::VIDIOC_QUERYCAP, unsafe { transmute(&mut cap) }) {
-            Ok(_) => {
-                if (cap.capabilities & v4l2::V4L2_CAP_VIDEO_CAPTURE) == 0 {
+        if self.test_pattern.is_some() {
+            return self.init_test_pattern();
+        }
+
+        match self.device.query_cap() {
+            Ok(cap) => {
+                self.card = cap.card.clone();
+                if !cap.has(v4l2::V4L2_CAP_VIDEO_CAPTURE) {
+                    if !cap.has(v4l2::V4L2_CAP_VIDEO_CAPTURE_MPLANE) {
+                        return Err(IoError {
+                            kind: io::OtherIoError,
+                            desc: "init(): V4L2_CAP_VIDEO_CAPTURE not supported",
+                            detail: Some(format!("{} is no video capture device", self.device_path.display()))
+                        });
+                    }
+                    self.mplane = true;
+                }
+                if !cap.has(v4l2::V4L2_CAP_STREAMING) {
+                    if self.io_method == IoMmap && cap.has(v4l2::V4L2_CAP_READWRITE) {
+                        info!("{} does not support streaming i/o, falling back to read()",
+                              self.device_path.display());
+                        self.io_method = IoRead;
+                    } else if self.io_method != IoRead {
+                        return Err(IoError {
+                            kind: io::OtherIoError,
+                            desc: "init(): V4L2_CAP_STREAMING not supported",
+                            detail: Some(format!("{} dose not support streaming i/o", self.device_path.display()))
+                        });
+                    }
+                }
+                if self.io_method == IoRead && !cap.has(v4l2::V4L2_CAP_READWRITE) {
                     return Err(IoError {
                         kind: io::OtherIoError,
-                        desc: "init(): V4L2_CAP_VIDEO_CAPTURE not supported",
-                        detail: Some(format!("{} is no video capture device", self.device_path.display()))
+                        desc: "init(): V4L2_CAP_READWRITE not supported",
+                        detail: Some(format!("{} does not support read i/o", self.device_path.display()))
                     });
                 }
-                if (cap.capabilities & v4l2::V4L2_CAP_STREAMING) == 0 {
+                if self.io_method == IoRead && self.mplane {
                     return Err(IoError {
                         kind: io::OtherIoError,
-                        desc: "init(): V4L2_CAP_STREAMING not supported",
-                        detail: Some(format!("{} dose not support streaming i/o", self.device_path.display()))
+                        desc: "init(): read i/o not supported on multi-planar devices",
+                        detail: Some(format!("{} only supports the multi-planar API",
+                                             self.device_path.display()))
                     });
                 }
             }
@@ -119,290 +352,1946 @@ impl UvcView {
             }
         }
 
-        // Select video input, video standard and tune here.
-
-        let mut cropcap: v4l2::v4l2_cropcap = Default::default();
+        let priority = if self.monitor {
+            Some(self.priority.unwrap_or(v4l2::V4L2_PRIORITY_BACKGROUND))
+        } else {
+            self.priority
+        };
+        match priority {
+            Some(priority) => try!(self.set_priority(priority)),
+            None => {}
+        }
 
-        match v4l2_ioctl(self.fd, v4l2::VIDIOC_CROPCAP, unsafe { transmute(&mut cropcap) }) {
-            Ok(_) => {
-                let mut crop: v4l2::v4l2_crop = Default::default();
-                crop._type = v4l2::V4L2_BUF_TYPE_VIDEO_CAPTURE;
-                crop.c = cropcap.defrect;
+        try!(self.select_source());
+        try!(self.configure());
+        match self.meta {
+            Some(ref mut meta) => try!(meta.init(self.buffer_count, self.monitor)),
+            None => {}
+        }
+        self.controls = self.enum_controls();
+        self.subscribe_events();
+        Ok(self)
+    }
 
+    // Negotiates crop, format and frame rate and sets up the buffers; the
+    // part of init() that is redone by reconfigure().
+    fn configure(&mut self) -> IoResult<()> {
+        let buf_type = self.buf_type();
+        let cropcap = self.device.crop_cap(buf_type).ok();
+        match cropcap {
+            Some(cropcap) => {
+                let aspect = cropcap.pixelaspect;
+                if aspect.numerator != 0 && aspect.denominator != 0 {
+                    self.pixel_aspect = Some((aspect.numerator, aspect.denominator));
+                    info!("pixel aspect {}/{}", aspect.numerator, aspect.denominator);
+                }
+            }
+            None => {
+                // Errors ignored.
+            }
+        }
+        // Drivers with the selection API need not implement VIDIOC_CROPCAP.
+        self.crop_default = match self.device.get_selection(buf_type, v4l2::V4L2_SEL_TGT_CROP_DEFAULT) {
+            Ok(rect) => Some(from_v4l2_rect(&rect)),
+            Err(_) => cropcap.map(|cropcap| from_v4l2_rect(&cropcap.defrect))
+        };
 
-                match v4l2_ioctl(self.fd, v4l2::VIDIOC_S_CROP, unsafe { transmute(&mut crop) }) {
+        match (self.crop, self.crop_default) {
+            (Some(rect), _) => {
+                try!(self.apply_crop(rect));
+            }
+            (None, Some(rect)) if !self.monitor => {
+                match self.apply_crop(rect) {
                     Ok(_) => {}
-                    Err(EINVAL) => {
-                        // Cropping not supported.
-                    }
                     Err(_) => {
-                        // Errors ignored.
+                        // Errors ignored, cropping is optional.
                     }
                 }
             }
-            Err(_) => {
-                // Errors ignored.
+            _ => {
+                // A monitor shows the crop the owner of the device set.
+                match self.device.get_selection(buf_type, v4l2::V4L2_SEL_TGT_CROP) {
+                    Ok(rect) => self.crop_rect = Some(from_v4l2_rect(&rect)),
+                    Err(_) => {}
+                }
             }
         }
 
-        let mut fmt: v4l2_format = Default::default();
-        fmt._type = v4l2::V4L2_BUF_TYPE_VIDEO_CAPTURE;
-        let pix = fmt.fmt.pix();
-        unsafe {
-            (*pix).width = self.width;
-            (*pix).height= self.height;
-            (*pix).pixelformat = v4l2::V4L2_PIX_FMT_YUYV;
-            (*pix).field = v4l2::V4L2_FIELD_INTERLACED; // TODO
-        }
+        let (width, height, field) = (self.width, self.height, self.field);
+        let format = self.pix_format(width, height, field);
 
-        match v4l2_ioctl(self.fd, v4l2::VIDIOC_S_FMT, unsafe { transmute(&mut fmt) }) {
-            Ok(_) => {}
+        // A monitor takes the format as the owner of the device set it.
+        let result = if self.monitor {
+            self.device.get_format(buf_type)
+        } else {
+            self.device.set_format(&format)
+        };
+        // Note VIDIOC_S_FMT may change width and height
+        let format = match result {
+            Ok(format) => checked_format(format),
             Err(errno) => {
-                return Err(IoError {
-                    kind: io::OtherIoError,
-                    desc: "init(): ioctl() returns -1",
-                    detail: Some(error_string(errno as uint))
-                });
+                return Err(self.ioctl_error("init(): ioctl() returns -1", None, errno));
             }
+        };
+
+        let pixelformat = format.pixelformat;
+        if !pixfmt::is_supported(pixelformat.code()) ||
+           format.planes.len() != pixfmt::num_planes(pixelformat.code()) {
+            return Err(IoError {
+                kind: io::OtherIoError,
+                desc: "init(): unsupported pixel format",
+                detail: Some(format!("{} selected {}, which cannot be displayed",
+                                     self.device_path.display(), pixelformat))
+            });
+        }
+        self.pixelformat = pixelformat;
+        self.width = format.width;
+        self.height = format.height;
+        self.field = match format.field {
+            // Drivers must pick an order; treat one that does not as progressive.
+            device::AnyField => device::Progressive,
+            field => field
+        };
+        self.bytesperline = format.planes.get(0).bytesperline;
+        self.sizeimage = format.planes.iter().fold(0, |sum, plane| sum + plane.sizeimage);
+        self.plane_fmt = format.planes;
+        self.deinterlacer.reset();
+        if self.field != device::Progressive {
+            info!("field order {}", self.field.name());
         }
 
-        // Note VIDIOC_S_FMT may change width and height
+        self.frame_interval = None;
+        match self.device.get_parm(buf_type) {
+            Ok(parm) => {
+                let timeperframe = parm.timeperframe;
+                if timeperframe.numerator != 0 && timeperframe.denominator != 0 {
+                    self.frame_interval = Some((timeperframe.numerator, timeperframe.denominator));
+                }
+            }
+            Err(_) => {
+                // Errors ignored, the frame rate is only reported.
+            }
+        }
+
+        match self.io_method {
+            IoRead => self.init_read(),
+            IoMmap => self.init_mmap(),
+            IoUserPtr => self.init_userptr(),
+            IoDmaBuf => self.init_dmabuf(),
+        }
+    }
+
+    // Stops streaming and frees the buffers, applies `change`, then
+    // negotiates the format and buffers again and resumes streaming. The
+    // stream comes back even if `change` fails; its error is returned.
+    pub fn reconfigure(&mut self, change: |&mut UvcView| -> IoResult<()>) -> IoResult<()> {
+        if self.test_pattern.is_some() {
+            return change(self);
+        }
+        let streaming = self.streaming;
+        if streaming {
+            self.stop_capturing();
+        }
+        self.release_buffers();
+        let result = change(self);
+        try!(self.configure());
+        self.deinterlacer.reset();
+        // The view into a frame of another size means nothing.
+        self.reset_zoom();
+        if streaming {
+            self.start_capturing();
+        }
+        result
+    }
 
-        // Buggy driver paranoia
-        unsafe {
-            let mut min = (*pix).width * 2;
-            if (*pix).bytesperline < min {
-                (*pix).bytesperline = min;
+    fn release_buffers(&mut self) {
+        // Unmapping comes first, drivers refuse to free mapped buffers.
+        self.truncate_buffers(0);
+        let memory = match self.io_method {
+            IoRead => return,
+            IoDmaBuf => device::Mmap,
+            _ => self.memory()
+        };
+        match self.request_buffers(0, memory) {
+            Ok(_) => {}
+            Err(_) => {
+                // Errors ignored, the next VIDIOC_REQBUFS will tell.
             }
-            min = (*pix).bytesperline * (*pix).height;
-            if (*pix).sizeimage < min {
-                (*pix).sizeimage = min;
+        }
+    }
+
+    // Selects the requested video input, TV standard and frequency, and
+    // finds out what is selected where nothing was requested.
+    fn select_source(&mut self) -> IoResult<()> {
+        match self.input {
+            Some(index) => try!(self.s_input(index)),
+            None => {
+                match self.device.get_input() {
+                    Ok(index) => self.input = Some(index),
+                    Err(_) => {
+                        // Errors ignored, the device has a single input.
+                    }
+                }
             }
+        }
+        for input in self.enum_inputs().iter() {
+            info!("input {}: {}{}", input.index, input.name,
+                  if input.has_signal() { "" } else { " (no signal)" });
+        }
 
-            if (*pix).width != self.width {
-                self.width = (*pix).width;
+        match self.std {
+            Some(v4l2::V4L2_STD_UNKNOWN) => {
+                let id = try!(self.query_std());
+                try!(self.s_std(id));
             }
-            if (*pix).height != self.height {
-                self.height = (*pix).height;
+            Some(id) => try!(self.s_std(id)),
+            None => {
+                match self.device.get_std() {
+                    Ok(id) => self.std = Some(id),
+                    Err(_) => {
+                        // Errors ignored, digital inputs have no standard.
+                    }
+                }
             }
         }
 
-        /*
-        let mut frmsize: v4l2::v4l2_frmivalenum = Default::default();
+        match self.frequency {
+            Some(mhz) => try!(self.set_frequency(mhz)),
+            None => {}
+        }
+        self.select_dv_timings()
+    }
 
-        match v4l2_ioctl(self.fd, v4l2::VIDIOC_ENUM_FRAMEINTERVALS, unsafe { transmute(&mut frmsize) }) {
-            Ok(_) => {}
-            Err(e) => {
-                fail!("VIDIOC_ENUM_FRAMEINTERVALS failed! {}", e);
-            }
-        }*/
-        //println!("frmsize.he = {}", frmsize.he);
+    // The inputs of the device, from VIDIOC_ENUMINPUT.
+    pub fn enum_inputs(&mut self) -> Vec<Input> {
+        let mut inputs = vec!();
+        loop {
+            let input = match self.device.enum_input(inputs.len() as u32) {
+                Ok(input) => input,
+                Err(_) => return inputs
+            };
+            inputs.push(Input {
+                index: input.index,
+                name: c_string(input.name.as_slice()),
+                tuner: if input._type == v4l2::V4L2_INPUT_TYPE_TUNER { Some(input.tuner) } else { None },
+                std: input.std,
+                status: input.status,
+                capabilities: input.capabilities,
+            });
+        }
+    }
+
+    // The standards of the current input, from VIDIOC_ENUMSTD.
+    pub fn enum_standards(&mut self) -> Vec<Standard> {
+        let mut standards = vec!();
+        loop {
+            let standard = match self.device.enum_std(standards.len() as u32) {
+                Ok(standard) => standard,
+                Err(_) => return standards
+            };
+            standards.push(Standard {
+                id: standard.id,
+                name: c_string(standard.name.as_slice()),
+                frame_period: (standard.frameperiod.numerator, standard.frameperiod.denominator),
+                frame_lines: standard.framelines,
+            });
+        }
+    }
+
+    // Switches to input `index`, renegotiating the stream around it.
+    pub fn set_input(&mut self, index: u32) -> IoResult<()> {
+        try!(self.check_writable("set_input(): read-only monitor"));
+        self.reconfigure(|uvcview| {
+            uvcview.s_input(index).and_then(|_| uvcview.select_dv_timings())
+        })
+    }
 
-        // mmap initialization
+    fn s_input(&mut self, index: u32) -> IoResult<()> {
+        try!(self.check_writable("set_input(): read-only monitor"));
+        match self.device.set_input(index) {
+            Ok(_) => {
+                self.input = Some(index);
+                Ok(())
+            }
+            Err(errno) => {
+                Err(self.ioctl_error("set_input(): VIDIOC_S_INPUT failed", Some(format!("input {}", index)), errno))
+            }
+        }
+    }
 
-        let mut req: v4l2::v4l2_requestbuffers = Default::default();
+    // Switches to standard `id`, renegotiating the stream around it since
+    // the standard decides the frame size and rate.
+    pub fn set_std(&mut self, id: v4l2_std_id) -> IoResult<()> {
+        try!(self.check_writable("set_std(): read-only monitor"));
+        self.reconfigure(|uvcview| uvcview.s_std(id))
+    }
 
-        req.count = 4;
-        req._type = v4l2::V4L2_BUF_TYPE_VIDEO_CAPTURE;
-        req.memory = v4l2::V4L2_MEMORY_MMAP;
+    // Asks the driver which standard the current input receives and
+    // switches to it.
+    pub fn detect_std(&mut self) -> IoResult<v4l2_std_id> {
+        let id = try!(self.query_std());
+        try!(self.set_std(id));
+        Ok(id)
+    }
 
-        match v4l2_ioctl(self.fd, v4l2::VIDIOC_REQBUFS, unsafe { transmute(&mut req) }) {
+    fn s_std(&mut self, id: v4l2_std_id) -> IoResult<()> {
+        try!(self.check_writable("set_std(): read-only monitor"));
+        match self.device.set_std(id) {
             Ok(_) => {}
             Err(errno) => {
-                if errno == EINVAL {
-                    return Err(IoError {
-                        kind: io::OtherIoError,
-                        desc: "init(): ioctl() returns -1",
-                        detail: Some(format!("{} does not support memory mapping",
-                                     self.device_path.display()))
-                    });
-                } else {
-                    return Err(IoError {
-                        kind: io::OtherIoError,
-                        desc: "init(): ioctl() returns -1",
-                        detail: Some(error_string(errno as uint))
-                    });
-                }
+                return Err(self.ioctl_error("set_std(): VIDIOC_S_STD failed", Some(analog::std_to_str(id)), errno));
+            }
+        }
+        // The driver picks one standard out of a mask like V4L2_STD_PAL.
+        match self.device.get_std() {
+            Ok(value) => self.std = Some(value),
+            Err(_) => self.std = Some(id)
+        }
+        info!("standard {}", analog::std_to_str(self.std.unwrap()));
+        Ok(())
+    }
+
+    fn query_std(&mut self) -> IoResult<v4l2_std_id> {
+        match self.device.query_std() {
+            Ok(id) if id != v4l2::V4L2_STD_UNKNOWN => Ok(id),
+            Ok(_) => {
+                Err(IoError {
+                    kind: io::OtherIoError,
+                    desc: "query_std(): no standard detected",
+                    detail: Some(format!("{} receives no signal", self.device_path.display()))
+                })
+            }
+            Err(errno) => {
+                Err(IoError {
+                    kind: io::OtherIoError,
+                    desc: "query_std(): VIDIOC_QUERYSTD failed",
+                    detail: Some(error_string(errno as uint))
+                })
             }
         }
+    }
+
+    // Tunes the tuner of the current input to `mhz`.
+    pub fn set_frequency(&mut self, mhz: f64) -> IoResult<()> {
+        try!(self.check_writable("set_frequency(): read-only monitor"));
+        let current = self.input.unwrap_or(0);
+        let inputs = self.enum_inputs();
+        let tuner = match inputs.iter().find(|input| input.index == current) {
+            Some(input) => input.tuner,
+            None => None
+        };
+        let index = match tuner {
+            Some(index) => index,
+            None => {
+                return Err(IoError {
+                    kind: io::OtherIoError,
+                    desc: "set_frequency(): input has no tuner",
+                    detail: Some(format!("input {} of {}", current, self.device_path.display()))
+                });
+            }
+        };
 
-        if req.count < 2 {
+        let tuner = match self.device.get_tuner(index) {
+            Ok(tuner) => tuner,
+            Err(errno) => {
+                return Err(IoError {
+                    kind: io::OtherIoError,
+                    desc: "set_frequency(): VIDIOC_G_TUNER failed",
+                    detail: Some(error_string(errno as uint))
+                });
+            }
+        };
+        let units = analog::to_tuner_units(mhz, tuner.capability);
+        if units < tuner.rangelow || units > tuner.rangehigh {
             return Err(IoError {
                 kind: io::OtherIoError,
-                desc: "init() error",
-                detail: Some(format!("Insufficient buffer memory on {}", self.device_path.display()))
+                desc: "set_frequency(): frequency out of range",
+                detail: Some(format!("{:.3} MHz is outside {:.3} - {:.3} MHz", mhz,
+                                     analog::from_tuner_units(tuner.rangelow, tuner.capability),
+                                     analog::from_tuner_units(tuner.rangehigh, tuner.capability)))
             });
         }
 
-        let mut count = 0;
-        while count < req.count {
-            let mut buf: v4l2::v4l2_buffer = Default::default();
-            buf._type = v4l2::V4L2_BUF_TYPE_VIDEO_CAPTURE;
-            buf.memory = v4l2::V4L2_MEMORY_MMAP;
-            buf.index = count;
-
-            match v4l2_ioctl(self.fd, v4l2::VIDIOC_QUERYBUF, unsafe { transmute(&mut buf) }) {
-                Ok(_) => {}
-                Err(errno) => {
-                    return Err(IoError {
-                        kind: io::OtherIoError,
-                        desc: "init() error",
-                        detail: Some(error_string(errno as uint)),
-                    });
-                }
+        match self.device.set_frequency(index, tuner._type, units) {
+            Ok(_) => {
+                self.frequency = Some(analog::from_tuner_units(units, tuner.capability));
+                Ok(())
+            }
+            Err(errno) => {
+                Err(self.ioctl_error("set_frequency(): VIDIOC_S_FREQUENCY failed", None, errno))
             }
+        }
+    }
 
-            match MemoryMap::new(buf.length as uint,
-                                 &[MapReadable, MapWritable, MapFd(self.fd),
-                                   MapNonStandardFlags(MAP_SHARED)]) {
-                Ok(m) => {
-                    self.buffers.push(Buffer {
-                        memory_map: m,
-                        length: buf.length
-                    });
+    // Applies timings to the current input if it is a DV input, as
+    // dv_request asks, and watches it for source changes. The frame size
+    // follows the timings.
+    fn select_dv_timings(&mut self) -> IoResult<()> {
+        let current = self.input.unwrap_or(0);
+        if !self.enum_inputs().iter().any(|input| input.index == current && input.has_dv_timings()) {
+            self.dv_timings = None;
+            return Ok(());
+        }
+        match self.dv_timings_cap() {
+            Some(cap) => info!("dv timings {}x{} - {}x{}, {} - {} Hz pixel clock",
+                               cap.min_width, cap.min_height, cap.max_width, cap.max_height,
+                               cap.min_pixelclock, cap.max_pixelclock),
+            None => {}
+        }
+
+        match self.dv_request {
+            Some(dv::Detect) => {
+                let timings = try!(self.query_dv_timings());
+                try!(self.s_dv_timings(timings));
+            }
+            Some(dv::Index(index)) => {
+                match self.enum_dv_timings().move_iter().nth(index as uint) {
+                    Some(timings) => try!(self.s_dv_timings(timings)),
+                    None => {
+                        return Err(IoError {
+                            kind: io::OtherIoError,
+                            desc: "set_dv_timings(): no such timings",
+                            detail: Some(format!("input {} lists no timings {}", current, index))
+                        });
+                    }
                 }
-                Err(e) => {
-                    return Err(IoError {
-                        kind: io::OtherIoError,
-                        desc: "init() error",
-                        detail: Some(format!("MemoryMap::new() failed. {}", e))
-                    });
+            }
+            None => {
+                // Capture with whatever the driver has set.
+                match self.device.get_dv_timings() {
+                    Ok(raw) => {
+                        match Timings::from_v4l2(raw) {
+                            Some(timings) => self.use_dv_timings(timings),
+                            None => {}
+                        }
+                    }
+                    Err(_) => {
+                        // Errors ignored, VIDIOC_S_FMT will pick a size.
+                    }
                 }
             }
+        }
 
-            count += 1;
+        // Older kernels have no source change event; then only detecting
+        // by hand notices a new mode.
+        match self.subscribe_event(v4l2::V4L2_EVENT_SOURCE_CHANGE, current) {
+            Ok(()) => {}
+            Err(e) => info!("{}", e)
         }
+        Ok(())
+    }
 
-        return Ok(self);
+    // Timings the DV input can be set to, from VIDIOC_ENUM_DV_TIMINGS.
+    pub fn enum_dv_timings(&mut self) -> Vec<Timings> {
+        let mut list = vec!();
+        let mut index = 0;
+        loop {
+            let raw = match self.device.enum_dv_timings(index) {
+                Ok(raw) => raw,
+                Err(_) => return list
+            };
+            match Timings::from_v4l2(raw) {
+                Some(timings) => list.push(timings),
+                None => {}
+            }
+            index += 1;
+        }
     }
 
-    pub fn set_surface(&mut self, surface: sdl::video::Surface) {
-        self.surface = Some(surface);
+    pub fn dv_timings_cap(&mut self) -> Option<dv::Capability> {
+        match self.device.dv_timings_cap() {
+            Ok(cap) => dv::Capability::from_v4l2(&cap),
+            Err(_) => None
+        }
     }
 
-    pub fn start_capturing(&mut self) {
-        let mut i = 0;
-        for _ in self.buffers.iter() {
-            let mut buf: v4l2::v4l2_buffer = Default::default();
-            buf._type = v4l2::V4L2_BUF_TYPE_VIDEO_CAPTURE;
-            buf.memory = v4l2::V4L2_MEMORY_MMAP;
-            buf.index = i;
-
-            match v4l2::v4l2_ioctl(self.fd, v4l2::VIDIOC_QBUF, unsafe { transmute(&mut buf) }) {
-                Ok(_) => {}
-                Err(e) => {
-                    fail!("VIDIOC_QBUF failed. {}", e);
+    // Asks the receiver what the source sends and switches to it,
+    // renegotiating the stream if the mode changed.
+    pub fn detect_dv_timings(&mut self) -> IoResult<Timings> {
+        let timings = try!(self.query_dv_timings());
+        let changed = match self.dv_timings {
+            Some(ref current) => !current.same_mode(&timings),
+            None => true
+        };
+        if changed && self.monitor {
+            // The owner of the device applies the new timings; follow them.
+            try!(self.reconfigure(|uvcview| {
+                uvcview.use_dv_timings(timings);
+                Ok(())
+            }));
+        } else if changed {
+            try!(self.set_dv_timings(timings));
+        }
+        Ok(timings)
+    }
+
+    pub fn set_dv_timings(&mut self, timings: Timings) -> IoResult<()> {
+        try!(self.check_writable("set_dv_timings(): read-only monitor"));
+        self.reconfigure(|uvcview| uvcview.s_dv_timings(timings))
+    }
+
+    fn query_dv_timings(&mut self) -> IoResult<Timings> {
+        let desc = match self.device.query_dv_timings() {
+            Ok(raw) => {
+                match Timings::from_v4l2(raw) {
+                    Some(timings) => return Ok(timings),
+                    None => "query_dv_timings(): unsupported timings type"
                 }
             }
+            Err(ENOLINK) => "query_dv_timings(): no signal",
+            Err(ENOLCK) => "query_dv_timings(): signal unstable",
+            Err(ERANGE) => "query_dv_timings(): timings out of range",
+            Err(_) => "query_dv_timings(): VIDIOC_QUERY_DV_TIMINGS failed"
+        };
+        Err(IoError {
+            kind: io::OtherIoError,
+            desc: desc,
+            detail: Some(format!("input {} of {}", self.input.unwrap_or(0), self.device_path.display()))
+        })
+    }
 
-            i = i + 1;
+    fn s_dv_timings(&mut self, timings: Timings) -> IoResult<()> {
+        try!(self.check_writable("set_dv_timings(): read-only monitor"));
+        match self.dv_timings_cap() {
+            Some(ref cap) if !cap.supports(&timings) => {
+                return Err(IoError {
+                    kind: io::OtherIoError,
+                    desc: "set_dv_timings(): timings not supported",
+                    detail: Some(format!("{} is beyond the receiver", timings))
+                });
+            }
+            _ => {}
         }
-
-        let mut buf_type: v4l2::v4l2_buf_type = v4l2::V4L2_BUF_TYPE_VIDEO_CAPTURE;
-
-        match v4l2::v4l2_ioctl(self.fd, v4l2::VIDIOC_STREAMON, unsafe { transmute(&mut buf_type) }) {
-            Ok(_) => {}
-            Err(e) => {
-                fail!("VIDIOC_STERAMON failed. {}", error_string(e as uint));
+        match self.device.set_dv_timings(&timings.raw) {
+            Ok(_) => {
+                info!("dv timings {}", timings);
+                self.use_dv_timings(timings);
+                Ok(())
+            }
+            Err(errno) => {
+                Err(self.ioctl_error("set_dv_timings(): VIDIOC_S_DV_TIMINGS failed", Some(format!("{}", timings)), errno))
             }
         }
     }
 
-    pub fn stop_capturing(&mut self) {
-        let mut buf_type: v4l2::v4l2_buf_type = v4l2::V4L2_BUF_TYPE_VIDEO_CAPTURE;
+    // Receivers cannot scale, so the format takes the size of the timings.
+    fn use_dv_timings(&mut self, timings: Timings) {
+        self.width = timings.width;
+        self.height = timings.height;
+        self.dv_timings = Some(timings);
+    }
 
-        match v4l2::v4l2_ioctl(self.fd, v4l2::VIDIOC_STREAMOFF, unsafe { transmute(&mut buf_type) }) {
-            Ok(_) => {}
-            Err(e) => {
-                fail!("VIDIOC_STREAMOFF failed. {}", error_string(e as uint));
+    fn subscribe_event(&mut self, event: u32, id: u32) -> IoResult<()> {
+        match self.device.subscribe_event(event, id) {
+            Ok(_) => Ok(()),
+            Err(errno) => {
+                Err(IoError {
+                    kind: io::OtherIoError,
+                    desc: "subscribe_event(): VIDIOC_SUBSCRIBE_EVENT failed",
+                    detail: Some(format!("event {}: {}", event, error_string(errno as uint)))
+                })
             }
         }
     }
 
-    pub fn read_frame(&mut self) -> bool {
-        let mut buffer: v4l2::v4l2_buffer = Default::default();
-
-        buffer._type = v4l2::V4L2_BUF_TYPE_VIDEO_CAPTURE;
-        buffer.memory = v4l2::V4L2_MEMORY_MMAP;
-
-        match v4l2::v4l2_ioctl(self.fd, v4l2::VIDIOC_DQBUF, unsafe { transmute(&mut buffer) }) {
-            Ok(_) => {}
-            Err(EAGAIN) => {
-                return false;
+    // Subscribes to control changes of every control, end of stream and
+    // frame sync. Drivers support what suits the device; the rest fail and
+    // are left out. Source changes are subscribed per DV input.
+    fn subscribe_events(&mut self) {
+        let ids: Vec<u32> = self.controls.iter().map(|control| control.id).collect();
+        for &id in ids.iter() {
+            match self.subscribe_event(v4l2::V4L2_EVENT_CTRL, id) {
+                Ok(()) => {}
+                Err(e) => info!("{}", e)
             }
-            Err(e) => {
-                fail!("VIDIOC_DQBUF failed. {}", error_string(e as uint));
+        }
+        for &event in [v4l2::V4L2_EVENT_EOS, v4l2::V4L2_EVENT_FRAME_SYNC].iter() {
+            match self.subscribe_event(event, 0) {
+                Ok(()) => {}
+                Err(e) => info!("{}", e)
             }
         }
+    }
 
-        if buffer.index >= self.buffers.len() as u32 {
-            fail!();
+    // Dequeues the pending events, once poll() flags POLLPRI, and passes
+    // each to `handler`. Control events update `controls` and source
+    // changes switch to the new DV timings before the handler runs.
+    pub fn dequeue_events(&mut self, handler: |&mut UvcView, &Event|) -> IoResult<()> {
+        let mut received = vec!();
+        loop {
+            let event = match self.device.dqevent() {
+                Ok(event) => event,
+                Err(ENOENT) => break,
+                Err(errno) => {
+                    return Err(IoError {
+                        kind: io::OtherIoError,
+                        desc: "dequeue_events(): VIDIOC_DQEVENT failed",
+                        detail: Some(error_string(errno as uint))
+                    });
+                }
+            };
+            match events::from_v4l2(&event) {
+                Some(event) => received.push(event),
+                None => {}
+            }
         }
 
-        self.process_image(buffer.index);
-
-        match v4l2::v4l2_ioctl(self.fd, v4l2::VIDIOC_QBUF, unsafe { transmute(&mut buffer) }) {
-            Ok(_) => {}
-            Err(e) => {
-                fail!("VIDIOC_QBUF failed. {}", error_string(e as uint));
+        let mut source_changed = false;
+        for event in received.iter() {
+            match *event {
+                events::Control(ref change) => {
+                    match self.controls.mut_iter().find(|control| control.id == change.id) {
+                        Some(control) => control.update(change),
+                        None => {}
+                    }
+                    self.control_changed = Some((change.id, time::precise_time_ns()));
+                }
+                events::SourceChange(_, changes) => {
+                    source_changed |= (changes & v4l2::V4L2_EVENT_SRC_CH_RESOLUTION) != 0;
+                }
+                _ => {}
             }
         }
-        return true;
-    }
-
-    fn yuv422_to_rgb(dest: *mut u8, src: *mut u8) {
-        unsafe {
-            let y0 = *src as f64;
-            let cb = *src.offset(1) as f64;
-            let y1 = *src.offset(2) as f64;
-            let cr = *src.offset(3) as f64;
-
-            *dest.offset(0) = (y0 + 1.77200 * (cb - 128.0)) as u8;
-            *dest.offset(1) = (y0 - 0.34414 * (cb - 128.0) - 0.71414 * (cr - 128.0)) as u8;
-            *dest.offset(2) = (y0 + 1.40200 * (cr - 128.0)) as u8;
-
-            *dest.offset(3) = (y1 + 1.77200 * (cb - 128.0)) as u8;
-            *dest.offset(4) = (y1 - 0.34414 * (cb - 128.0) - 0.71414 * (cr - 128.0)) as u8;
-            *dest.offset(5) = (y1 + 1.40200 * (cr - 128.0)) as u8;
+        let result = if source_changed {
+            self.detect_dv_timings().map(|timings| info!("source changed to {}", timings))
+        } else {
+            Ok(())
+        };
 
+        for event in received.iter() {
+            handler(self, event);
         }
+        result
     }
 
-    fn process_image(&mut self, buffer_index: u32) {
-        println!("buffer_index = {}", buffer_index);
-        match self.surface {
-            Some(ref surface) => {
-                surface.with_lock(|pixels| {
-                    let buffer = self.buffers.get(buffer_index as uint);
-                    let dest = pixels.as_mut_ptr();
-                    let src = buffer.memory_map.data;
-                    unsafe {
-                        let mut y: int = 0;
-                        while y < self.height as int {
-                            let mut x = 0;
-                            while x < self.width as int {
-                                UvcView::yuv422_to_rgb(
-                                    dest.offset((y * self.width as int + x) * 3),
-                                    src.offset((y * self.width as int + x) * 2));
-                                x += 2;
-                            }
-                            y += 1;
-                        }
-                    }
-                });
-                surface.flip();
+    // Sets the control `id` to `value`, as far as the driver allows.
+    pub fn set_control(&mut self, id: u32, value: i32) -> IoResult<()> {
+        try!(self.check_writable("set_control(): read-only monitor"));
+        let value = match self.device.set_ctrl(id, value) {
+            Ok(value) => value,
+            Err(errno) => {
+                return Err(self.ioctl_error("set_control(): VIDIOC_S_CTRL failed",
+                                            Some(format!("control 0x{:x}", id)), errno));
+            }
+        };
+        match self.controls.mut_iter().find(|control| control.id == id) {
+            Some(cached) => cached.value = value as i64,
+            None => {}
+        }
+        Ok(())
+    }
+
+    // The value of control `selector` of extension unit `unit`, and the
+    // flags GET_INFO reports for it.
+    pub fn xu_get(&mut self, unit: u8, selector: u8) -> IoResult<(xu::Info, Vec<u8>)> {
+        let info = match xu::get_info(&mut self.device, unit, selector) {
+            Ok(info) => info,
+            Err(errno) => {
+                return Err(self.ioctl_error("xu_get(): GET_INFO failed",
+                                            Some(xu::address_to_str(unit, selector)), errno));
+            }
+        };
+        if !info.can_get() {
+            return Err(IoError {
+                kind: io::OtherIoError,
+                desc: "xu_get(): control cannot be read",
+                detail: Some(format!("{} ({})", xu::address_to_str(unit, selector), info))
+            });
+        }
+        let len = match xu::get_len(&mut self.device, unit, selector) {
+            Ok(len) => len,
+            Err(errno) => {
+                return Err(self.ioctl_error("xu_get(): GET_LEN failed",
+                                            Some(xu::address_to_str(unit, selector)), errno));
+            }
+        };
+        match xu::get_cur(&mut self.device, unit, selector, len) {
+            Ok(value) => Ok((info, value)),
+            Err(errno) => {
+                Err(self.ioctl_error("xu_get(): GET_CUR failed",
+                                     Some(xu::address_to_str(unit, selector)), errno))
+            }
+        }
+    }
+
+    // Sets control `selector` of extension unit `unit` to `value`, which
+    // must be as long as GET_LEN says.
+    pub fn xu_set(&mut self, unit: u8, selector: u8, value: &[u8]) -> IoResult<()> {
+        try!(self.check_writable("xu_set(): read-only monitor"));
+        let info = match xu::get_info(&mut self.device, unit, selector) {
+            Ok(info) => info,
+            Err(errno) => {
+                return Err(self.ioctl_error("xu_set(): GET_INFO failed",
+                                            Some(xu::address_to_str(unit, selector)), errno));
+            }
+        };
+        if !info.can_set() {
+            return Err(IoError {
+                kind: io::OtherIoError,
+                desc: "xu_set(): control cannot be set",
+                detail: Some(format!("{} ({})", xu::address_to_str(unit, selector), info))
+            });
+        }
+        match xu::get_len(&mut self.device, unit, selector) {
+            Ok(len) if len as uint == value.len() => {}
+            Ok(len) => {
+                return Err(IoError {
+                    kind: io::InvalidInput,
+                    desc: "xu_set(): wrong value length",
+                    detail: Some(format!("{} takes {} bytes, not {}", xu::address_to_str(unit, selector),
+                                         len, value.len()))
+                });
+            }
+            Err(errno) => {
+                return Err(self.ioctl_error("xu_set(): GET_LEN failed",
+                                            Some(xu::address_to_str(unit, selector)), errno));
+            }
+        }
+        match xu::set_cur(&mut self.device, unit, selector, value) {
+            Ok(()) => Ok(()),
+            Err(errno) => {
+                Err(self.ioctl_error("xu_set(): SET_CUR failed",
+                                     Some(xu::address_to_str(unit, selector)), errno))
+            }
+        }
+    }
+
+    // Claims `priority` for this file handle. A background handle never
+    // keeps others from changing the device; a record handle keeps every
+    // other handle from changing it.
+    pub fn set_priority(&mut self, priority: u32) -> IoResult<()> {
+        match self.device.set_priority(priority) {
+            Ok(_) => {}
+            Err(errno) => {
+                return Err(self.ioctl_error("set_priority(): VIDIOC_S_PRIORITY failed",
+                                            Some(priority_to_str(priority).to_owned()), errno));
+            }
+        }
+        self.priority = Some(priority);
+        // G_PRIORITY tells the highest priority of all handles.
+        match self.device.get_priority() {
+            Ok(highest) if highest > priority => {
+                info!("priority {}, another application holds {}", priority_to_str(priority),
+                      priority_to_str(highest));
+            }
+            _ => info!("priority {}", priority_to_str(priority))
+        }
+        Ok(())
+    }
+
+    fn check_writable(&self, desc: &'static str) -> IoResult<()> {
+        if !self.monitor {
+            return Ok(());
+        }
+        Err(IoError {
+            kind: io::PermissionDenied,
+            desc: desc,
+            detail: Some(format!("{} is opened as a monitor", self.device_path.display()))
+        })
+    }
+
+    // The error for an ioctl that failed with `errno`, on `what` if given.
+    fn ioctl_error(&mut self, desc: &'static str, what: Option<~str>, errno: c_int) -> IoError {
+        let reason = self.errno_reason(errno);
+        IoError {
+            kind: if errno == EBUSY { io::ResourceUnavailable } else { io::OtherIoError },
+            desc: desc,
+            detail: Some(match what {
+                Some(what) => format!("{}: {}", what, reason),
+                None => reason
+            })
+        }
+    }
+
+    // EBUSY means another file handle owns the device, which the plain
+    // "Device or resource busy" does not tell. A monitor can still watch
+    // a device another application holds a higher priority on.
+    fn errno_reason(&mut self, errno: c_int) -> ~str {
+        if errno != EBUSY {
+            return error_string(errno as uint);
+        }
+        let ours = self.priority.unwrap_or(v4l2::V4L2_PRIORITY_DEFAULT);
+        match self.device.get_priority() {
+            Ok(highest) if highest > ours => {
+                format!("{} is busy: another application holds {} priority (try --monitor)",
+                        self.device_path.display(), priority_to_str(highest))
+            }
+            _ => format!("{} is busy: another application is streaming from it", self.device_path.display())
+        }
+    }
+
+    // The device's controls, from VIDIOC_QUERYCTRL and VIDIOC_G_CTRL.
+    pub fn enum_controls(&mut self) -> Vec<Control> {
+        let mut controls = vec!();
+        let mut id = 0;
+        loop {
+            let query = match self.device.query_ctrl(id | v4l2::V4L2_CTRL_FLAG_NEXT_CTRL) {
+                Ok(query) => query,
+                Err(_) => return controls
+            };
+            id = query.id;
+            if query._type == v4l2::V4L2_CTRL_TYPE_CTRL_CLASS ||
+                (query.flags & v4l2::V4L2_CTRL_FLAG_DISABLED) != 0 {
+                continue;
+            }
+            let value = match self.device.get_ctrl(query.id) {
+                Ok(value) => value,
+                Err(_) => query.default_value
+            };
+            controls.push(Control {
+                id: query.id,
+                name: c_string(query.name.as_slice()),
+                kind: query._type,
+                minimum: query.minimum,
+                maximum: query.maximum,
+                step: query.step,
+                default_value: query.default_value,
+                flags: query.flags,
+                value: value as i64,
+            });
+        }
+    }
+
+    // Crops to `rect` in sensor pixels, renegotiating the stream around it
+    // since the crop may decide the frame size, and returns the rectangle
+    // the driver settled on. Only the crop target is set; the compose
+    // rectangle of capture drivers that have one stays at its default.
+    pub fn set_crop(&mut self, rect: Rect) -> IoResult<Rect> {
+        try!(self.check_writable("set_crop(): read-only monitor"));
+        try!(self.reconfigure(|uvcview| {
+            let actual = try!(uvcview.apply_crop(rect));
+            // Kept over later renegotiations.
+            uvcview.crop = Some(actual);
+            Ok(())
+        }));
+        Ok(self.crop_rect.unwrap_or(rect))
+    }
+
+    // Sets the crop rectangle with VIDIOC_S_SELECTION, or VIDIOC_S_CROP on
+    // drivers without the selection API, and returns the rectangle the
+    // driver settled on.
+    fn apply_crop(&mut self, rect: Rect) -> IoResult<Rect> {
+        try!(self.check_writable("set_crop(): read-only monitor"));
+        let buf_type = self.buf_type();
+        let result = match self.device.set_selection(buf_type, v4l2::V4L2_SEL_TGT_CROP, to_v4l2_rect(rect)) {
+            Ok(actual) => Ok(from_v4l2_rect(&actual)),
+            Err(ENOTTY) | Err(EINVAL) => self.s_crop(rect),
+            Err(errno) => Err(errno)
+        };
+        match result {
+            Ok(actual) => {
+                self.crop_rect = Some(actual);
+                Ok(actual)
+            }
+            Err(errno) => {
+                let what = format!("{}x{}+{}+{}", rect.width, rect.height, rect.x, rect.y);
+                Err(self.ioctl_error("set_crop(): cropping failed", Some(what), errno))
+            }
+        }
+    }
+
+    fn s_crop(&mut self, rect: Rect) -> Result<Rect, c_int> {
+        let buf_type = self.buf_type();
+        try!(self.device.set_crop(buf_type, to_v4l2_rect(rect)));
+
+        // VIDIOC_S_CROP is write-only, ask what the driver made of it.
+        match self.device.get_crop(buf_type) {
+            Ok(actual) => Ok(from_v4l2_rect(&actual)),
+            Err(_) => Ok(rect)
+        }
+    }
+
+    // Goes back to the driver's default crop rectangle.
+    pub fn reset_crop(&mut self) -> IoResult<()> {
+        if self.crop_default.is_none() {
+            return Ok(());
+        }
+        try!(self.check_writable("set_crop(): read-only monitor"));
+        self.reconfigure(|uvcview| {
+            uvcview.crop = None;
+            Ok(())
+        })
+    }
+
+    pub fn begin_selection(&mut self, x: uint, y: uint) {
+        self.selection = Some(((x, y), (x, y)));
+    }
+
+    pub fn update_selection(&mut self, x: uint, y: uint) {
+        match self.selection {
+            Some((start, _)) => self.selection = Some((start, (x, y))),
+            None => {}
+        }
+    }
+
+    // Crops to the part of the image under the finished rubber band. A
+    // click without a drag leaves the crop alone.
+    pub fn end_selection(&mut self) -> IoResult<()> {
+        let ((x0, y0), (x1, y1)) = match self.selection.take() {
+            Some(selection) => selection,
+            None => return Ok(())
+        };
+        let (window_width, window_height) = self.window_size();
+        let dest = self.viewport(window_width, window_height);
+        if dest.width == 0 || dest.height == 0 {
+            return Ok(());
+        }
+
+        // Rubber band edges in frame pixels.
+        let src = self.source_region();
+        let to_frame = |x: uint, y: uint| -> (f64, f64) {
+            let x = clamp(x as f64, dest.x as f64, (dest.x + dest.width) as f64);
+            let y = clamp(y as f64, dest.y as f64, (dest.y + dest.height) as f64);
+            (src.x + (x - dest.x as f64) * src.width / dest.width as f64,
+             src.y + (y - dest.y as f64) * src.height / dest.height as f64)
+        };
+        let (fx0, fy0) = to_frame(min(x0, x1), min(y0, y1));
+        let (fx1, fy1) = to_frame(max(x0, x1), max(y0, y1));
+        if fx1 - fx0 < 1.0 || fy1 - fy0 < 1.0 {
+            return Ok(());
+        }
+
+        // The frame shows the current crop rectangle, possibly scaled.
+        let current = self.crop_rect.unwrap_or(Rect {
+            x: 0,
+            y: 0,
+            width: self.width as uint,
+            height: self.height as uint,
+        });
+        let scale_x = current.width as f64 / self.width as f64;
+        let scale_y = current.height as f64 / self.height as f64;
+        let rect = Rect {
+            x: current.x + (fx0 * scale_x).round() as uint,
+            y: current.y + (fy0 * scale_y).round() as uint,
+            width: ((fx1 - fx0) * scale_x).round() as uint,
+            height: ((fy1 - fy0) * scale_y).round() as uint,
+        };
+        self.set_crop(rect).map(|_| ())
+    }
+
+    fn buf_type(&self) -> BufType {
+        if self.mplane {
+            device::VideoCaptureMplane
+        } else {
+            device::VideoCapture
+        }
+    }
+
+    // A format for a `width` x `height` image in the current pixel format,
+    // using the multi-planar layout on MPLANE devices.
+    fn pix_format(&self, width: u32, height: u32, field: Field) -> PixFormat {
+        PixFormat::new(self.buf_type(), width, height, self.pixelformat, field)
+    }
+
+    fn memory(&self) -> Memory {
+        match self.io_method {
+            IoRead | IoMmap => device::Mmap,
+            IoUserPtr => device::UserPtr,
+            IoDmaBuf => device::DmaBuf,
+        }
+    }
+
+    fn request_buffers(&mut self, count: u32, memory: Memory) -> IoResult<u32> {
+        let buf_type = self.buf_type();
+        let granted = match self.device.req_bufs(buf_type, memory, count) {
+            Ok(granted) => granted,
+            Err(errno) => {
+                if errno == EINVAL {
+                    let what = match memory {
+                        device::UserPtr => "user pointer i/o",
+                        device::DmaBuf => "dma-buf i/o",
+                        device::Mmap => "memory mapping"
+                    };
+                    return Err(IoError {
+                        kind: io::OtherIoError,
+                        desc: "init(): ioctl() returns -1",
+                        detail: Some(format!("{} does not support {}",
+                                     self.device_path.display(), what))
+                    });
+                } else {
+                    return Err(self.ioctl_error("init(): ioctl() returns -1", None, errno));
+                }
+            }
+        };
+
+        if count > 0 && granted < 2 {
+            return Err(IoError {
+                kind: io::OtherIoError,
+                desc: "init() error",
+                detail: Some(format!("Insufficient buffer memory on {}", self.device_path.display()))
+            });
+        }
+        if count > 0 {
+            info!("requested {} buffers, driver granted {}", count, granted);
+        }
+
+        return Ok(granted);
+    }
+
+    fn init_read(&mut self) -> IoResult<()> {
+        match MemoryMap::new(self.sizeimage as uint, &[MapReadable, MapWritable]) {
+            Ok(m) => {
+                self.buffers.push(Buffer::new(m, self.sizeimage));
+            }
+            Err(e) => {
+                return Err(IoError {
+                    kind: io::OtherIoError,
+                    desc: "init() error",
+                    detail: Some(format!("MemoryMap::new() failed. {}", e))
+                });
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn init_mmap(&mut self) -> IoResult<()> {
+        let count = try!(self.request_buffers(self.buffer_count, device::Mmap));
+
+        for index in range(0, count) {
+            try!(self.map_buffer(index));
+        }
+
+        return Ok(());
+    }
+
+    fn map_buffer(&mut self, index: u32) -> IoResult<()> {
+        let (buf_type, num_planes) = (self.buf_type(), self.plane_fmt.len());
+        let buf = match self.device.query_buf(buf_type, device::Mmap, index, num_planes) {
+            Ok(buf) => buf,
+            Err(errno) => {
+                return Err(IoError {
+                    kind: io::OtherIoError,
+                    desc: "init() error",
+                    detail: Some(error_string(errno as uint)),
+                });
+            }
+        };
+
+        let mut buffer = Buffer { planes: vec!() };
+        for plane in buf.planes.iter() {
+            let length = plane.length;
+            let offset = match plane.location {
+                device::MmapOffset(offset) => offset,
+                _ => 0
+            };
+            match self.device.mmap(length as uint, offset) {
+                Ok(m) => {
+                    buffer.planes.push(BufferPlane {
+                        memory_map: m,
+                        length: length,
+                        dmabuf_fd: -1,
+                    });
+                }
+                Err(e) => {
+                    return Err(IoError {
+                        kind: io::OtherIoError,
+                        desc: "init() error",
+                        detail: Some(format!("MemoryMap::new() failed. {}", e))
+                    });
+                }
+            }
+        }
+        self.buffers.push(buffer);
+
+        return Ok(());
+    }
+
+    fn init_userptr(&mut self) -> IoResult<()> {
+        let count = try!(self.request_buffers(self.buffer_count, device::UserPtr));
+
+        for _ in range(0, count) {
+            let planes = self.plane_fmt.clone();
+            try!(self.alloc_userptr_buffer(planes.as_slice()));
+        }
+
+        return Ok(());
+    }
+
+    fn alloc_userptr_buffer(&mut self, planes: &[PlaneFormat]) -> IoResult<()> {
+        // Anonymous mappings are page aligned, which is what most drivers
+        // require of user pointers.
+        let page_size = os::page_size();
+        let mut buffer = Buffer { planes: vec!() };
+
+        for plane in planes.iter() {
+            let map_length = (plane.sizeimage as uint + page_size - 1) & !(page_size - 1);
+            match MemoryMap::new(map_length, &[MapReadable, MapWritable]) {
+                Ok(m) => {
+                    buffer.planes.push(BufferPlane {
+                        memory_map: m,
+                        length: plane.sizeimage,
+                        dmabuf_fd: -1,
+                    });
+                }
+                Err(e) => {
+                    return Err(IoError {
+                        kind: io::OtherIoError,
+                        desc: "init() error",
+                        detail: Some(format!("MemoryMap::new() failed. {}", e))
+                    });
+                }
+            }
+        }
+        self.buffers.push(buffer);
+
+        return Ok(());
+    }
+
+    // Allocates buffers from the driver, exports them as dma-bufs and queues
+    // those back as DMABUF buffers, the same path buffers imported from
+    // another component would take.
+    fn init_dmabuf(&mut self) -> IoResult<()> {
+        try!(self.init_mmap());
+        try!(self.export_buffers());
+
+        // Map through the dma-bufs so the driver mappings can go away.
+        for buffer in self.buffers.mut_iter() {
+            for plane in buffer.planes.mut_iter() {
+                match self.device.mmap_dmabuf(plane.dmabuf_fd, plane.length as uint) {
+                    Ok(m) => {
+                        plane.memory_map = m;
+                    }
+                    Err(e) => {
+                        return Err(IoError {
+                            kind: io::OtherIoError,
+                            desc: "init() error",
+                            detail: Some(format!("MemoryMap::new() failed. {}", e))
+                        });
+                    }
+                }
+            }
+        }
+
+        // The exported dma-bufs keep the memory alive once the MMAP
+        // buffers are released.
+        let count = self.buffers.len() as u32;
+        try!(self.request_buffers(0, device::Mmap));
+        let granted = try!(self.request_buffers(count, device::DmaBuf));
+        self.truncate_buffers(granted as uint);
+
+        return Ok(());
+    }
+
+    // Drops the buffers from `len` on, closing their dma-bufs.
+    fn truncate_buffers(&mut self, len: uint) {
+        for buffer in self.buffers.iter().skip(len) {
+            for plane in buffer.planes.iter() {
+                if plane.dmabuf_fd != -1 {
+                    self.device.close_dmabuf(plane.dmabuf_fd);
+                }
+            }
+        }
+        self.buffers.truncate(len);
+    }
+
+    // Exports every mmap buffer as a dma-buf fd for zero-copy handoff to
+    // other components.
+    pub fn export_buffers(&mut self) -> IoResult<()> {
+        static O_CLOEXEC: c_int = 0x80000;
+
+        let buf_type = self.buf_type();
+        for (index, buffer) in self.buffers.mut_iter().enumerate() {
+            for (plane_index, plane) in buffer.planes.mut_iter().enumerate() {
+                let flags = (O_RDWR | O_CLOEXEC) as u32;
+                match self.device.expbuf(buf_type, index as u32, plane_index as u32, flags) {
+                    Ok(fd) => {
+                        plane.dmabuf_fd = fd;
+                    }
+                    Err(errno) => {
+                        return Err(IoError {
+                            kind: io::OtherIoError,
+                            desc: "VIDIOC_EXPBUF failed",
+                            detail: Some(error_string(errno as uint))
+                        });
+                    }
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn init_test_pattern<'a>(&'a mut self) -> IoResult<&'a mut UvcView> {
+        if !pixfmt::is_supported(self.pixelformat.code()) {
+            return Err(IoError {
+                kind: io::OtherIoError,
+                desc: "init(): unsupported pixel format",
+                detail: Some(format!("test pattern cannot generate {}", self.pixelformat))
+            });
+        }
+
+        self.card = "test pattern".to_owned();
+        let fps = self.test_pattern.get_ref().fps;
+        self.frame_interval = Some((1000, (fps * 1000.0) as u32));
+
+        // Keep 4:2:x chroma subsampling aligned.
+        self.width &= !1;
+        self.height &= !1;
+        self.bytesperline = pixfmt::min_bytesperline(self.pixelformat.code(), self.width as uint) as u32;
+        self.sizeimage = pixfmt::image_size(self.pixelformat.code(), self.bytesperline as uint,
+                                            self.height as uint) as u32;
+        self.plane_fmt = vec!(PlaneFormat {
+            bytesperline: self.bytesperline,
+            sizeimage: self.sizeimage,
+        });
+
+        match MemoryMap::new(self.sizeimage as uint, &[MapReadable, MapWritable]) {
+            Ok(m) => {
+                self.buffers.push(Buffer::new(m, self.sizeimage));
+            }
+            Err(e) => {
+                return Err(IoError {
+                    kind: io::OtherIoError,
+                    desc: "init() error",
+                    detail: Some(format!("MemoryMap::new() failed. {}", e))
+                });
+            }
+        }
+
+        return Ok(self);
+    }
+
+    pub fn set_surface(&mut self, surface: sdl::video::Surface) {
+        self.surface = Some(surface);
+    }
+
+    pub fn start_capturing(&mut self) {
+        self.stats.reset();
+        match self.latency {
+            Some(ref mut latency) => latency.reset(),
+            None => {}
+        }
+        if self.test_pattern.is_some() || self.io_method == IoRead {
+            return;
+        }
+
+        for i in range(0, self.buffers.len()) {
+            match self.queue_buffer(i as u32) {
+                Ok(_) => {}
+                Err(e) => {
+                    fail!("VIDIOC_QBUF failed. {}", e);
+                }
+            }
+        }
+
+        // The metadata of a frame must be queued for before the frame
+        // arrives.
+        match self.meta {
+            Some(ref mut meta) => {
+                match meta.start() {
+                    Ok(()) => {}
+                    Err(e) => fail!("{}", e)
+                }
+            }
+            None => {}
+        }
+
+        let buf_type = self.buf_type();
+        match self.device.stream_on(buf_type) {
+            Ok(_) => {}
+            Err(e) => {
+                fail!("VIDIOC_STERAMON failed. {}", self.errno_reason(e));
+            }
+        }
+        self.streaming = true;
+    }
+
+    // Adds `count` buffers sized for a `width` x `height` image in the current
+    // pixel format, keeping the existing ones, so the stream can move to a
+    // larger size without tearing everything down. New buffers are checked
+    // with VIDIOC_PREPARE_BUF and queued at once if streaming. Returns the
+    // number of buffers the driver created.
+    pub fn create_buffers(&mut self, count: u32, width: u32, height: u32) -> IoResult<u32> {
+        match self.io_method {
+            IoMmap | IoUserPtr => {}
+            _ => {
+                return Err(IoError {
+                    kind: io::OtherIoError,
+                    desc: "create_buffers(): unsupported i/o method",
+                    detail: Some(format!("VIDIOC_CREATE_BUFS is not available with {} i/o",
+                                         self.io_method))
+                });
+            }
+        }
+
+        let format = self.pix_format(width, height, self.field);
+        let format = match self.device.try_format(&format) {
+            Ok(format) => checked_format(format),
+            Err(errno) => {
+                return Err(IoError {
+                    kind: io::OtherIoError,
+                    desc: "VIDIOC_TRY_FMT failed",
+                    detail: Some(error_string(errno as uint))
+                });
+            }
+        };
+        let sizeimage = format.planes.iter().fold(0, |sum, plane| sum + plane.sizeimage);
+
+        let memory = self.memory();
+        let (first, created) = match self.device.create_bufs(memory, count, &format) {
+            Ok(result) => result,
+            Err(errno) => {
+                return Err(IoError {
+                    kind: io::OtherIoError,
+                    desc: "VIDIOC_CREATE_BUFS failed",
+                    detail: Some(error_string(errno as uint))
+                });
+            }
+        };
+        if first != self.buffers.len() as u32 {
+            return Err(IoError {
+                kind: io::OtherIoError,
+                desc: "VIDIOC_CREATE_BUFS failed",
+                detail: Some(format!("driver created buffers at index {}, expected {}",
+                                     first, self.buffers.len()))
+            });
+        }
+        info!("requested {} buffers of {} bytes, driver created {}", count, sizeimage, created);
+
+        for index in range(first, first + created) {
+            match self.io_method {
+                IoMmap => try!(self.map_buffer(index)),
+                _ => try!(self.alloc_userptr_buffer(format.planes.as_slice())),
+            }
+
+            match self.buffer_ioctl(v4l2::VIDIOC_PREPARE_BUF, index) {
+                Ok(_) => {}
+                Err(errno) => {
+                    return Err(IoError {
+                        kind: io::OtherIoError,
+                        desc: "VIDIOC_PREPARE_BUF failed",
+                        detail: Some(error_string(errno as uint))
+                    });
+                }
+            }
+
+            if self.streaming {
+                match self.queue_buffer(index) {
+                    Ok(_) => {}
+                    Err(errno) => {
+                        return Err(IoError {
+                            kind: io::OtherIoError,
+                            desc: "VIDIOC_QBUF failed",
+                            detail: Some(error_string(errno as uint))
+                        });
+                    }
+                }
+            }
+        }
+
+        return Ok(created);
+    }
+
+    fn queue_buffer(&mut self, index: u32) -> Result<(), c_int> {
+        self.buffer_ioctl(v4l2::VIDIOC_QBUF, index)
+    }
+
+    // Issues VIDIOC_QBUF or VIDIOC_PREPARE_BUF for buffer `index`.
+    fn buffer_ioctl(&mut self, req: u32, index: u32) -> Result<(), c_int> {
+        let mut buf = device::Buffer::new(self.buf_type(), self.memory(), index, self.plane_fmt.len());
+
+        let buffer = self.buffers.get(index as uint);
+        for (info, plane) in buf.planes.mut_iter().zip(buffer.planes.iter()) {
+            match self.io_method {
+                IoRead | IoMmap => continue,
+                IoUserPtr => info.location = device::UserPointer(plane.memory_map.data as uint),
+                IoDmaBuf => info.location = device::DmaBufFd(plane.dmabuf_fd),
+            }
+            info.length = plane.length;
+        }
+
+        match req {
+            v4l2::VIDIOC_PREPARE_BUF => self.device.prepare_buf(&buf),
+            _ => self.device.qbuf(&buf)
+        }
+    }
+
+    pub fn stop_capturing(&mut self) {
+        if self.test_pattern.is_some() || self.io_method == IoRead {
+            return;
+        }
+
+        let buf_type = self.buf_type();
+        match self.device.stream_off(buf_type) {
+            Ok(_) => {}
+            Err(e) => {
+                fail!("VIDIOC_STREAMOFF failed. {}", error_string(e as uint));
+            }
+        }
+        self.streaming = false;
+        match self.meta {
+            Some(ref mut meta) => {
+                match meta.stop() {
+                    Ok(()) => {}
+                    Err(e) => fail!("{}", e)
+                }
+            }
+            None => {}
+        }
+    }
+
+    pub fn read_frame(&mut self) -> bool {
+        if self.test_pattern.is_some() {
+            let sequence = {
+                let fourcc = self.pixelformat.code();
+                let width = self.width as uint;
+                let height = self.height as uint;
+                let bytesperline = self.bytesperline as uint;
+                let buffer = self.buffers.get_mut(0).planes.get_mut(0);
+                let pattern = self.test_pattern.get_mut_ref();
+
+                pattern.wait();
+                let dest = device::mapped_mut(&mut buffer.memory_map).mut_slice_to(buffer.length as uint);
+                pattern.render(fourcc, width, height, bytesperline, dest);
+                pattern.frame_count - 1
+            };
+            // Rendered rather than captured, so there is no capture time.
+            self.record_frame(sequence, time::precise_time_ns(), 0);
+            let field = self.field;
+            self.process_image(0, field);
+            return true;
+        }
+
+        if self.io_method == IoRead {
+            return self.read_frame_read();
+        }
+
+        let (buf_type, memory, num_planes) = (self.buf_type(), self.memory(), self.plane_fmt.len());
+        let buffer = match self.device.dqbuf(buf_type, memory, num_planes) {
+            Ok(buffer) => buffer,
+            Err(EAGAIN) => {
+                return false;
+            }
+            Err(e) => {
+                fail!("VIDIOC_DQBUF failed. {}", error_string(e as uint));
+            }
+        };
+
+        if buffer.index >= self.buffers.len() as u32 {
+            fail!();
+        }
+
+        self.record_frame(buffer.sequence, buffer.timestamp_ns, buffer.flags);
+        match self.meta {
+            Some(ref mut meta) => {
+                match meta.dequeue() {
+                    Ok(()) => {}
+                    Err(e) => fail!("{}", e)
+                }
+                if meta.take(buffer.sequence).is_none() {
+                    debug!("no metadata for frame {}", buffer.sequence);
+                }
+            }
+            None => {}
+        }
+        self.process_image(buffer.index, buffer.field);
+
+        match self.queue_buffer(buffer.index) {
+            Ok(_) => {}
+            Err(e) => {
+                fail!("VIDIOC_QBUF failed. {}", error_string(e as uint));
+            }
+        }
+        return true;
+    }
+
+    fn read_frame_read(&mut self) -> bool {
+        let buffer = self.buffers.get(0).planes.get(0);
+        let count = match self.device.read(buffer.memory_map.data, buffer.length as uint) {
+            Ok(count) => count,
+            Err(EAGAIN) => {
+                return false;
+            }
+            Err(e) => {
+                fail!("read() failed. {}", error_string(e as uint));
+            }
+        };
+
+        // read() carries no sequence or timestamp, so no drops can be seen.
+        // A frame cut short would show the rest of the previous one, so it
+        // is counted as an error and not shown. sizeimage may be padded
+        // beyond the image, so only the bytes the image needs are expected.
+        let sequence = self.stats.frames as u32;
+        let needed = pixfmt::image_size(self.pixelformat.code(), self.bytesperline as uint,
+                                        self.height as uint);
+        let short = count < needed;
+        let flags = if short { v4l2::V4L2_BUF_FLAG_ERROR } else { 0 };
+        self.record_frame(sequence, time::precise_time_ns(), flags);
+        if short {
+            debug!("read() returned {} of {} bytes", count, needed);
+            return true;
+        }
+        let field = self.field;
+        self.process_image(0, field);
+        return true;
+    }
+
+    // Records a frame just dequeued with the given v4l2_buffer sequence,
+    // timestamp and flags.
+    fn record_frame(&mut self, sequence: u32, timestamp_ns: u64, flags: u32) {
+        let now_ns = time::precise_time_ns();
+        match self.latency {
+            Some(ref mut latency) => latency.dequeued(timestamp_ns, flags, now_ns),
+            None => {}
+        }
+        self.stats.update(sequence, timestamp_ns, (flags & v4l2::V4L2_BUF_FLAG_ERROR) != 0);
+        if self.stats.log_due(now_ns) {
+            info!("{}", self.stats);
+            match self.latency {
+                Some(ref latency) => info!("{}", latency),
+                None => {}
+            }
+        }
+    }
+
+    // Where the frame is drawn in a `width` x `height` window.
+    pub fn viewport(&self, width: uint, height: uint) -> Rect {
+        scale::fit(self.scale_mode, self.width as uint, self.height as uint, width, height)
+    }
+
+    fn window_size(&self) -> (uint, uint) {
+        match self.surface {
+            Some(ref surface) => (surface.get_width() as uint, surface.get_height() as uint),
+            None => (self.width as uint, self.height as uint)
+        }
+    }
+
+    // The part of the frame shown at the current zoom and pan.
+    pub fn source_region(&self) -> Region {
+        let width = self.width as f64 / self.zoom;
+        let height = self.height as f64 / self.zoom;
+        let (x, y) = self.pan;
+        Region {
+            x: clamp(x, 0.0, self.width as f64 - width),
+            y: clamp(y, 0.0, self.height as f64 - height),
+            width: width,
+            height: height,
+        }
+    }
+
+    // Frame position under window position (x, y), None outside the image.
+    pub fn window_to_frame(&self, x: uint, y: uint) -> Option<(f64, f64)> {
+        let (window_width, window_height) = self.window_size();
+        let dest = self.viewport(window_width, window_height);
+        if !dest.contains(x, y) {
+            return None;
+        }
+        let src = self.source_region();
+        Some((src.x + ((x - dest.x) as f64 + 0.5) * src.width / dest.width as f64,
+              src.y + ((y - dest.y) as f64 + 0.5) * src.height / dest.height as f64))
+    }
+
+    // Zooms by `factor`, keeping the frame position under window position
+    // (x, y) in place.
+    pub fn zoom_at(&mut self, x: uint, y: uint, factor: f64) {
+        static MAX_ZOOM: f64 = 64.0;
+
+        let (window_width, window_height) = self.window_size();
+        let dest = self.viewport(window_width, window_height);
+        let src = self.source_region();
+        let (rx, ry) = if dest.contains(x, y) {
+            ((x - dest.x) as f64 / dest.width as f64, (y - dest.y) as f64 / dest.height as f64)
+        } else {
+            (0.5, 0.5)
+        };
+        let (fx, fy) = (src.x + rx * src.width, src.y + ry * src.height);
+
+        self.zoom = clamp(self.zoom * factor, 1.0, MAX_ZOOM);
+        let width = self.width as f64 / self.zoom;
+        let height = self.height as f64 / self.zoom;
+        self.pan = (fx - rx * width, fy - ry * height);
+        let region = self.source_region();
+        self.pan = (region.x, region.y);
+    }
+
+    // Moves the view along with a mouse drag of (dx, dy) window pixels.
+    pub fn pan_by(&mut self, dx: int, dy: int) {
+        let (window_width, window_height) = self.window_size();
+        let dest = self.viewport(window_width, window_height);
+        if dest.width == 0 || dest.height == 0 {
+            return;
+        }
+        let src = self.source_region();
+        self.pan = (src.x - dx as f64 * src.width / dest.width as f64,
+                    src.y - dy as f64 * src.height / dest.height as f64);
+        let region = self.source_region();
+        self.pan = (region.x, region.y);
+    }
+
+    pub fn reset_zoom(&mut self) {
+        self.zoom = 1.0;
+        self.pan = (0.0, 0.0);
+    }
+
+    // Pixel coordinates, raw samples and converted colour under the cursor.
+    // Must run before anything is drawn over the converted image.
+    fn inspector_lines(&self, frame: &Frame, canvas: &Canvas) -> Option<Vec<~str>> {
+        let (cx, cy) = match self.cursor {
+            Some(cursor) => cursor,
+            None => return None
+        };
+        if cx >= canvas.width || cy >= canvas.height {
+            return None;
+        }
+        let (fx, fy) = match self.window_to_frame(cx, cy) {
+            Some(position) => position,
+            None => return None
+        };
+        let x = clamp(fx.floor(), 0.0, (frame.width - 1) as f64) as uint;
+        let y = clamp(fy.floor(), 0.0, (frame.height - 1) as f64) as uint;
+
+        // Bayer formats are never negotiated, see pixfmt::is_supported(), so
+        // there are no Bayer channels to read out.
+        let raw = if frame.is_rgb() {
+            let (r, g, b) = frame.rgb_at(x, y);
+            format!("R {} G {} B {}", r, g, b)
+        } else if frame.fourcc == v4l2::V4L2_PIX_FMT_GREY {
+            let (luma, _, _) = frame.ycbcr_at(x, y);
+            format!("Y {}", luma)
+        } else {
+            let (luma, cb, cr) = frame.ycbcr_at(x, y);
+            format!("Y {} CB {} CR {}", luma, cb, cr)
+        };
+        let (r, g, b) = canvas.get(cx, cy);
+        Some(vec!(format!("POS {},{}", x, y), raw, format!("RGB {} {} {}", r, g, b)))
+    }
+
+    // Lines of the heads-up display describing the stream and latest frame.
+    fn hud_lines(&self) -> Vec<~str> {
+        let negotiated = match self.frame_interval {
+            Some((numerator, denominator)) => {
+                format!("{:.2}", denominator as f64 / numerator as f64)
+            }
+            None => "-".to_owned()
+        };
+        let sequence = match self.stats.last_sequence {
+            Some(sequence) => sequence.to_str(),
+            None => "-".to_owned()
+        };
+        let timestamp_ns = self.stats.last_timestamp_ns;
+        let mut lines = vec!(self.card.clone(),
+             format!("{} {}x{}", self.pixelformat, self.width, self.height),
+             format!("FPS {} / {:.2}", negotiated, self.stats.fps),
+             format!("SEQ {}", sequence),
+             format!("TS {}.{:06u}", timestamp_ns / 1000000000, (timestamp_ns % 1000000000) / 1000));
+        // Only analogue inputs have a standard; webcams have no choices here.
+        match self.std {
+            Some(std) => {
+                lines.push(format!("INPUT {} {}", self.input.unwrap_or(0), analog::std_to_str(std)));
+            }
+            None => {}
+        }
+        match self.frequency {
+            Some(mhz) => lines.push(format!("FREQ {:.2} MHZ", mhz)),
+            None => {}
+        }
+        match self.dv_timings {
+            Some(ref timings) => lines.push(format!("DV {}", timings)),
+            None => {}
+        }
+        match self.meta {
+            Some(ref meta) => lines.push_all_move(meta.hud_lines()),
+            None => {}
+        }
+        if self.monitor {
+            lines.push("MONITOR".to_owned());
+        }
+        // Changes from other applications show for a while.
+        static CONTROL_CHANGE_NS: u64 = 3000000000;
+        match self.control_changed {
+            Some((id, ns)) if time::precise_time_ns() - ns < CONTROL_CHANGE_NS => {
+                match self.controls.iter().find(|control| control.id == id) {
+                    Some(control) => lines.push(format!("{} {}", control.name, control.value)),
+                    None => {}
+                }
+            }
+            _ => {}
+        }
+        if self.field.is_interlaced() {
+            lines.push(format!("{} {}", self.field.name(),
+                               self.deinterlacer.method.to_str().to_ascii_upper()));
+        }
+        match self.crop_rect {
+            Some(rect) => {
+                lines.push(format!("CROP {}x{}+{}+{}", rect.width, rect.height, rect.x, rect.y));
+            }
+            None => {}
+        }
+        match self.pixel_aspect {
+            Some((numerator, denominator)) => {
+                lines.push(format!("PIXEL ASPECT {}/{}", numerator, denominator));
+            }
+            None => {}
+        }
+        lines
+    }
+
+    // Outlines the ROI where it is visible and lists its statistics in the
+    // top right corner.
+    fn draw_roi(&self, canvas: &mut Canvas, frame: &Frame, dest: Rect, scale: uint, margin: uint) {
+        static ROI_COLOR: (u8, u8, u8) = (255, 255, 0);
+
+        let roi = match self.roi {
+            Some(roi) if self.show_roi => roi,
+            _ => return
+        };
+        let stats = match guides::measure(frame, roi) {
+            Some(stats) => stats,
+            None => return
+        };
+
+        let src = self.source_region();
+        if dest.width > 0 && dest.height > 0 {
+            let to_window = |fx: f64, fy: f64| -> (f64, f64) {
+                (dest.x as f64 + (fx - src.x) * dest.width as f64 / src.width,
+                 dest.y as f64 + (fy - src.y) * dest.height as f64 / src.height)
+            };
+            let (x0, y0) = to_window(roi.x as f64, roi.y as f64);
+            let (x1, y1) = to_window((roi.x + roi.width) as f64, (roi.y + roi.height) as f64);
+            let x0 = clamp(x0, dest.x as f64, (dest.x + dest.width) as f64) as uint;
+            let y0 = clamp(y0, dest.y as f64, (dest.y + dest.height) as f64) as uint;
+            let x1 = clamp(x1, dest.x as f64, (dest.x + dest.width) as f64) as uint;
+            let y1 = clamp(y1, dest.y as f64, (dest.y + dest.height) as f64) as uint;
+            if x1 > x0 && y1 > y0 {
+                canvas.draw_rect(x0, y0, x1 - x0, y1 - y0, ROI_COLOR);
+            }
+        }
+
+        let lines = stats.lines();
+        let (w, h) = overlay::text_box_size(lines.as_slice(), scale);
+        if w + margin <= canvas.width && h + margin <= canvas.height {
+            canvas.text_box(lines.as_slice(), canvas.width - w - margin, margin, scale);
+        }
+    }
+
+    // Lines the enabled scopes up from the bottom right corner.
+    fn draw_scopes(&self, canvas: &mut Canvas, frame: &Frame, margin: uint) {
+        static SCOPE_HEIGHT: uint = 128;
+
+        if !self.show_histogram && !self.show_waveform && !self.show_vectorscope {
+            return;
+        }
+        if canvas.height < SCOPE_HEIGHT + margin {
+            return;
+        }
+        let levels = Scopes::compute(frame);
+        let y = canvas.height - SCOPE_HEIGHT - margin;
+        let mut right = canvas.width;
+
+        if self.show_vectorscope && right >= SCOPE_HEIGHT + margin {
+            right -= SCOPE_HEIGHT + margin;
+            levels.draw_vectorscope(canvas, right, y, SCOPE_HEIGHT);
+        }
+        if self.show_waveform && right >= scopes::WAVEFORM_COLUMNS + margin {
+            right -= scopes::WAVEFORM_COLUMNS + margin;
+            levels.draw_waveform(canvas, right, y, SCOPE_HEIGHT);
+        }
+        if self.show_histogram && right >= 256 + margin {
+            right -= 256 + margin;
+            levels.draw_histogram(canvas, right, y, SCOPE_HEIGHT);
+        }
+    }
+
+    // Draws the focus assists and overlays over `frame`, already converted
+    // to `dest` of `canvas`, and returns the lines the pixel inspector shows.
+    pub fn draw_over(&self, frame: &Frame, canvas: &mut Canvas, dest: Rect, src: Region) -> Option<Vec<~str>> {
+        // The inspector reads the converted colour before the assists
+        // paint over it.
+        let inspector = if self.show_inspector {
+            self.inspector_lines(frame, canvas)
+        } else {
+            None
+        };
+        self.assist.apply(frame, canvas, dest, src);
+        self.draw_overlays(canvas, frame, inspector.clone());
+        inspector
+    }
+
+    fn draw_overlays(&self, canvas: &mut Canvas, frame: &Frame, inspector: Option<Vec<~str>>) {
+        let scale = if canvas.width >= 640 { 2 } else { 1 };
+        let margin = 4 * scale;
+
+        let dest = self.viewport(canvas.width, canvas.height);
+        self.guides.draw(canvas, dest);
+        self.draw_roi(canvas, frame, dest, scale, margin);
+
+        match self.selection {
+            Some(((x0, y0), (x1, y1))) => {
+                let (left, top) = (min(x0, x1), min(y0, y1));
+                canvas.draw_rect(left, top, max(x0, x1) - left + 1, max(y0, y1) - top + 1, overlay::WHITE);
+            }
+            None => {}
+        }
+
+        if self.show_hud {
+            let lines = self.hud_lines();
+            let (w, h) = overlay::text_box_size(lines.as_slice(), scale);
+            if w + margin <= canvas.width && h + margin <= canvas.height {
+                canvas.text_box(lines.as_slice(), margin, margin, scale);
+            }
+        }
+
+        if self.show_stats {
+            let mut lines = self.stats.lines();
+            match self.latency {
+                Some(ref latency) => lines.push_all_move(latency.lines()),
+                None => {}
+            }
+            let (w, h) = overlay::text_box_size(lines.as_slice(), scale);
+            if w + margin <= canvas.width && h + margin <= canvas.height {
+                canvas.text_box(lines.as_slice(), margin, canvas.height - h - margin, scale);
+            }
+        }
+
+        self.draw_scopes(canvas, frame, margin);
+
+        match (inspector, self.cursor) {
+            (Some(lines), Some((cx, cy))) => {
+                // Beside the cursor, flipped to stay inside the window.
+                let (w, h) = overlay::text_box_size(lines.as_slice(), scale);
+                let offset = 8 * scale;
+                let x = if cx + offset + w <= canvas.width {
+                    cx + offset
+                } else if cx >= offset + w {
+                    cx - offset - w
+                } else {
+                    0
+                };
+                let y = if cy + offset + h <= canvas.height {
+                    cy + offset
+                } else if cy >= offset + h {
+                    cy - offset - h
+                } else {
+                    0
+                };
+                canvas.text_box(lines.as_slice(), x, y, scale);
+            }
+            _ => {}
+        }
+    }
+
+    fn process_image(&mut self, buffer_index: u32, field: Field) {
+        if self.surface.is_none() {
+            return;
+        }
+        // Each buffer of an alternating stream holds a single field.
+        let height = if self.field == device::Alternate { self.height / 2 } else { self.height };
+        let captured = buffer_frame(self.buffers.get(buffer_index as uint), self.plane_fmt.as_slice(),
+                                    self.pixelformat, self.width as uint, height as uint);
+        // `field` is the buffer's field order, which tells the fields of an
+        // alternating stream apart.
+        let frame = if self.field.is_interlaced() {
+            let field = if self.field == device::Alternate { field } else { self.field };
+            self.deinterlacer.process(&captured, field);
+            self.deinterlacer.frame()
+        } else {
+            captured
+        };
+        // The flash is looked for where the camera sees the window.
+        let region = self.roi.unwrap_or(Rect { x: 0, y: 0, width: frame.width, height: frame.height });
+        match self.latency {
+            Some(ref mut latency) if latency.flash.is_some() => {
+                match latency::mean_luma(&frame, region) {
+                    Some(luma) => latency.observe(luma),
+                    None => {}
+                }
+            }
+            _ => {}
+        }
+        let flash = self.latency.as_ref().map_or(false, |latency| latency.flash_lit());
+
+        match self.surface {
+            Some(ref surface) => {
+                let (width, height) = (surface.get_width() as uint, surface.get_height() as uint);
+                let dest = self.viewport(width, height);
+                let src = self.source_region();
+                let mut converted_ns = 0;
+                surface.with_lock(|pixels| {
+                    // The locked pixels are `height` rows of the surface pitch.
+                    let pitch = pixels.len() / height;
+                    let mut canvas = Canvas::new(pixels, pitch, width, height);
+                    scale::convert(&frame, &mut canvas, dest, src, self.filter);
+                    converted_ns = time::precise_time_ns();
+                    self.draw_over(&frame, &mut canvas, dest, src);
+                    if flash {
+                        canvas.fill_rect(0, 0, width, height, overlay::WHITE);
+                    }
+                });
+                surface.flip();
+                let flipped_ns = time::precise_time_ns();
+                match self.latency {
+                    Some(ref mut latency) => {
+                        latency.converted(converted_ns);
+                        latency.flipped(flipped_ns);
+                    }
+                    None => {}
+                }
             }
             None => {}
         }
     }
 }
 
+// Describes `buffer` as a Frame in the negotiated format.
+fn buffer_frame<'a>(buffer: &'a Buffer, plane_fmt: &[PlaneFormat], pixelformat: Fourcc,
+                    width: uint, height: uint) -> Frame<'a> {
+    let planes = buffer.planes.iter().zip(plane_fmt.iter()).map(|(plane, fmt)| {
+        let data = device::mapped(&plane.memory_map);
+        Plane {
+            data: data.slice_to(min(plane.length as uint, data.len())),
+            bytesperline: fmt.bytesperline as uint,
+        }
+    }).collect();
+    Frame::from_planes(pixelformat.code(), width, height, planes)
+}
+
+fn clamp(v: f64, lo: f64, hi: f64) -> f64 {
+    if v > hi { hi } else if v < lo { lo } else { v }
+}
+
+fn to_v4l2_rect(rect: Rect) -> v4l2_rect {
+    v4l2_rect {
+        left: rect.x as i32,
+        top: rect.y as i32,
+        width: rect.width as i32,
+        height: rect.height as i32,
+    }
+}
+
+// Negative offsets, which some drivers use for bounds centred on the
+// optical axis, are clamped to the top left corner.
+fn from_v4l2_rect(rect: &v4l2_rect) -> Rect {
+    let positive = |v: i32| if v > 0 { v as uint } else { 0 };
+    Rect {
+        x: positive(rect.left),
+        y: positive(rect.top),
+        width: positive(rect.width),
+        height: positive(rect.height),
+    }
+}
+
+// Buggy driver paranoia: raises the line length and size of each plane of
+// a format the driver filled in to what the pixel format needs.
+fn checked_format(mut format: PixFormat) -> PixFormat {
+    let pixelformat = format.pixelformat.code();
+    let (width, height) = (format.width as uint, format.height as uint);
+    for (i, plane) in format.planes.mut_iter().enumerate() {
+        let min_bpl = pixfmt::plane_min_bytesperline(pixelformat, i, width) as u32;
+        if plane.bytesperline < min_bpl {
+            plane.bytesperline = min_bpl;
+        }
+        let min_size = pixfmt::plane_size(pixelformat, i, plane.bytesperline as uint, height) as u32;
+        if plane.sizeimage < min_size {
+            plane.sizeimage = min_size;
+        }
+    }
+    format
+}
+
 impl Drop for UvcView {
     fn drop(&mut self) {
-        if self.fd != -1 {
-            unsafe {
-                libc::close(self.fd);
-            }
-        }
+        self.truncate_buffers(0);
+        self.device.close();
     }
 }
@@ -79,12 +79,26 @@ pub static V4L2_CAP_AUDIO: u32 = 0x20000;
 pub static V4L2_CAP_RADIO: u32 = 0x40000;
 //#define V4L2_CAP_MODULATOR 0x00080000 /* has a modulator */
 pub static V4L2_CAP_MODULATOR: u32 = 0x80000;
+//#define V4L2_CAP_SDR_CAPTURE 0x00100000 /* Is a SDR capture device */
+pub static V4L2_CAP_SDR_CAPTURE: u32 = 0x100000;
+//#define V4L2_CAP_EXT_PIX_FORMAT 0x00200000 /* Supports the extended pixel format */
+pub static V4L2_CAP_EXT_PIX_FORMAT: u32 = 0x200000;
+//#define V4L2_CAP_SDR_OUTPUT 0x00400000 /* Is a SDR output device */
+pub static V4L2_CAP_SDR_OUTPUT: u32 = 0x400000;
+//#define V4L2_CAP_META_CAPTURE 0x00800000 /* Is a metadata capture device */
+pub static V4L2_CAP_META_CAPTURE: u32 = 0x800000;
 //#define V4L2_CAP_READWRITE 0x01000000 /* read/write systemcalls */
 pub static V4L2_CAP_READWRITE: u32 = 0x1000000;
 //#define V4L2_CAP_ASYNCIO 0x02000000 /* async I/O */
 pub static V4L2_CAP_ASYNCIO: u32 = 0x2000000;
 //#define V4L2_CAP_STREAMING 0x04000000 /* streaming I/O ioctls */
 pub static V4L2_CAP_STREAMING: u32 = 0x4000000;
+//#define V4L2_CAP_META_OUTPUT 0x08000000 /* Is a metadata output device */
+pub static V4L2_CAP_META_OUTPUT: u32 = 0x8000000;
+//#define V4L2_CAP_TOUCH 0x10000000 /* Is a touch device */
+pub static V4L2_CAP_TOUCH: u32 = 0x10000000;
+//#define V4L2_CAP_IO_MC 0x20000000 /* Is input/output controlled by the media controller */
+pub static V4L2_CAP_IO_MC: u32 = 0x20000000;
 //#define V4L2_CAP_DEVICE_CAPS 0x80000000 /* sets device capabilities field */
 pub static V4L2_CAP_DEVICE_CAPS: u32 = 0x80000000;
 //#define V4L2_PIX_FMT_RGB332 v4l2_fourcc('R', 'G', 'B', '1') /* 8 RGB-3-3-2 */
@@ -303,6 +317,72 @@ pub static V4L2_PIX_FMT_JPGL: u32 = 0x4c47504a;
 pub static V4L2_PIX_FMT_SE401: u32 = 0x31303453;
 //#define V4L2_PIX_FMT_S5C_UYVY_JPG v4l2_fourcc('S', '5', 'C', 'I') /* S5C73M3 interleaved UYVY/JPEG */
 pub static V4L2_PIX_FMT_S5C_UYVY_JPG: u32 = 0x49433553;
+//#define V4L2_PIX_FMT_ABGR32 v4l2_fourcc('A', 'R', '2', '4') /* 32 BGRA-8-8-8-8 */
+pub static V4L2_PIX_FMT_ABGR32: u32 = 0x34325241;
+//#define V4L2_PIX_FMT_XBGR32 v4l2_fourcc('X', 'R', '2', '4') /* 32 BGRX-8-8-8-8 */
+pub static V4L2_PIX_FMT_XBGR32: u32 = 0x34325258;
+//#define V4L2_PIX_FMT_BGRA32 v4l2_fourcc('R', 'A', '2', '4') /* 32 ABGR-8-8-8-8 */
+pub static V4L2_PIX_FMT_BGRA32: u32 = 0x34324152;
+//#define V4L2_PIX_FMT_BGRX32 v4l2_fourcc('R', 'X', '2', '4') /* 32 XBGR-8-8-8-8 */
+pub static V4L2_PIX_FMT_BGRX32: u32 = 0x34325852;
+//#define V4L2_PIX_FMT_RGBA32 v4l2_fourcc('A', 'B', '2', '4') /* 32 RGBA-8-8-8-8 */
+pub static V4L2_PIX_FMT_RGBA32: u32 = 0x34324241;
+//#define V4L2_PIX_FMT_RGBX32 v4l2_fourcc('X', 'B', '2', '4') /* 32 RGBX-8-8-8-8 */
+pub static V4L2_PIX_FMT_RGBX32: u32 = 0x34324258;
+//#define V4L2_PIX_FMT_ARGB32 v4l2_fourcc('B', 'A', '2', '4') /* 32 ARGB-8-8-8-8 */
+pub static V4L2_PIX_FMT_ARGB32: u32 = 0x34324142;
+//#define V4L2_PIX_FMT_XRGB32 v4l2_fourcc('B', 'X', '2', '4') /* 32 XRGB-8-8-8-8 */
+pub static V4L2_PIX_FMT_XRGB32: u32 = 0x34325842;
+//#define V4L2_PIX_FMT_Y14 v4l2_fourcc('Y', '1', '4', ' ') /* 14 Greyscale */
+pub static V4L2_PIX_FMT_Y14: u32 = 0x20343159;
+//#define V4L2_PIX_FMT_Y10P v4l2_fourcc('Y', '1', '0', 'P') /* 10 Greyscale, MIPI RAW10 packed */
+pub static V4L2_PIX_FMT_Y10P: u32 = 0x50303159;
+//#define V4L2_PIX_FMT_Z16 v4l2_fourcc('Z', '1', '6', ' ') /* Depth data 16-bit */
+pub static V4L2_PIX_FMT_Z16: u32 = 0x2036315a;
+//#define V4L2_PIX_FMT_INZI v4l2_fourcc('I', 'N', 'Z', 'I') /* Intel Planar Greyscale 10-bit and Depth 16-bit */
+pub static V4L2_PIX_FMT_INZI: u32 = 0x495a4e49;
+//#define V4L2_PIX_FMT_CNF4 v4l2_fourcc('C', 'N', 'F', '4') /* Intel 4-bit packed depth confidence information */
+pub static V4L2_PIX_FMT_CNF4: u32 = 0x34464e43;
+//#define V4L2_PIX_FMT_NV16M v4l2_fourcc('N', 'M', '1', '6') /* 16 Y/CbCr 4:2:2 */
+pub static V4L2_PIX_FMT_NV16M: u32 = 0x36314d4e;
+//#define V4L2_PIX_FMT_NV61M v4l2_fourcc('N', 'M', '6', '1') /* 16 Y/CrCb 4:2:2 */
+pub static V4L2_PIX_FMT_NV61M: u32 = 0x31364d4e;
+//#define V4L2_PIX_FMT_YUV422M v4l2_fourcc('Y', 'M', '1', '6') /* 16 YUV422 planar */
+pub static V4L2_PIX_FMT_YUV422M: u32 = 0x36314d59;
+//#define V4L2_PIX_FMT_YVU422M v4l2_fourcc('Y', 'M', '6', '1') /* 16 YVU422 planar */
+pub static V4L2_PIX_FMT_YVU422M: u32 = 0x31364d59;
+//#define V4L2_PIX_FMT_YUV444M v4l2_fourcc('Y', 'M', '2', '4') /* 24 YUV444 planar */
+pub static V4L2_PIX_FMT_YUV444M: u32 = 0x34324d59;
+//#define V4L2_PIX_FMT_YVU444M v4l2_fourcc('Y', 'M', '4', '2') /* 24 YVU444 planar */
+pub static V4L2_PIX_FMT_YVU444M: u32 = 0x32344d59;
+//#define V4L2_PIX_FMT_P010 v4l2_fourcc('P', '0', '1', '0') /* 24 Y/CbCr 4:2:0 10-bit per component */
+pub static V4L2_PIX_FMT_P010: u32 = 0x30313050;
+//#define V4L2_PIX_FMT_SGBRG16 v4l2_fourcc('G', 'B', '1', '6') /* 16 GBGB.. RGRG.. */
+pub static V4L2_PIX_FMT_SGBRG16: u32 = 0x36314247;
+//#define V4L2_PIX_FMT_SGRBG16 v4l2_fourcc('G', 'R', '1', '6') /* 16 GRGR.. BGBG.. */
+pub static V4L2_PIX_FMT_SGRBG16: u32 = 0x36315247;
+//#define V4L2_PIX_FMT_SRGGB16 v4l2_fourcc('R', 'G', '1', '6') /* 16 RGRG.. GBGB.. */
+pub static V4L2_PIX_FMT_SRGGB16: u32 = 0x36314752;
+//#define V4L2_PIX_FMT_SBGGR10P v4l2_fourcc('p', 'B', 'A', 'A')
+pub static V4L2_PIX_FMT_SBGGR10P: u32 = 0x41414270;
+//#define V4L2_PIX_FMT_SGBRG10P v4l2_fourcc('p', 'G', 'A', 'A')
+pub static V4L2_PIX_FMT_SGBRG10P: u32 = 0x41414770;
+//#define V4L2_PIX_FMT_SGRBG10P v4l2_fourcc('p', 'g', 'A', 'A')
+pub static V4L2_PIX_FMT_SGRBG10P: u32 = 0x41416770;
+//#define V4L2_PIX_FMT_SRGGB10P v4l2_fourcc('p', 'R', 'A', 'A')
+pub static V4L2_PIX_FMT_SRGGB10P: u32 = 0x41415270;
+//#define V4L2_PIX_FMT_HEVC v4l2_fourcc('H', 'E', 'V', 'C') /* HEVC aka H.265 */
+pub static V4L2_PIX_FMT_HEVC: u32 = 0x43564548;
+//#define V4L2_PIX_FMT_VP9 v4l2_fourcc('V', 'P', '9', '0') /* VP9 */
+pub static V4L2_PIX_FMT_VP9: u32 = 0x30395056;
+//#define V4L2_PIX_FMT_Y16_BE v4l2_fourcc_be('Y', '1', '6', ' ') /* 16 Greyscale BE */
+pub static V4L2_PIX_FMT_Y16_BE: u32 = 0xa0363159;
+//#define V4L2_META_FMT_VSP1_HGO v4l2_fourcc('V', 'S', 'P', 'H') /* R-Car VSP1 1-D Histogram */
+pub static V4L2_META_FMT_VSP1_HGO: u32 = 0x48505356;
+//#define V4L2_META_FMT_UVC v4l2_fourcc('U', 'V', 'C', 'H') /* UVC Payload Header metadata */
+pub static V4L2_META_FMT_UVC: u32 = 0x48435655;
+//#define V4L2_META_FMT_D4XX v4l2_fourcc('D', '4', 'X', 'X') /* D4XX Payload Header metadata */
+pub static V4L2_META_FMT_D4XX: u32 = 0x58583444;
 //#define V4L2_FMT_FLAG_COMPRESSED 0x0001
 pub static V4L2_FMT_FLAG_COMPRESSED: u32 = 0x1;
 //#define V4L2_FMT_FLAG_EMULATED 0x0002
@@ -351,6 +431,8 @@ pub static V4L2_BUF_FLAG_PFRAME: u32 = 0x10;
 pub static V4L2_BUF_FLAG_BFRAME: u32 = 0x20;
 //#define V4L2_BUF_FLAG_ERROR 0x0040
 pub static V4L2_BUF_FLAG_ERROR: u32 = 0x40;
+//#define V4L2_BUF_FLAG_IN_REQUEST 0x0080 /* Buffer is part of a request */
+pub static V4L2_BUF_FLAG_IN_REQUEST: u32 = 0x80;
 //#define V4L2_BUF_FLAG_TIMECODE 0x0100 /* timecode field is valid */
 pub static V4L2_BUF_FLAG_TIMECODE: u32 = 0x100;
 //#define V4L2_BUF_FLAG_PREPARED 0x0400 /* Buffer is prepared for queuing */
@@ -359,6 +441,44 @@ pub static V4L2_BUF_FLAG_PREPARED: u32 = 0x400;
 pub static V4L2_BUF_FLAG_NO_CACHE_INVALIDATE: u32 = 0x800;
 //#define V4L2_BUF_FLAG_NO_CACHE_CLEAN 0x1000
 pub static V4L2_BUF_FLAG_NO_CACHE_CLEAN: u32 = 0x1000;
+//#define V4L2_BUF_FLAG_TIMESTAMP_MASK 0x0000e000
+pub static V4L2_BUF_FLAG_TIMESTAMP_MASK: u32 = 0xe000;
+//#define V4L2_BUF_FLAG_TIMESTAMP_UNKNOWN 0x00000000
+pub static V4L2_BUF_FLAG_TIMESTAMP_UNKNOWN: u32 = 0x0;
+//#define V4L2_BUF_FLAG_TIMESTAMP_MONOTONIC 0x00002000
+pub static V4L2_BUF_FLAG_TIMESTAMP_MONOTONIC: u32 = 0x2000;
+//#define V4L2_BUF_FLAG_TIMESTAMP_COPY 0x00004000
+pub static V4L2_BUF_FLAG_TIMESTAMP_COPY: u32 = 0x4000;
+//#define V4L2_BUF_FLAG_TSTAMP_SRC_MASK 0x00070000
+pub static V4L2_BUF_FLAG_TSTAMP_SRC_MASK: u32 = 0x70000;
+//#define V4L2_BUF_FLAG_TSTAMP_SRC_EOF 0x00000000
+pub static V4L2_BUF_FLAG_TSTAMP_SRC_EOF: u32 = 0x0;
+//#define V4L2_BUF_FLAG_TSTAMP_SRC_SOE 0x00010000
+pub static V4L2_BUF_FLAG_TSTAMP_SRC_SOE: u32 = 0x10000;
+//#define V4L2_BUF_FLAG_LAST 0x00100000 /* mem2mem encoder/decoder */
+pub static V4L2_BUF_FLAG_LAST: u32 = 0x100000;
+//#define V4L2_BUF_FLAG_REQUEST_FD 0x00800000 /* request_fd is valid */
+pub static V4L2_BUF_FLAG_REQUEST_FD: u32 = 0x800000;
+//#define V4L2_SEL_TGT_CROP 0x0000
+pub static V4L2_SEL_TGT_CROP: u32 = 0x0;
+//#define V4L2_SEL_TGT_CROP_DEFAULT 0x0001
+pub static V4L2_SEL_TGT_CROP_DEFAULT: u32 = 0x1;
+//#define V4L2_SEL_TGT_CROP_BOUNDS 0x0002
+pub static V4L2_SEL_TGT_CROP_BOUNDS: u32 = 0x2;
+//#define V4L2_SEL_TGT_COMPOSE 0x0100
+pub static V4L2_SEL_TGT_COMPOSE: u32 = 0x100;
+//#define V4L2_SEL_TGT_COMPOSE_DEFAULT 0x0101
+pub static V4L2_SEL_TGT_COMPOSE_DEFAULT: u32 = 0x101;
+//#define V4L2_SEL_TGT_COMPOSE_BOUNDS 0x0102
+pub static V4L2_SEL_TGT_COMPOSE_BOUNDS: u32 = 0x102;
+//#define V4L2_SEL_TGT_COMPOSE_PADDED 0x0103
+pub static V4L2_SEL_TGT_COMPOSE_PADDED: u32 = 0x103;
+//#define V4L2_SEL_FLAG_GE (1 << 0)
+pub static V4L2_SEL_FLAG_GE: u32 = 0x1;
+//#define V4L2_SEL_FLAG_LE (1 << 1)
+pub static V4L2_SEL_FLAG_LE: u32 = 0x2;
+//#define V4L2_SEL_FLAG_KEEP_CONFIG (1 << 2)
+pub static V4L2_SEL_FLAG_KEEP_CONFIG: u32 = 0x4;
 //#define V4L2_FBUF_CAP_EXTERNOVERLAY 0x0001
 pub static V4L2_FBUF_CAP_EXTERNOVERLAY: u32 = 0x1;
 //#define V4L2_FBUF_CAP_CHROMAKEY 0x0002
@@ -394,135 +514,97 @@ pub static V4L2_MODE_HIGHQUALITY: u32 = 0x1;
 //#define V4L2_CAP_TIMEPERFRAME 0x1000 /* timeperframe field is supported */
 pub static V4L2_CAP_TIMEPERFRAME: u32 = 0x1000;
 //#define V4L2_STD_PAL_B ((v4l2_std_id)0x00000001)
-pub static V4L2_STD_PAL_B: u32 = 0x1;
+pub static V4L2_STD_PAL_B: v4l2_std_id = 0x1;
 //#define V4L2_STD_PAL_B1 ((v4l2_std_id)0x00000002)
-pub static V4L2_STD_PAL_B1: u32 = 0x2;
+pub static V4L2_STD_PAL_B1: v4l2_std_id = 0x2;
 //#define V4L2_STD_PAL_G ((v4l2_std_id)0x00000004)
-pub static V4L2_STD_PAL_G: u32 = 0x4;
+pub static V4L2_STD_PAL_G: v4l2_std_id = 0x4;
 //#define V4L2_STD_PAL_H ((v4l2_std_id)0x00000008)
-pub static V4L2_STD_PAL_H: u32 = 0x8;
+pub static V4L2_STD_PAL_H: v4l2_std_id = 0x8;
 //#define V4L2_STD_PAL_I ((v4l2_std_id)0x00000010)
-pub static V4L2_STD_PAL_I: u32 = 0x10;
+pub static V4L2_STD_PAL_I: v4l2_std_id = 0x10;
 //#define V4L2_STD_PAL_D ((v4l2_std_id)0x00000020)
-pub static V4L2_STD_PAL_D: u32 = 0x20;
+pub static V4L2_STD_PAL_D: v4l2_std_id = 0x20;
 //#define V4L2_STD_PAL_D1 ((v4l2_std_id)0x00000040)
-pub static V4L2_STD_PAL_D1: u32 = 0x40;
+pub static V4L2_STD_PAL_D1: v4l2_std_id = 0x40;
 //#define V4L2_STD_PAL_K ((v4l2_std_id)0x00000080)
-pub static V4L2_STD_PAL_K: u32 = 0x80;
+pub static V4L2_STD_PAL_K: v4l2_std_id = 0x80;
 //#define V4L2_STD_PAL_M ((v4l2_std_id)0x00000100)
-pub static V4L2_STD_PAL_M: u32 = 0x100;
+pub static V4L2_STD_PAL_M: v4l2_std_id = 0x100;
 //#define V4L2_STD_PAL_N ((v4l2_std_id)0x00000200)
-pub static V4L2_STD_PAL_N: u32 = 0x200;
+pub static V4L2_STD_PAL_N: v4l2_std_id = 0x200;
 //#define V4L2_STD_PAL_Nc ((v4l2_std_id)0x00000400)
-pub static V4L2_STD_PAL_Nc: u32 = 0x400;
+pub static V4L2_STD_PAL_Nc: v4l2_std_id = 0x400;
 //#define V4L2_STD_PAL_60 ((v4l2_std_id)0x00000800)
-pub static V4L2_STD_PAL_60: u32 = 0x800;
+pub static V4L2_STD_PAL_60: v4l2_std_id = 0x800;
 //#define V4L2_STD_NTSC_M ((v4l2_std_id)0x00001000) /* BTSC */
-pub static V4L2_STD_NTSC_M: u32 = 0x1000;
+pub static V4L2_STD_NTSC_M: v4l2_std_id = 0x1000;
 //#define V4L2_STD_NTSC_M_JP ((v4l2_std_id)0x00002000) /* EIA-J */
-pub static V4L2_STD_NTSC_M_JP: u32 = 0x2000;
+pub static V4L2_STD_NTSC_M_JP: v4l2_std_id = 0x2000;
 //#define V4L2_STD_NTSC_443 ((v4l2_std_id)0x00004000)
-pub static V4L2_STD_NTSC_443: u32 = 0x4000;
+pub static V4L2_STD_NTSC_443: v4l2_std_id = 0x4000;
 //#define V4L2_STD_NTSC_M_KR ((v4l2_std_id)0x00008000) /* FM A2 */
-pub static V4L2_STD_NTSC_M_KR: u32 = 0x8000;
+pub static V4L2_STD_NTSC_M_KR: v4l2_std_id = 0x8000;
 //#define V4L2_STD_SECAM_B ((v4l2_std_id)0x00010000)
-pub static V4L2_STD_SECAM_B: u32 = 0x10000;
+pub static V4L2_STD_SECAM_B: v4l2_std_id = 0x10000;
 //#define V4L2_STD_SECAM_D ((v4l2_std_id)0x00020000)
-pub static V4L2_STD_SECAM_D: u32 = 0x20000;
+pub static V4L2_STD_SECAM_D: v4l2_std_id = 0x20000;
 //#define V4L2_STD_SECAM_G ((v4l2_std_id)0x00040000)
-pub static V4L2_STD_SECAM_G: u32 = 0x40000;
+pub static V4L2_STD_SECAM_G: v4l2_std_id = 0x40000;
 //#define V4L2_STD_SECAM_H ((v4l2_std_id)0x00080000)
-pub static V4L2_STD_SECAM_H: u32 = 0x80000;
+pub static V4L2_STD_SECAM_H: v4l2_std_id = 0x80000;
 //#define V4L2_STD_SECAM_K ((v4l2_std_id)0x00100000)
-pub static V4L2_STD_SECAM_K: u32 = 0x100000;
+pub static V4L2_STD_SECAM_K: v4l2_std_id = 0x100000;
 //#define V4L2_STD_SECAM_K1 ((v4l2_std_id)0x00200000)
-pub static V4L2_STD_SECAM_K1: u32 = 0x200000;
+pub static V4L2_STD_SECAM_K1: v4l2_std_id = 0x200000;
 //#define V4L2_STD_SECAM_L ((v4l2_std_id)0x00400000)
-pub static V4L2_STD_SECAM_L: u32 = 0x400000;
+pub static V4L2_STD_SECAM_L: v4l2_std_id = 0x400000;
 //#define V4L2_STD_SECAM_LC ((v4l2_std_id)0x00800000)
-pub static V4L2_STD_SECAM_LC: u32 = 0x800000;
+pub static V4L2_STD_SECAM_LC: v4l2_std_id = 0x800000;
 //#define V4L2_STD_ATSC_8_VSB ((v4l2_std_id)0x01000000)
-pub static V4L2_STD_ATSC_8_VSB: u32 = 0x1000000;
+pub static V4L2_STD_ATSC_8_VSB: v4l2_std_id = 0x1000000;
 //#define V4L2_STD_ATSC_16_VSB ((v4l2_std_id)0x02000000)
-pub static V4L2_STD_ATSC_16_VSB: u32 = 0x2000000;
+pub static V4L2_STD_ATSC_16_VSB: v4l2_std_id = 0x2000000;
 //#define V4L2_STD_NTSC (V4L2_STD_NTSC_M | ...
-pub static V4L2_STD_NTSC: u32 = 0xb000;
+pub static V4L2_STD_NTSC: v4l2_std_id = 0xb000;
 //#define V4L2_STD_SECAM_DK (V4L2_STD_SECAM_D | ...
-pub static V4L2_STD_SECAM_DK: u32 = 0x320000;
+pub static V4L2_STD_SECAM_DK: v4l2_std_id = 0x320000;
 //#define V4L2_STD_SECAM (V4L2_STD_SECAM_B | ...
-pub static V4L2_STD_SECAM: u32 = 0xff0000;
+pub static V4L2_STD_SECAM: v4l2_std_id = 0xff0000;
 //#define V4L2_STD_PAL_BG (V4L2_STD_PAL_B | ...
-pub static V4L2_STD_PAL_BG: u32 = 0x7;
+pub static V4L2_STD_PAL_BG: v4l2_std_id = 0x7;
 //#define V4L2_STD_PAL_DK (V4L2_STD_PAL_D | ...
-pub static V4L2_STD_PAL_DK: u32 = 0xe0;
+pub static V4L2_STD_PAL_DK: v4l2_std_id = 0xe0;
 //#define V4L2_STD_PAL (V4L2_STD_PAL_BG | ...
-pub static V4L2_STD_PAL: u32 = 0xff;
+pub static V4L2_STD_PAL: v4l2_std_id = 0xff;
 //#define V4L2_STD_B (V4L2_STD_PAL_B | ...
-pub static V4L2_STD_B: u32 = 0x10003;
+pub static V4L2_STD_B: v4l2_std_id = 0x10003;
 //#define V4L2_STD_G (V4L2_STD_PAL_G | ...
-pub static V4L2_STD_G: u32 = 0x40004;
+pub static V4L2_STD_G: v4l2_std_id = 0x40004;
 //#define V4L2_STD_H (V4L2_STD_PAL_H | ...
-pub static V4L2_STD_H: u32 = 0x80008;
+pub static V4L2_STD_H: v4l2_std_id = 0x80008;
 //#define V4L2_STD_L (V4L2_STD_SECAM_L | ...
-pub static V4L2_STD_L: u32 = 0xc00000;
+pub static V4L2_STD_L: v4l2_std_id = 0xc00000;
 //#define V4L2_STD_GH (V4L2_STD_G | ...
-pub static V4L2_STD_GH: u32 = 0xc000c;
+pub static V4L2_STD_GH: v4l2_std_id = 0xc000c;
 //#define V4L2_STD_DK (V4L2_STD_PAL_DK | ...
-pub static V4L2_STD_DK: u32 = 0x3200e0;
+pub static V4L2_STD_DK: v4l2_std_id = 0x3200e0;
 //#define V4L2_STD_BG (V4L2_STD_B | ...
-pub static V4L2_STD_BG: u32 = 0x50007;
+pub static V4L2_STD_BG: v4l2_std_id = 0x50007;
 //#define V4L2_STD_MN (V4L2_STD_PAL_M | ...
-pub static V4L2_STD_MN: u32 = 0xb700;
+pub static V4L2_STD_MN: v4l2_std_id = 0xb700;
 //#define V4L2_STD_MTS (V4L2_STD_NTSC_M | ...
-pub static V4L2_STD_MTS: u32 = 0x1700;
+pub static V4L2_STD_MTS: v4l2_std_id = 0x1700;
 //#define V4L2_STD_525_60 (V4L2_STD_PAL_M | ...
-pub static V4L2_STD_525_60: u32 = 0xf900;
+pub static V4L2_STD_525_60: v4l2_std_id = 0xf900;
 //#define V4L2_STD_625_50 (V4L2_STD_PAL | ...
-pub static V4L2_STD_625_50: u32 = 0xff06ff;
+pub static V4L2_STD_625_50: v4l2_std_id = 0xff06ff;
 //#define V4L2_STD_ATSC (V4L2_STD_ATSC_8_VSB | ...
-pub static V4L2_STD_ATSC: u32 = 0x3000000;
+pub static V4L2_STD_ATSC: v4l2_std_id = 0x3000000;
 //#define V4L2_STD_UNKNOWN 0
-pub static V4L2_STD_UNKNOWN: u32 = 0x0;
+pub static V4L2_STD_UNKNOWN: v4l2_std_id = 0x0;
 //#define V4L2_STD_ALL (V4L2_STD_525_60 | ...
-pub static V4L2_STD_ALL: u32 = 0xffffff;
-//#define V4L2_DV_INVALID 0
-pub static V4L2_DV_INVALID: u32 = 0x0;
-//#define V4L2_DV_480P59_94 1 /* BT.1362 */
-pub static V4L2_DV_480P59_94: u32 = 0x1;
-//#define V4L2_DV_576P50 2 /* BT.1362 */
-pub static V4L2_DV_576P50: u32 = 0x2;
-//#define V4L2_DV_720P24 3 /* SMPTE 296M */
-pub static V4L2_DV_720P24: u32 = 0x3;
-//#define V4L2_DV_720P25 4 /* SMPTE 296M */
-pub static V4L2_DV_720P25: u32 = 0x4;
-//#define V4L2_DV_720P30 5 /* SMPTE 296M */
-pub static V4L2_DV_720P30: u32 = 0x5;
-//#define V4L2_DV_720P50 6 /* SMPTE 296M */
-pub static V4L2_DV_720P50: u32 = 0x6;
-//#define V4L2_DV_720P59_94 7 /* SMPTE 274M */
-pub static V4L2_DV_720P59_94: u32 = 0x7;
-//#define V4L2_DV_720P60 8 /* SMPTE 274M/296M */
-pub static V4L2_DV_720P60: u32 = 0x8;
-//#define V4L2_DV_1080I29_97 9 /* BT.1120/ SMPTE 274M */
-pub static V4L2_DV_1080I29_97: u32 = 0x9;
-//#define V4L2_DV_1080I30 10 /* BT.1120/ SMPTE 274M */
-pub static V4L2_DV_1080I30: u32 = 0xa;
-//#define V4L2_DV_1080I25 11 /* BT.1120 */
-pub static V4L2_DV_1080I25: u32 = 0xb;
-//#define V4L2_DV_1080I50 12 /* SMPTE 296M */
-pub static V4L2_DV_1080I50: u32 = 0xc;
-//#define V4L2_DV_1080I60 13 /* SMPTE 296M */
-pub static V4L2_DV_1080I60: u32 = 0xd;
-//#define V4L2_DV_1080P24 14 /* SMPTE 296M */
-pub static V4L2_DV_1080P24: u32 = 0xe;
-//#define V4L2_DV_1080P25 15 /* SMPTE 296M */
-pub static V4L2_DV_1080P25: u32 = 0xf;
-//#define V4L2_DV_1080P30 16 /* SMPTE 296M */
-pub static V4L2_DV_1080P30: u32 = 0x10;
-//#define V4L2_DV_1080P50 17 /* BT.1120 */
-pub static V4L2_DV_1080P50: u32 = 0x11;
-//#define V4L2_DV_1080P60 18 /* BT.1120 */
-pub static V4L2_DV_1080P60: u32 = 0x12;
+pub static V4L2_STD_ALL: v4l2_std_id = 0xffffff;
 //#define V4L2_DV_PROGRESSIVE 0
 pub static V4L2_DV_PROGRESSIVE: u32 = 0x0;
 //#define V4L2_DV_INTERLACED 1
@@ -587,8 +669,6 @@ pub static V4L2_IN_ST_MACROVISION: u32 = 0x1000000;
 pub static V4L2_IN_ST_NO_ACCESS: u32 = 0x2000000;
 //#define V4L2_IN_ST_VTR 0x04000000 /* VTR time constant */
 pub static V4L2_IN_ST_VTR: u32 = 0x4000000;
-//#define V4L2_IN_CAP_PRESETS 0x00000001 /* Supports S_DV_PRESET */
-pub static V4L2_IN_CAP_PRESETS: u32 = 0x1;
 //#define V4L2_IN_CAP_DV_TIMINGS 0x00000002 /* Supports S_DV_TIMINGS */
 pub static V4L2_IN_CAP_DV_TIMINGS: u32 = 0x2;
 //#define V4L2_IN_CAP_CUSTOM_TIMINGS V4L2_IN_CAP_DV_TIMINGS /* For compatibility */
@@ -601,8 +681,6 @@ pub static V4L2_OUTPUT_TYPE_MODULATOR: u32 = 0x1;
 pub static V4L2_OUTPUT_TYPE_ANALOG: u32 = 0x2;
 //#define V4L2_OUTPUT_TYPE_ANALOGVGAOVERLAY 3
 pub static V4L2_OUTPUT_TYPE_ANALOGVGAOVERLAY: u32 = 0x3;
-//#define V4L2_OUT_CAP_PRESETS 0x00000001 /* Supports S_DV_PRESET */
-pub static V4L2_OUT_CAP_PRESETS: u32 = 0x1;
 //#define V4L2_OUT_CAP_DV_TIMINGS 0x00000002 /* Supports S_DV_TIMINGS */
 pub static V4L2_OUT_CAP_DV_TIMINGS: u32 = 0x2;
 //#define V4L2_OUT_CAP_CUSTOM_TIMINGS V4L2_OUT_CAP_DV_TIMINGS /* For compatibility */
@@ -633,6 +711,24 @@ pub static V4L2_CTRL_FLAG_NEXT_CTRL: u32 = 0x80000000;
 pub static V4L2_CID_MAX_CTRLS: u32 = 0x400;
 //#define V4L2_CID_PRIVATE_BASE 0x08000000
 pub static V4L2_CID_PRIVATE_BASE: u32 = 0x8000000;
+//#define V4L2_CID_BASE (V4L2_CTRL_CLASS_USER | 0x900)
+pub static V4L2_CID_BASE: u32 = 0x980900;
+//#define V4L2_CID_BRIGHTNESS (V4L2_CID_BASE+0)
+pub static V4L2_CID_BRIGHTNESS: u32 = 0x980900;
+//#define V4L2_CID_CONTRAST (V4L2_CID_BASE+1)
+pub static V4L2_CID_CONTRAST: u32 = 0x980901;
+//#define V4L2_CID_SATURATION (V4L2_CID_BASE+2)
+pub static V4L2_CID_SATURATION: u32 = 0x980902;
+//#define V4L2_CID_GAIN (V4L2_CID_BASE+19)
+pub static V4L2_CID_GAIN: u32 = 0x980913;
+//#define V4L2_CID_CAMERA_CLASS_BASE (V4L2_CTRL_CLASS_CAMERA | 0x900)
+pub static V4L2_CID_CAMERA_CLASS_BASE: u32 = 0x9a0900;
+//#define V4L2_CID_EXPOSURE_AUTO (V4L2_CID_CAMERA_CLASS_BASE+1)
+pub static V4L2_CID_EXPOSURE_AUTO: u32 = 0x9a0901;
+//#define V4L2_CID_EXPOSURE_ABSOLUTE (V4L2_CID_CAMERA_CLASS_BASE+2)
+pub static V4L2_CID_EXPOSURE_ABSOLUTE: u32 = 0x9a0902;
+//#define V4L2_CID_FOCUS_ABSOLUTE (V4L2_CID_CAMERA_CLASS_BASE+10)
+pub static V4L2_CID_FOCUS_ABSOLUTE: u32 = 0x9a090a;
 //#define V4L2_CID_DV_CLASS_BASE (V4L2_CTRL_CLASS_DV | 0x900)
 pub static V4L2_CID_DV_CLASS_BASE: u32 = 0xa00900;
 //#define V4L2_CID_DV_CLASS (V4L2_CTRL_CLASS_DV | 1)
@@ -809,16 +905,24 @@ pub static V4L2_EVENT_EOS: u32 = 0x2;
 pub static V4L2_EVENT_CTRL: u32 = 0x3;
 //#define V4L2_EVENT_FRAME_SYNC 4
 pub static V4L2_EVENT_FRAME_SYNC: u32 = 0x4;
+//#define V4L2_EVENT_SOURCE_CHANGE 5
+pub static V4L2_EVENT_SOURCE_CHANGE: u32 = 0x5;
+//#define V4L2_EVENT_MOTION_DET 6
+pub static V4L2_EVENT_MOTION_DET: u32 = 0x6;
 //#define V4L2_EVENT_PRIVATE_START 0x08000000
 pub static V4L2_EVENT_PRIVATE_START: u32 = 0x8000000;
 //#define V4L2_EVENT_CTRL_CH_VALUE (1 << 0)
 pub static V4L2_EVENT_CTRL_CH_VALUE: u32 = 0x1;
 //#define V4L2_EVENT_CTRL_CH_FLAGS (1 << 1)
 pub static V4L2_EVENT_CTRL_CH_FLAGS: u32 = 0x2;
+//#define V4L2_EVENT_CTRL_CH_RANGE (1 << 2)
+pub static V4L2_EVENT_CTRL_CH_RANGE: u32 = 0x4;
 //#define V4L2_EVENT_SUB_FL_SEND_INITIAL (1 << 0)
 pub static V4L2_EVENT_SUB_FL_SEND_INITIAL: u32 = 0x1;
 //#define V4L2_EVENT_SUB_FL_ALLOW_FEEDBACK (1 << 1)
 pub static V4L2_EVENT_SUB_FL_ALLOW_FEEDBACK: u32 = 0x2;
+//#define V4L2_EVENT_SRC_CH_RESOLUTION (1 << 0)
+pub static V4L2_EVENT_SRC_CH_RESOLUTION: u32 = 0x1;
 //#define V4L2_CHIP_MATCH_HOST 0 /* Match against chip ID on host (0 for the host) */
 pub static V4L2_CHIP_MATCH_HOST: u32 = 0x0;
 //#define V4L2_CHIP_MATCH_I2C_DRIVER 1 /* Match against I2C driver name */
@@ -827,172 +931,257 @@ pub static V4L2_CHIP_MATCH_I2C_DRIVER: u32 = 0x1;
 pub static V4L2_CHIP_MATCH_I2C_ADDR: u32 = 0x2;
 //#define V4L2_CHIP_MATCH_AC97 3 /* Match against anciliary AC97 chip */
 pub static V4L2_CHIP_MATCH_AC97: u32 = 0x3;
+// sizeof() of each ioctl argument, which the request numbers below encode.
+// Rust cannot evaluate size_of() in a static, so the sizes are spelled out
+// for each ABI; test_layout checks them against the structs in this file.
+// gen.sh regenerates the requests but not this table, which is kept by hand.
+// Pointers and longs, struct timeval and timespec make the 32 bit sizes
+// differ, and i386 aligns 64 bit fields to 4 bytes where ARM uses 8.
+#[allow(non_uppercase_statics)]
+pub mod size {
+    pub static v4l2_capability: u32 = 104;
+    pub static v4l2_fmtdesc: u32 = 64;
+    #[cfg(target_word_size = "64")]
+    pub static v4l2_format: u32 = 208;
+    #[cfg(target_word_size = "32")]
+    pub static v4l2_format: u32 = 204;
+    pub static v4l2_requestbuffers: u32 = 20;
+    #[cfg(target_word_size = "64")]
+    pub static v4l2_buffer: u32 = 88;
+    #[cfg(target_word_size = "32")]
+    pub static v4l2_buffer: u32 = 68;
+    #[cfg(target_word_size = "64")]
+    pub static v4l2_framebuffer: u32 = 48;
+    #[cfg(target_word_size = "32")]
+    pub static v4l2_framebuffer: u32 = 44;
+    pub static int: u32 = 4;
+    pub static v4l2_exportbuffer: u32 = 64;
+    pub static v4l2_streamparm: u32 = 204;
+    pub static v4l2_std_id: u32 = 8;
+    #[cfg(not(target_arch = "x86"))]
+    pub static v4l2_standard: u32 = 72;
+    #[cfg(target_arch = "x86")]
+    pub static v4l2_standard: u32 = 64;
+    #[cfg(not(target_arch = "x86"))]
+    pub static v4l2_input: u32 = 80;
+    #[cfg(target_arch = "x86")]
+    pub static v4l2_input: u32 = 76;
+    pub static v4l2_control: u32 = 8;
+    pub static v4l2_tuner: u32 = 84;
+    pub static v4l2_audio: u32 = 52;
+    pub static v4l2_queryctrl: u32 = 68;
+    pub static v4l2_querymenu: u32 = 44;
+    #[cfg(target_word_size = "64")]
+    pub static v4l2_edid: u32 = 40;
+    #[cfg(target_word_size = "32")]
+    pub static v4l2_edid: u32 = 36;
+    pub static v4l2_output: u32 = 72;
+    pub static v4l2_audioout: u32 = 52;
+    pub static v4l2_modulator: u32 = 68;
+    pub static v4l2_frequency: u32 = 44;
+    pub static v4l2_cropcap: u32 = 44;
+    pub static v4l2_crop: u32 = 20;
+    pub static v4l2_jpegcompression: u32 = 140;
+    pub static __u32: u32 = 4;
+    pub static v4l2_sliced_vbi_cap: u32 = 116;
+    #[cfg(target_word_size = "64")]
+    pub static v4l2_ext_controls: u32 = 32;
+    #[cfg(target_word_size = "32")]
+    pub static v4l2_ext_controls: u32 = 24;
+    pub static v4l2_frmsizeenum: u32 = 44;
+    pub static v4l2_frmivalenum: u32 = 52;
+    pub static v4l2_enc_idx: u32 = 2072;
+    pub static v4l2_encoder_cmd: u32 = 40;
+    pub static v4l2_dbg_register: u32 = 56;
+    pub static v4l2_hw_freq_seek: u32 = 48;
+    pub static v4l2_dv_timings: u32 = 132;
+    #[cfg(target_word_size = "64")]
+    pub static v4l2_event: u32 = 136;
+    #[cfg(target_arch = "arm")]
+    pub static v4l2_event: u32 = 128;
+    #[cfg(target_arch = "x86")]
+    pub static v4l2_event: u32 = 120;
+    pub static v4l2_event_subscription: u32 = 32;
+    #[cfg(target_word_size = "64")]
+    pub static v4l2_create_buffers: u32 = 256;
+    #[cfg(target_word_size = "32")]
+    pub static v4l2_create_buffers: u32 = 248;
+    pub static v4l2_selection: u32 = 64;
+    pub static v4l2_decoder_cmd: u32 = 72;
+    pub static v4l2_enum_dv_timings: u32 = 148;
+    pub static v4l2_dv_timings_cap: u32 = 144;
+    pub static v4l2_frequency_band: u32 = 64;
+    pub static v4l2_dbg_chip_info: u32 = 200;
+    pub static v4l2_query_ext_ctrl: u32 = 232;
+    pub static v4l2_remove_buffers: u32 = 64;
+}
+
 //#define VIDIOC_QUERYCAP _IOR('V', 0, struct v4l2_capability)
-pub static VIDIOC_QUERYCAP: u32 = 0x80685600;
-//#define VIDIOC_RESERVED _IO('V', 1)
-pub static VIDIOC_RESERVED: u32 = 0x5601;
+pub static VIDIOC_QUERYCAP: u32 = ior!('V', 0, size::v4l2_capability);
 //#define VIDIOC_ENUM_FMT _IOWR('V', 2, struct v4l2_fmtdesc)
-pub static VIDIOC_ENUM_FMT: u32 = 0xc0405602;
+pub static VIDIOC_ENUM_FMT: u32 = iowr!('V', 2, size::v4l2_fmtdesc);
 //#define VIDIOC_G_FMT _IOWR('V', 4, struct v4l2_format)
-pub static VIDIOC_G_FMT: u32 = 0xc0d05604;
+pub static VIDIOC_G_FMT: u32 = iowr!('V', 4, size::v4l2_format);
 //#define VIDIOC_S_FMT _IOWR('V', 5, struct v4l2_format)
-pub static VIDIOC_S_FMT: u32 = 0xc0d05605;
+pub static VIDIOC_S_FMT: u32 = iowr!('V', 5, size::v4l2_format);
 //#define VIDIOC_REQBUFS _IOWR('V', 8, struct v4l2_requestbuffers)
-pub static VIDIOC_REQBUFS: u32 = 0xc0145608;
+pub static VIDIOC_REQBUFS: u32 = iowr!('V', 8, size::v4l2_requestbuffers);
 //#define VIDIOC_QUERYBUF _IOWR('V', 9, struct v4l2_buffer)
-pub static VIDIOC_QUERYBUF: u32 = 0xc0585609;
+pub static VIDIOC_QUERYBUF: u32 = iowr!('V', 9, size::v4l2_buffer);
 //#define VIDIOC_G_FBUF _IOR('V', 10, struct v4l2_framebuffer)
-pub static VIDIOC_G_FBUF: u32 = 0x8030560a;
+pub static VIDIOC_G_FBUF: u32 = ior!('V', 10, size::v4l2_framebuffer);
 //#define VIDIOC_S_FBUF _IOW('V', 11, struct v4l2_framebuffer)
-pub static VIDIOC_S_FBUF: u32 = 0x4030560b;
+pub static VIDIOC_S_FBUF: u32 = iow!('V', 11, size::v4l2_framebuffer);
 //#define VIDIOC_OVERLAY _IOW('V', 14, int)
-pub static VIDIOC_OVERLAY: u32 = 0x4004560e;
+pub static VIDIOC_OVERLAY: u32 = iow!('V', 14, size::int);
 //#define VIDIOC_QBUF _IOWR('V', 15, struct v4l2_buffer)
-pub static VIDIOC_QBUF: u32 = 0xc058560f;
+pub static VIDIOC_QBUF: u32 = iowr!('V', 15, size::v4l2_buffer);
+//#define VIDIOC_EXPBUF _IOWR('V', 16, struct v4l2_exportbuffer)
+pub static VIDIOC_EXPBUF: u32 = iowr!('V', 16, size::v4l2_exportbuffer);
 //#define VIDIOC_DQBUF _IOWR('V', 17, struct v4l2_buffer)
-pub static VIDIOC_DQBUF: u32 = 0xc0585611;
+pub static VIDIOC_DQBUF: u32 = iowr!('V', 17, size::v4l2_buffer);
 //#define VIDIOC_STREAMON _IOW('V', 18, int)
-pub static VIDIOC_STREAMON: u32 = 0x40045612;
+pub static VIDIOC_STREAMON: u32 = iow!('V', 18, size::int);
 //#define VIDIOC_STREAMOFF _IOW('V', 19, int)
-pub static VIDIOC_STREAMOFF: u32 = 0x40045613;
+pub static VIDIOC_STREAMOFF: u32 = iow!('V', 19, size::int);
 //#define VIDIOC_G_PARM _IOWR('V', 21, struct v4l2_streamparm)
-pub static VIDIOC_G_PARM: u32 = 0xc0cc5615;
+pub static VIDIOC_G_PARM: u32 = iowr!('V', 21, size::v4l2_streamparm);
 //#define VIDIOC_S_PARM _IOWR('V', 22, struct v4l2_streamparm)
-pub static VIDIOC_S_PARM: u32 = 0xc0cc5616;
+pub static VIDIOC_S_PARM: u32 = iowr!('V', 22, size::v4l2_streamparm);
 //#define VIDIOC_G_STD _IOR('V', 23, v4l2_std_id)
-pub static VIDIOC_G_STD: u32 = 0x80085617;
+pub static VIDIOC_G_STD: u32 = ior!('V', 23, size::v4l2_std_id);
 //#define VIDIOC_S_STD _IOW('V', 24, v4l2_std_id)
-pub static VIDIOC_S_STD: u32 = 0x40085618;
+pub static VIDIOC_S_STD: u32 = iow!('V', 24, size::v4l2_std_id);
 //#define VIDIOC_ENUMSTD _IOWR('V', 25, struct v4l2_standard)
-pub static VIDIOC_ENUMSTD: u32 = 0xc0485619;
+pub static VIDIOC_ENUMSTD: u32 = iowr!('V', 25, size::v4l2_standard);
 //#define VIDIOC_ENUMINPUT _IOWR('V', 26, struct v4l2_input)
-pub static VIDIOC_ENUMINPUT: u32 = 0xc050561a;
+pub static VIDIOC_ENUMINPUT: u32 = iowr!('V', 26, size::v4l2_input);
 //#define VIDIOC_G_CTRL _IOWR('V', 27, struct v4l2_control)
-pub static VIDIOC_G_CTRL: u32 = 0xc008561b;
+pub static VIDIOC_G_CTRL: u32 = iowr!('V', 27, size::v4l2_control);
 //#define VIDIOC_S_CTRL _IOWR('V', 28, struct v4l2_control)
-pub static VIDIOC_S_CTRL: u32 = 0xc008561c;
+pub static VIDIOC_S_CTRL: u32 = iowr!('V', 28, size::v4l2_control);
 //#define VIDIOC_G_TUNER _IOWR('V', 29, struct v4l2_tuner)
-pub static VIDIOC_G_TUNER: u32 = 0xc054561d;
+pub static VIDIOC_G_TUNER: u32 = iowr!('V', 29, size::v4l2_tuner);
 //#define VIDIOC_S_TUNER _IOW('V', 30, struct v4l2_tuner)
-pub static VIDIOC_S_TUNER: u32 = 0x4054561e;
+pub static VIDIOC_S_TUNER: u32 = iow!('V', 30, size::v4l2_tuner);
 //#define VIDIOC_G_AUDIO _IOR('V', 33, struct v4l2_audio)
-pub static VIDIOC_G_AUDIO: u32 = 0x80345621;
+pub static VIDIOC_G_AUDIO: u32 = ior!('V', 33, size::v4l2_audio);
 //#define VIDIOC_S_AUDIO _IOW('V', 34, struct v4l2_audio)
-pub static VIDIOC_S_AUDIO: u32 = 0x40345622;
+pub static VIDIOC_S_AUDIO: u32 = iow!('V', 34, size::v4l2_audio);
 //#define VIDIOC_QUERYCTRL _IOWR('V', 36, struct v4l2_queryctrl)
-pub static VIDIOC_QUERYCTRL: u32 = 0xc0445624;
+pub static VIDIOC_QUERYCTRL: u32 = iowr!('V', 36, size::v4l2_queryctrl);
 //#define VIDIOC_QUERYMENU _IOWR('V', 37, struct v4l2_querymenu)
-pub static VIDIOC_QUERYMENU: u32 = 0xc02c5625;
+pub static VIDIOC_QUERYMENU: u32 = iowr!('V', 37, size::v4l2_querymenu);
 //#define VIDIOC_G_INPUT _IOR('V', 38, int)
-pub static VIDIOC_G_INPUT: u32 = 0x80045626;
+pub static VIDIOC_G_INPUT: u32 = ior!('V', 38, size::int);
 //#define VIDIOC_S_INPUT _IOWR('V', 39, int)
-pub static VIDIOC_S_INPUT: u32 = 0xc0045627;
+pub static VIDIOC_S_INPUT: u32 = iowr!('V', 39, size::int);
+//#define VIDIOC_G_EDID _IOWR('V', 40, struct v4l2_edid)
+pub static VIDIOC_G_EDID: u32 = iowr!('V', 40, size::v4l2_edid);
+//#define VIDIOC_S_EDID _IOWR('V', 41, struct v4l2_edid)
+pub static VIDIOC_S_EDID: u32 = iowr!('V', 41, size::v4l2_edid);
 //#define VIDIOC_G_OUTPUT _IOR('V', 46, int)
-pub static VIDIOC_G_OUTPUT: u32 = 0x8004562e;
+pub static VIDIOC_G_OUTPUT: u32 = ior!('V', 46, size::int);
 //#define VIDIOC_S_OUTPUT _IOWR('V', 47, int)
-pub static VIDIOC_S_OUTPUT: u32 = 0xc004562f;
+pub static VIDIOC_S_OUTPUT: u32 = iowr!('V', 47, size::int);
 //#define VIDIOC_ENUMOUTPUT _IOWR('V', 48, struct v4l2_output)
-pub static VIDIOC_ENUMOUTPUT: u32 = 0xc0485630;
+pub static VIDIOC_ENUMOUTPUT: u32 = iowr!('V', 48, size::v4l2_output);
 //#define VIDIOC_G_AUDOUT _IOR('V', 49, struct v4l2_audioout)
-pub static VIDIOC_G_AUDOUT: u32 = 0x80345631;
+pub static VIDIOC_G_AUDOUT: u32 = ior!('V', 49, size::v4l2_audioout);
 //#define VIDIOC_S_AUDOUT _IOW('V', 50, struct v4l2_audioout)
-pub static VIDIOC_S_AUDOUT: u32 = 0x40345632;
+pub static VIDIOC_S_AUDOUT: u32 = iow!('V', 50, size::v4l2_audioout);
 //#define VIDIOC_G_MODULATOR _IOWR('V', 54, struct v4l2_modulator)
-pub static VIDIOC_G_MODULATOR: u32 = 0xc0445636;
+pub static VIDIOC_G_MODULATOR: u32 = iowr!('V', 54, size::v4l2_modulator);
 //#define VIDIOC_S_MODULATOR _IOW('V', 55, struct v4l2_modulator)
-pub static VIDIOC_S_MODULATOR: u32 = 0x40445637;
+pub static VIDIOC_S_MODULATOR: u32 = iow!('V', 55, size::v4l2_modulator);
 //#define VIDIOC_G_FREQUENCY _IOWR('V', 56, struct v4l2_frequency)
-pub static VIDIOC_G_FREQUENCY: u32 = 0xc02c5638;
+pub static VIDIOC_G_FREQUENCY: u32 = iowr!('V', 56, size::v4l2_frequency);
 //#define VIDIOC_S_FREQUENCY _IOW('V', 57, struct v4l2_frequency)
-pub static VIDIOC_S_FREQUENCY: u32 = 0x402c5639;
+pub static VIDIOC_S_FREQUENCY: u32 = iow!('V', 57, size::v4l2_frequency);
 //#define VIDIOC_CROPCAP _IOWR('V', 58, struct v4l2_cropcap)
-pub static VIDIOC_CROPCAP: u32 = 0xc02c563a;
+pub static VIDIOC_CROPCAP: u32 = iowr!('V', 58, size::v4l2_cropcap);
 //#define VIDIOC_G_CROP _IOWR('V', 59, struct v4l2_crop)
-pub static VIDIOC_G_CROP: u32 = 0xc014563b;
+pub static VIDIOC_G_CROP: u32 = iowr!('V', 59, size::v4l2_crop);
 //#define VIDIOC_S_CROP _IOW('V', 60, struct v4l2_crop)
-pub static VIDIOC_S_CROP: u32 = 0x4014563c;
+pub static VIDIOC_S_CROP: u32 = iow!('V', 60, size::v4l2_crop);
 //#define VIDIOC_G_JPEGCOMP _IOR('V', 61, struct v4l2_jpegcompression)
-pub static VIDIOC_G_JPEGCOMP: u32 = 0x808c563d;
+pub static VIDIOC_G_JPEGCOMP: u32 = ior!('V', 61, size::v4l2_jpegcompression);
 //#define VIDIOC_S_JPEGCOMP _IOW('V', 62, struct v4l2_jpegcompression)
-pub static VIDIOC_S_JPEGCOMP: u32 = 0x408c563e;
+pub static VIDIOC_S_JPEGCOMP: u32 = iow!('V', 62, size::v4l2_jpegcompression);
 //#define VIDIOC_QUERYSTD _IOR('V', 63, v4l2_std_id)
-pub static VIDIOC_QUERYSTD: u32 = 0x8008563f;
+pub static VIDIOC_QUERYSTD: u32 = ior!('V', 63, size::v4l2_std_id);
 //#define VIDIOC_TRY_FMT _IOWR('V', 64, struct v4l2_format)
-pub static VIDIOC_TRY_FMT: u32 = 0xc0d05640;
+pub static VIDIOC_TRY_FMT: u32 = iowr!('V', 64, size::v4l2_format);
 //#define VIDIOC_ENUMAUDIO _IOWR('V', 65, struct v4l2_audio)
-pub static VIDIOC_ENUMAUDIO: u32 = 0xc0345641;
+pub static VIDIOC_ENUMAUDIO: u32 = iowr!('V', 65, size::v4l2_audio);
 //#define VIDIOC_ENUMAUDOUT _IOWR('V', 66, struct v4l2_audioout)
-pub static VIDIOC_ENUMAUDOUT: u32 = 0xc0345642;
+pub static VIDIOC_ENUMAUDOUT: u32 = iowr!('V', 66, size::v4l2_audioout);
 //#define VIDIOC_G_PRIORITY _IOR('V', 67, __u32) /* enum v4l2_priority */
-pub static VIDIOC_G_PRIORITY: u32 = 0x80045643;
+pub static VIDIOC_G_PRIORITY: u32 = ior!('V', 67, size::__u32);
 //#define VIDIOC_S_PRIORITY _IOW('V', 68, __u32) /* enum v4l2_priority */
-pub static VIDIOC_S_PRIORITY: u32 = 0x40045644;
+pub static VIDIOC_S_PRIORITY: u32 = iow!('V', 68, size::__u32);
 //#define VIDIOC_G_SLICED_VBI_CAP _IOWR('V', 69, struct v4l2_sliced_vbi_cap)
-pub static VIDIOC_G_SLICED_VBI_CAP: u32 = 0xc0745645;
+pub static VIDIOC_G_SLICED_VBI_CAP: u32 = iowr!('V', 69, size::v4l2_sliced_vbi_cap);
 //#define VIDIOC_LOG_STATUS _IO('V', 70)
-pub static VIDIOC_LOG_STATUS: u32 = 0x5646;
+pub static VIDIOC_LOG_STATUS: u32 = io!('V', 70);
 //#define VIDIOC_G_EXT_CTRLS _IOWR('V', 71, struct v4l2_ext_controls)
-pub static VIDIOC_G_EXT_CTRLS: u32 = 0xc0205647;
+pub static VIDIOC_G_EXT_CTRLS: u32 = iowr!('V', 71, size::v4l2_ext_controls);
 //#define VIDIOC_S_EXT_CTRLS _IOWR('V', 72, struct v4l2_ext_controls)
-pub static VIDIOC_S_EXT_CTRLS: u32 = 0xc0205648;
+pub static VIDIOC_S_EXT_CTRLS: u32 = iowr!('V', 72, size::v4l2_ext_controls);
 //#define VIDIOC_TRY_EXT_CTRLS _IOWR('V', 73, struct v4l2_ext_controls)
-pub static VIDIOC_TRY_EXT_CTRLS: u32 = 0xc0205649;
+pub static VIDIOC_TRY_EXT_CTRLS: u32 = iowr!('V', 73, size::v4l2_ext_controls);
 //#define VIDIOC_ENUM_FRAMESIZES _IOWR('V', 74, struct v4l2_frmsizeenum)
-pub static VIDIOC_ENUM_FRAMESIZES: u32 = 0xc02c564a;
+pub static VIDIOC_ENUM_FRAMESIZES: u32 = iowr!('V', 74, size::v4l2_frmsizeenum);
 //#define VIDIOC_ENUM_FRAMEINTERVALS _IOWR('V', 75, struct v4l2_frmivalenum)
-pub static VIDIOC_ENUM_FRAMEINTERVALS: u32 = 0xc034564b;
+pub static VIDIOC_ENUM_FRAMEINTERVALS: u32 = iowr!('V', 75, size::v4l2_frmivalenum);
 //#define VIDIOC_G_ENC_INDEX _IOR('V', 76, struct v4l2_enc_idx)
-pub static VIDIOC_G_ENC_INDEX: u32 = 0x8818564c;
+pub static VIDIOC_G_ENC_INDEX: u32 = ior!('V', 76, size::v4l2_enc_idx);
 //#define VIDIOC_ENCODER_CMD _IOWR('V', 77, struct v4l2_encoder_cmd)
-pub static VIDIOC_ENCODER_CMD: u32 = 0xc028564d;
+pub static VIDIOC_ENCODER_CMD: u32 = iowr!('V', 77, size::v4l2_encoder_cmd);
 //#define VIDIOC_TRY_ENCODER_CMD _IOWR('V', 78, struct v4l2_encoder_cmd)
-pub static VIDIOC_TRY_ENCODER_CMD: u32 = 0xc028564e;
+pub static VIDIOC_TRY_ENCODER_CMD: u32 = iowr!('V', 78, size::v4l2_encoder_cmd);
 //#define VIDIOC_DBG_S_REGISTER _IOW('V', 79, struct v4l2_dbg_register)
-pub static VIDIOC_DBG_S_REGISTER: u32 = 0x4038564f;
+pub static VIDIOC_DBG_S_REGISTER: u32 = iow!('V', 79, size::v4l2_dbg_register);
 //#define VIDIOC_DBG_G_REGISTER _IOWR('V', 80, struct v4l2_dbg_register)
-pub static VIDIOC_DBG_G_REGISTER: u32 = 0xc0385650;
-//#define VIDIOC_DBG_G_CHIP_IDENT _IOWR('V', 81, struct v4l2_dbg_chip_ident)
-pub static VIDIOC_DBG_G_CHIP_IDENT: u32 = 0xc02c5651;
+pub static VIDIOC_DBG_G_REGISTER: u32 = iowr!('V', 80, size::v4l2_dbg_register);
 //#define VIDIOC_S_HW_FREQ_SEEK _IOW('V', 82, struct v4l2_hw_freq_seek)
-pub static VIDIOC_S_HW_FREQ_SEEK: u32 = 0x40305652;
-//#define VIDIOC_ENUM_DV_PRESETS _IOWR('V', 83, struct v4l2_dv_enum_preset)
-pub static VIDIOC_ENUM_DV_PRESETS: u32 = 0xc0405653;
-//#define VIDIOC_S_DV_PRESET _IOWR('V', 84, struct v4l2_dv_preset)
-pub static VIDIOC_S_DV_PRESET: u32 = 0xc0145654;
-//#define VIDIOC_G_DV_PRESET _IOWR('V', 85, struct v4l2_dv_preset)
-pub static VIDIOC_G_DV_PRESET: u32 = 0xc0145655;
-//#define VIDIOC_QUERY_DV_PRESET _IOR('V', 86, struct v4l2_dv_preset)
-pub static VIDIOC_QUERY_DV_PRESET: u32 = 0x80145656;
+pub static VIDIOC_S_HW_FREQ_SEEK: u32 = iow!('V', 82, size::v4l2_hw_freq_seek);
 //#define VIDIOC_S_DV_TIMINGS _IOWR('V', 87, struct v4l2_dv_timings)
-pub static VIDIOC_S_DV_TIMINGS: u32 = 0xc0845657;
+pub static VIDIOC_S_DV_TIMINGS: u32 = iowr!('V', 87, size::v4l2_dv_timings);
 //#define VIDIOC_G_DV_TIMINGS _IOWR('V', 88, struct v4l2_dv_timings)
-pub static VIDIOC_G_DV_TIMINGS: u32 = 0xc0845658;
+pub static VIDIOC_G_DV_TIMINGS: u32 = iowr!('V', 88, size::v4l2_dv_timings);
 //#define VIDIOC_DQEVENT _IOR('V', 89, struct v4l2_event)
-pub static VIDIOC_DQEVENT: u32 = 0x80885659;
+pub static VIDIOC_DQEVENT: u32 = ior!('V', 89, size::v4l2_event);
 //#define VIDIOC_SUBSCRIBE_EVENT _IOW('V', 90, struct v4l2_event_subscription)
-pub static VIDIOC_SUBSCRIBE_EVENT: u32 = 0x4020565a;
+pub static VIDIOC_SUBSCRIBE_EVENT: u32 = iow!('V', 90, size::v4l2_event_subscription);
 //#define VIDIOC_UNSUBSCRIBE_EVENT _IOW('V', 91, struct v4l2_event_subscription)
-pub static VIDIOC_UNSUBSCRIBE_EVENT: u32 = 0x4020565b;
+pub static VIDIOC_UNSUBSCRIBE_EVENT: u32 = iow!('V', 91, size::v4l2_event_subscription);
 //#define VIDIOC_CREATE_BUFS _IOWR('V', 92, struct v4l2_create_buffers)
-pub static VIDIOC_CREATE_BUFS: u32 = 0xc100565c;
+pub static VIDIOC_CREATE_BUFS: u32 = iowr!('V', 92, size::v4l2_create_buffers);
 //#define VIDIOC_PREPARE_BUF _IOWR('V', 93, struct v4l2_buffer)
-pub static VIDIOC_PREPARE_BUF: u32 = 0xc058565d;
+pub static VIDIOC_PREPARE_BUF: u32 = iowr!('V', 93, size::v4l2_buffer);
 //#define VIDIOC_G_SELECTION _IOWR('V', 94, struct v4l2_selection)
-pub static VIDIOC_G_SELECTION: u32 = 0xc040565e;
+pub static VIDIOC_G_SELECTION: u32 = iowr!('V', 94, size::v4l2_selection);
 //#define VIDIOC_S_SELECTION _IOWR('V', 95, struct v4l2_selection)
-pub static VIDIOC_S_SELECTION: u32 = 0xc040565f;
+pub static VIDIOC_S_SELECTION: u32 = iowr!('V', 95, size::v4l2_selection);
 //#define VIDIOC_DECODER_CMD _IOWR('V', 96, struct v4l2_decoder_cmd)
-pub static VIDIOC_DECODER_CMD: u32 = 0xc0485660;
+pub static VIDIOC_DECODER_CMD: u32 = iowr!('V', 96, size::v4l2_decoder_cmd);
 //#define VIDIOC_TRY_DECODER_CMD _IOWR('V', 97, struct v4l2_decoder_cmd)
-pub static VIDIOC_TRY_DECODER_CMD: u32 = 0xc0485661;
+pub static VIDIOC_TRY_DECODER_CMD: u32 = iowr!('V', 97, size::v4l2_decoder_cmd);
 //#define VIDIOC_ENUM_DV_TIMINGS _IOWR('V', 98, struct v4l2_enum_dv_timings)
-pub static VIDIOC_ENUM_DV_TIMINGS: u32 = 0xc0945662;
+pub static VIDIOC_ENUM_DV_TIMINGS: u32 = iowr!('V', 98, size::v4l2_enum_dv_timings);
 //#define VIDIOC_QUERY_DV_TIMINGS _IOR('V', 99, struct v4l2_dv_timings)
-pub static VIDIOC_QUERY_DV_TIMINGS: u32 = 0x80845663;
+pub static VIDIOC_QUERY_DV_TIMINGS: u32 = ior!('V', 99, size::v4l2_dv_timings);
 //#define VIDIOC_DV_TIMINGS_CAP _IOWR('V', 100, struct v4l2_dv_timings_cap)
-pub static VIDIOC_DV_TIMINGS_CAP: u32 = 0xc0905664;
+pub static VIDIOC_DV_TIMINGS_CAP: u32 = iowr!('V', 100, size::v4l2_dv_timings_cap);
 //#define VIDIOC_ENUM_FREQ_BANDS _IOWR('V', 101, struct v4l2_frequency_band)
-pub static VIDIOC_ENUM_FREQ_BANDS: u32 = 0xc0405665;
+pub static VIDIOC_ENUM_FREQ_BANDS: u32 = iowr!('V', 101, size::v4l2_frequency_band);
+//#define VIDIOC_DBG_G_CHIP_INFO _IOWR('V', 102, struct v4l2_dbg_chip_info)
+pub static VIDIOC_DBG_G_CHIP_INFO: u32 = iowr!('V', 102, size::v4l2_dbg_chip_info);
+//#define VIDIOC_QUERY_EXT_CTRL _IOWR('V', 103, struct v4l2_query_ext_ctrl)
+pub static VIDIOC_QUERY_EXT_CTRL: u32 = iowr!('V', 103, size::v4l2_query_ext_ctrl);
+//#define VIDIOC_REMOVE_BUFS _IOWR('V', 104, struct v4l2_remove_buffers)
+pub static VIDIOC_REMOVE_BUFS: u32 = iowr!('V', 104, size::v4l2_remove_buffers);
 //#define BASE_VIDIOC_PRIVATE 192 /* 192-255 are private */
 pub static BASE_VIDIOC_PRIVATE: u32 = 0xc0;
 
@@ -1018,7 +1207,10 @@ pub static V4L2_BUF_TYPE_SLICED_VBI_OUTPUT: c_uint = 7;
 pub static V4L2_BUF_TYPE_VIDEO_OUTPUT_OVERLAY: c_uint = 8;
 pub static V4L2_BUF_TYPE_VIDEO_CAPTURE_MPLANE: c_uint = 9;
 pub static V4L2_BUF_TYPE_VIDEO_OUTPUT_MPLANE: c_uint = 10;
-pub static V4L2_BUF_TYPE_PRIVATE: c_uint = 128;
+pub static V4L2_BUF_TYPE_SDR_CAPTURE: c_uint = 11;
+pub static V4L2_BUF_TYPE_SDR_OUTPUT: c_uint = 12;
+pub static V4L2_BUF_TYPE_META_CAPTURE: c_uint = 13;
+pub static V4L2_BUF_TYPE_META_OUTPUT: c_uint = 14;
 pub type v4l2_tuner_type = c_uint;
 pub static V4L2_TUNER_RADIO: c_uint = 1;
 pub static V4L2_TUNER_ANALOG_TV: c_uint = 2;
@@ -1027,7 +1219,9 @@ pub type v4l2_memory = c_uint;
 pub static V4L2_MEMORY_MMAP: c_uint = 1;
 pub static V4L2_MEMORY_USERPTR: c_uint = 2;
 pub static V4L2_MEMORY_OVERLAY: c_uint = 3;
+pub static V4L2_MEMORY_DMABUF: c_uint = 4;
 pub type v4l2_colorspace = c_uint;
+pub static V4L2_COLORSPACE_DEFAULT: c_uint = 0;
 pub static V4L2_COLORSPACE_SMPTE170M: c_uint = 1;
 pub static V4L2_COLORSPACE_SMPTE240M: c_uint = 2;
 pub static V4L2_COLORSPACE_REC709: c_uint = 3;
@@ -1036,6 +1230,10 @@ pub static V4L2_COLORSPACE_470_SYSTEM_M: c_uint = 5;
 pub static V4L2_COLORSPACE_470_SYSTEM_BG: c_uint = 6;
 pub static V4L2_COLORSPACE_JPEG: c_uint = 7;
 pub static V4L2_COLORSPACE_SRGB: c_uint = 8;
+pub static V4L2_COLORSPACE_OPRGB: c_uint = 9;
+pub static V4L2_COLORSPACE_BT2020: c_uint = 10;
+pub static V4L2_COLORSPACE_RAW: c_uint = 11;
+pub static V4L2_COLORSPACE_DCI_P3: c_uint = 12;
 pub type v4l2_priority = c_uint;
 pub static V4L2_PRIORITY_UNSET: c_uint = 0;
 pub static V4L2_PRIORITY_BACKGROUND: c_uint = 1;
@@ -1086,6 +1284,10 @@ pub struct v4l2_pix_format {
     pub sizeimage: u32,
     pub colorspace: u32,
     pub _priv: u32,
+    pub flags: u32,
+    pub ycbcr_enc: u32,
+    pub quantization: u32,
+    pub xfer_func: u32,
 }
 pub struct v4l2_fmtdesc {
     pub index: u32,
@@ -1093,7 +1295,8 @@ pub struct v4l2_fmtdesc {
     pub flags: u32,
     pub description: [u8, ..32u],
     pub pixelformat: u32,
-    pub reserved: [u32, ..4u],
+    pub mbus_code: u32,
+    pub reserved: [u32, ..3u],
 } // TODO: implement Default
 pub type v4l2_frmsizetypes = c_uint;
 pub static V4L2_FRMSIZE_TYPE_DISCRETE: c_uint = 1;
@@ -1213,7 +1416,9 @@ pub struct v4l2_requestbuffers {
     pub count: u32,
     pub _type: u32,
     pub memory: u32,
-    pub reserved: [u32, ..2u],
+    pub capabilities: u32,
+    pub flags: u8,
+    pub reserved: [u8, ..3u],
 }
 impl Default for v4l2_requestbuffers {
     fn default() -> v4l2_requestbuffers {
@@ -1221,7 +1426,9 @@ impl Default for v4l2_requestbuffers {
             count: Default::default(),
             _type: Default::default(),
             memory: Default::default(),
-            reserved: [Default::default(), ..2u],
+            capabilities: Default::default(),
+            flags: Default::default(),
+            reserved: [Default::default(), ..3u],
         }
     }
 }
@@ -1232,8 +1439,19 @@ pub struct v4l2_plane {
     pub data_offset: u32,
     pub reserved: [u32, ..11u],
 }
+impl Default for v4l2_plane {
+    fn default() -> v4l2_plane {
+        v4l2_plane {
+            bytesused: Default::default(),
+            length: Default::default(),
+            m: Default::default(),
+            data_offset: Default::default(),
+            reserved: [Default::default(), ..11u],
+        }
+    }
+}
 pub struct union3 {
-    pub data: [u64, ..1u],
+    pub data: [c_ulong, ..1u],
 }
 impl union3 {
     pub fn mem_offset(&mut self) -> *mut u32 {
@@ -1242,6 +1460,16 @@ impl union3 {
     pub fn userptr(&mut self) -> *mut c_ulong {
         unsafe { ::std::mem::transmute(self) }
     }
+    pub fn fd(&mut self) -> *mut i32 {
+        unsafe { ::std::mem::transmute(self) }
+    }
+}
+impl Default for union3 {
+    fn default() -> union3 {
+        union3 {
+            data: [Default::default(), ..1u],
+        }
+    }
 }
 pub struct v4l2_buffer {
     pub index: u32,
@@ -1256,6 +1484,7 @@ pub struct v4l2_buffer {
     pub m: union4,
     pub length: u32,
     pub reserved2: u32,
+    // Or request_fd, with V4L2_BUF_FLAG_REQUEST_FD.
     pub reserved: u32,
 }
 impl Default for v4l2_buffer {
@@ -1281,7 +1510,7 @@ impl Default for v4l2_buffer {
     }
 }
 pub struct union4 {
-    pub data: [u64, ..1u],
+    pub data: [c_ulong, ..1u],
 }
 impl union4 {
     pub fn offset(&mut self) -> *mut u32 {
@@ -1293,6 +1522,9 @@ impl union4 {
     pub fn planes(&mut self) -> *mut *mut v4l2_plane {
         unsafe { ::std::mem::transmute(self) }
     }
+    pub fn fd(&mut self) -> *mut i32 {
+        unsafe { ::std::mem::transmute(self) }
+    }
 }
 impl Default for union4 {
     fn default() -> union4 {
@@ -1301,11 +1533,42 @@ impl Default for union4 {
         }
     }
 }
+pub struct v4l2_exportbuffer {
+    pub _type: u32,
+    pub index: u32,
+    pub plane: u32,
+    pub flags: u32,
+    pub fd: i32,
+    pub reserved: [u32, ..11u],
+}
+impl Default for v4l2_exportbuffer {
+    fn default() -> v4l2_exportbuffer {
+        v4l2_exportbuffer {
+            _type: Default::default(),
+            index: Default::default(),
+            plane: Default::default(),
+            flags: Default::default(),
+            fd: Default::default(),
+            reserved: [Default::default(), ..11u],
+        }
+    }
+}
 pub struct v4l2_framebuffer {
     pub capability: u32,
     pub flags: u32,
     pub base: *mut c_void,
-    pub fmt: v4l2_pix_format,
+    pub fmt: v4l2_framebuffer_fmt,
+}
+// The v4l2_pix_format of old, without the extended fields.
+pub struct v4l2_framebuffer_fmt {
+    pub width: u32,
+    pub height: u32,
+    pub pixelformat: u32,
+    pub field: u32,
+    pub bytesperline: u32,
+    pub sizeimage: u32,
+    pub colorspace: u32,
+    pub _priv: u32,
 }
 pub struct v4l2_clip {
     pub c: v4l2_rect,
@@ -1355,6 +1618,17 @@ pub struct v4l2_selection {
     pub r: v4l2_rect,
     pub reserved: [u32, ..9u],
 }
+impl Default for v4l2_selection {
+    fn default() -> v4l2_selection {
+        v4l2_selection {
+            _type: Default::default(),
+            target: Default::default(),
+            flags: Default::default(),
+            r: Default::default(),
+            reserved: [Default::default(), ..9u],
+        }
+    }
+}
 pub type v4l2_std_id = u64;
 pub struct v4l2_standard {
     pub index: u32,
@@ -1364,18 +1638,19 @@ pub struct v4l2_standard {
     pub framelines: u32,
     pub reserved: [u32, ..4u],
 }
-pub struct v4l2_dv_preset {
-    pub preset: u32,
-    pub reserved: [u32, ..4u],
-}
-pub struct v4l2_dv_enum_preset {
-    pub index: u32,
-    pub preset: u32,
-    pub name: [u8, ..32u],
-    pub width: u32,
-    pub height: u32,
-    pub reserved: [u32, ..4u],
+impl Default for v4l2_standard {
+    fn default() -> v4l2_standard {
+        v4l2_standard {
+            index: Default::default(),
+            id: Default::default(),
+            name: [Default::default(), ..24u],
+            frameperiod: Default::default(),
+            framelines: Default::default(),
+            reserved: [Default::default(), ..4u],
+        }
+    }
 }
+#[packed]
 pub struct v4l2_bt_timings {
     pub width: u32,
     pub height: u32,
@@ -1393,8 +1668,12 @@ pub struct v4l2_bt_timings {
     pub il_vbackporch: u32,
     pub standards: u32,
     pub flags: u32,
-    pub reserved: [u32, ..14u],
+    pub picture_aspect: v4l2_fract,
+    pub cea861_vic: u8,
+    pub hdmi_vic: u8,
+    pub reserved: [u8, ..46u],
 }
+#[packed]
 pub struct v4l2_dv_timings {
     pub _type: u32,
     _union: [u32, ..32u],
@@ -1417,9 +1696,21 @@ impl Default for v4l2_dv_timings {
 }
 pub struct v4l2_enum_dv_timings {
     pub index: u32,
-    pub reserved: [u32, ..3u],
+    pub pad: u32,
+    pub reserved: [u32, ..2u],
     pub timings: v4l2_dv_timings,
 }
+impl Default for v4l2_enum_dv_timings {
+    fn default() -> v4l2_enum_dv_timings {
+        v4l2_enum_dv_timings {
+            index: Default::default(),
+            pad: Default::default(),
+            reserved: [Default::default(), ..2u],
+            timings: Default::default(),
+        }
+    }
+}
+#[packed]
 pub struct v4l2_bt_timings_cap {
     pub min_width: u32,
     pub max_width: u32,
@@ -1433,7 +1724,8 @@ pub struct v4l2_bt_timings_cap {
 }
 pub struct v4l2_dv_timings_cap {
     pub _type: u32,
-    pub reserved: [u32, ..3u],
+    pub pad: u32,
+    pub reserved: [u32, ..2u],
     _union: [u32, ..32u],
 }
 impl v4l2_dv_timings_cap {
@@ -1444,6 +1736,16 @@ impl v4l2_dv_timings_cap {
         unsafe { ::std::mem::transmute(&mut self._union) }
     }
 }
+impl Default for v4l2_dv_timings_cap {
+    fn default() -> v4l2_dv_timings_cap {
+        v4l2_dv_timings_cap {
+            _type: Default::default(),
+            pad: Default::default(),
+            reserved: [Default::default(), ..2u],
+            _union: [Default::default(), ..32u],
+        }
+    }
+}
 pub struct v4l2_input {
     pub index: u32,
     pub name: [u8, ..32u],
@@ -1455,6 +1757,21 @@ pub struct v4l2_input {
     pub capabilities: u32,
     pub reserved: [u32, ..3u],
 }
+impl Default for v4l2_input {
+    fn default() -> v4l2_input {
+        v4l2_input {
+            index: Default::default(),
+            name: [Default::default(), ..32u],
+            _type: Default::default(),
+            audioset: Default::default(),
+            tuner: Default::default(),
+            std: Default::default(),
+            status: Default::default(),
+            capabilities: Default::default(),
+            reserved: [Default::default(), ..3u],
+        }
+    }
+}
 pub struct v4l2_output {
     pub index: u32,
     pub name: [u8, ..32u],
@@ -1469,6 +1786,15 @@ pub struct v4l2_control {
     pub id: u32,
     pub value: i32,
 }
+impl Default for v4l2_control {
+    fn default() -> v4l2_control {
+        v4l2_control {
+            id: Default::default(),
+            value: Default::default(),
+        }
+    }
+}
+#[packed]
 pub struct v4l2_ext_control {
     pub id: u32,
     pub size: u32,
@@ -1487,10 +1813,12 @@ impl v4l2_ext_control {
     }
 }
 pub struct v4l2_ext_controls {
+    // Or which, V4L2_CTRL_WHICH_*.
     pub ctrl_class: u32,
     pub count: u32,
     pub error_idx: u32,
-    pub reserved: [u32, ..2u],
+    pub request_fd: i32,
+    pub reserved: [u32, ..1u],
     pub controls: *mut v4l2_ext_control,
 }
 pub type v4l2_ctrl_type = c_uint;
@@ -1514,10 +1842,41 @@ pub struct v4l2_queryctrl {
     pub flags: u32,
     pub reserved: [u32, ..2u],
 }
+pub struct v4l2_query_ext_ctrl {
+    pub id: u32,
+    pub _type: u32,
+    pub name: [u8, ..32u],
+    pub minimum: i64,
+    pub maximum: i64,
+    pub step: u64,
+    pub default_value: i64,
+    pub flags: u32,
+    pub elem_size: u32,
+    pub elems: u32,
+    pub nr_of_dims: u32,
+    pub dims: [u32, ..4u],
+    pub reserved: [u32, ..32u],
+}
+impl Default for v4l2_queryctrl {
+    fn default() -> v4l2_queryctrl {
+        v4l2_queryctrl {
+            id: Default::default(),
+            _type: Default::default(),
+            name: [Default::default(), ..32u],
+            minimum: Default::default(),
+            maximum: Default::default(),
+            step: Default::default(),
+            default_value: Default::default(),
+            flags: Default::default(),
+            reserved: [Default::default(), ..2u],
+        }
+    }
+}
+#[packed]
 pub struct v4l2_querymenu {
     pub id: u32,
     pub index: u32,
-    _union: [u64, ..4u],
+    _union: [u8, ..32u],
     pub reserved: u32,
 }
 impl v4l2_querymenu {
@@ -1548,6 +1907,23 @@ pub struct v4l2_tuner {
     pub afc: i32,
     pub reserved: [u32, ..4u],
 }
+impl Default for v4l2_tuner {
+    fn default() -> v4l2_tuner {
+        v4l2_tuner {
+            index: Default::default(),
+            name: [Default::default(), ..32u],
+            _type: Default::default(),
+            capability: Default::default(),
+            rangelow: Default::default(),
+            rangehigh: Default::default(),
+            rxsubchans: Default::default(),
+            audmode: Default::default(),
+            signal: Default::default(),
+            afc: Default::default(),
+            reserved: [Default::default(), ..4u],
+        }
+    }
+}
 pub struct v4l2_modulator {
     pub index: u32,
     pub name: [u8, ..32u],
@@ -1563,6 +1939,16 @@ pub struct v4l2_frequency {
     pub frequency: u32,
     pub reserved: [u32, ..8u],
 }
+impl Default for v4l2_frequency {
+    fn default() -> v4l2_frequency {
+        v4l2_frequency {
+            tuner: Default::default(),
+            _type: Default::default(),
+            frequency: Default::default(),
+            reserved: [Default::default(), ..8u],
+        }
+    }
+}
 pub struct v4l2_frequency_band {
     pub tuner: u32,
     pub _type: u32,
@@ -1583,6 +1969,7 @@ pub struct v4l2_hw_freq_seek {
     pub rangehigh: u32,
     pub reserved: [u32, ..5u],
 }
+#[packed]
 pub struct v4l2_rds_data {
     pub lsb: u8,
     pub msb: u8,
@@ -1683,17 +2070,21 @@ pub struct v4l2_sliced_vbi_data {
     pub reserved: u32,
     pub data: [u8, ..48u],
 }
+#[packed]
 pub struct v4l2_mpeg_vbi_itv0_line {
     pub id: u8,
     pub data: [u8, ..42u],
 }
+#[packed]
 pub struct v4l2_mpeg_vbi_itv0 {
     pub linemask: [u32, ..2u],
     pub line: [v4l2_mpeg_vbi_itv0_line, ..35u],
 }
+#[packed]
 pub struct v4l2_mpeg_vbi_ITV0 {
     pub line: [v4l2_mpeg_vbi_itv0_line, ..36u],
 }
+#[packed]
 pub struct v4l2_mpeg_vbi_fmt_ivtv {
     pub magic: [u8, ..4u],
     _union: [u8, ..1548u],
@@ -1708,8 +2099,8 @@ impl v4l2_mpeg_vbi_fmt_ivtv {
 }
 pub struct v4l2_plane_pix_format {
     pub sizeimage: u32,
-    pub bytesperline: u16,
-    pub reserved: [u16, ..7u],
+    pub bytesperline: u32,
+    pub reserved: [u16, ..6u],
 }
 pub struct v4l2_pix_format_mplane {
     pub width: u32,
@@ -1719,15 +2110,25 @@ pub struct v4l2_pix_format_mplane {
     pub colorspace: u32,
     pub plane_fmt: [v4l2_plane_pix_format, ..8u],
     pub num_planes: u8,
-    pub reserved: [u8, ..11u],
+    pub flags: u8,
+    pub ycbcr_enc: u8,
+    pub quantization: u8,
+    pub xfer_func: u8,
+    pub reserved: [u8, ..7u],
+}
+pub struct v4l2_meta_format {
+    pub dataformat: u32,
+    pub buffersize: u32,
 }
 #[deriving(Default)]
 pub struct v4l2_format {
     pub _type: u32,
     pub fmt: union16,
 }
+// 200 bytes, aligned like the pointers in v4l2_window.
 pub struct union16 {
-    pub data: [u64, ..25u],
+    pub data: [u32, ..50u],
+    _align: [c_ulong, ..0u],
 }
 impl union16 {
     pub fn pix(&mut self) -> *mut v4l2_pix_format {
@@ -1745,6 +2146,9 @@ impl union16 {
     pub fn sliced(&mut self) -> *mut v4l2_sliced_vbi_format {
         unsafe { ::std::mem::transmute(self) }
     }
+    pub fn meta(&mut self) -> *mut v4l2_meta_format {
+        unsafe { ::std::mem::transmute(self) }
+    }
     pub fn raw_data(&mut self) -> *mut [u8, ..200u] {
         unsafe { ::std::mem::transmute(self) }
     }
@@ -1752,10 +2156,12 @@ impl union16 {
 impl Default for union16 {
     fn default() -> union16 {
         union16 {
-            data: [Default::default(), ..25u],
+            data: [Default::default(), ..50u],
+            _align: [],
         }
     }
 }
+#[deriving(Default)]
 pub struct v4l2_streamparm {
     pub _type: u32,
     pub parm: union17,
@@ -1774,6 +2180,13 @@ impl union17 {
         unsafe { ::std::mem::transmute(self) }
     }
 }
+impl Default for union17 {
+    fn default() -> union17 {
+        union17 {
+            data: [Default::default(), ..50u],
+        }
+    }
+}
 pub struct v4l2_event_vsync {
     pub field: u8,
 }
@@ -1798,6 +2211,9 @@ impl v4l2_event_ctrl {
 pub struct v4l2_event_frame_sync {
     pub frame_sequence: u32,
 }
+pub struct v4l2_event_src_change {
+    pub changes: u32,
+}
 pub struct v4l2_event {
     pub _type: u32,
     pub u: union19,
@@ -1807,6 +2223,22 @@ pub struct v4l2_event {
     pub id: u32,
     pub reserved: [u32, ..8u],
 }
+impl Default for v4l2_event {
+    fn default() -> v4l2_event {
+        v4l2_event {
+            _type: Default::default(),
+            u: Default::default(),
+            pending: Default::default(),
+            sequence: Default::default(),
+            timestamp: timespec {
+                tv_sec: Default::default(),
+                tv_nsec: Default::default(),
+            },
+            id: Default::default(),
+            reserved: [Default::default(), ..8u],
+        }
+    }
+}
 pub struct union19 {
     pub data: [u64, ..8u],
 }
@@ -1820,16 +2252,36 @@ impl union19 {
     pub fn frame_sync(&mut self) -> *mut v4l2_event_frame_sync {
         unsafe { ::std::mem::transmute(self) }
     }
+    pub fn src_change(&mut self) -> *mut v4l2_event_src_change {
+        unsafe { ::std::mem::transmute(self) }
+    }
     pub fn data(&mut self) -> *mut [u8, ..64u] {
         unsafe { ::std::mem::transmute(self) }
     }
 }
+impl Default for union19 {
+    fn default() -> union19 {
+        union19 {
+            data: [Default::default(), ..8u],
+        }
+    }
+}
 pub struct v4l2_event_subscription {
     pub _type: u32,
     pub id: u32,
     pub flags: u32,
     pub reserved: [u32, ..5u],
 }
+impl Default for v4l2_event_subscription {
+    fn default() -> v4l2_event_subscription {
+        v4l2_event_subscription {
+            _type: Default::default(),
+            id: Default::default(),
+            flags: Default::default(),
+            reserved: [Default::default(), ..5u],
+        }
+    }
+}
 pub struct v4l2_dbg_match {
     pub _type: u32,
     _union: [u32, ..8u],
@@ -1842,23 +2294,56 @@ impl v4l2_dbg_match {
         unsafe { ::std::mem::transmute(&mut self._union) }
     }
 }
+#[packed]
 pub struct v4l2_dbg_register {
     pub _match: v4l2_dbg_match,
     pub size: u32,
     pub reg: u64,
     pub val: u64,
 }
-pub struct v4l2_dbg_chip_ident {
+#[packed]
+pub struct v4l2_dbg_chip_info {
     pub _match: v4l2_dbg_match,
-    pub ident: u32,
-    pub revision: u32,
+    pub name: [u8, ..32u],
+    pub flags: u32,
+    pub reserved: [u32, ..32u],
 }
 pub struct v4l2_create_buffers {
     pub index: u32,
     pub count: u32,
     pub memory: u32,
     pub format: v4l2_format,
-    pub reserved: [u32, ..8u],
+    pub capabilities: u32,
+    pub flags: u32,
+    pub max_num_buffers: u32,
+    pub reserved: [u32, ..5u],
+}
+impl Default for v4l2_create_buffers {
+    fn default() -> v4l2_create_buffers {
+        v4l2_create_buffers {
+            index: Default::default(),
+            count: Default::default(),
+            memory: Default::default(),
+            format: Default::default(),
+            capabilities: Default::default(),
+            flags: Default::default(),
+            max_num_buffers: Default::default(),
+            reserved: [Default::default(), ..5u],
+        }
+    }
+}
+pub struct v4l2_remove_buffers {
+    pub index: u32,
+    pub count: u32,
+    pub _type: u32,
+    pub reserved: [u32, ..13u],
+}
+pub struct v4l2_edid {
+    pub pad: u32,
+    pub start_block: u32,
+    pub blocks: u32,
+    pub reserved: [u32, ..5u],
+    pub edid: *mut u8,
 }
 #[link(name = "xioctl")]
 extern {
@@ -1867,7 +2352,7 @@ extern {
 
 pub fn v4l2_ioctl(fd: c_int, req: u32, arg: *mut c_void) -> Result<c_int, c_int> {
     let result = unsafe {
-        xioctl(fd, req as u64, arg)
+        xioctl(fd, req as c_ulong, arg)
     };
     if result == -1 {
         Err(errno() as c_int)
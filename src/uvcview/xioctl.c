@@ -1,7 +1,7 @@
 #include <errno.h>
 #include <sys/ioctl.h>
 
-int xioctl(int fd, int request, void *arg)
+int xioctl(int fd, unsigned long request, void *arg)
 {
     int r;
     do {
@@ -0,0 +1,192 @@
+// UVC extension units: the vendor controls of a camera, such as LED modes,
+// HDR switches or firmware versions, which uvcvideo maps to no V4L2
+// control but passes through with UVCIOC_CTRL_QUERY.
+//
+// An extension unit control is addressed by the ID of its unit in the
+// camera's descriptors and its selector within the unit. Its value is a
+// byte string as long as GET_LEN says, with a meaning only the vendor
+// knows. Unit IDs differ between camera models, so a definitions file
+// names the controls of the models at hand, one per line:
+//
+//     # name     unit  selector
+//     led-mode   4     1
+//     firmware   0x04  0x03
+
+use libc::c_int;
+use std::fmt;
+use std::io::{File,IoResult,IoError};
+use std::io;
+use std::num::from_str_radix;
+use std::str;
+
+use device::Device;
+use uvc;
+
+// A control named in a definitions file.
+#[deriving(Clone, Eq, Show)]
+pub struct Definition {
+    pub name: ~str,
+    pub unit: u8,
+    pub selector: u8,
+}
+
+// Decimal, or hex with a 0x prefix.
+fn parse_u8(s: &str) -> Option<u8> {
+    if s.starts_with("0x") {
+        from_str_radix::<u8>(s.slice_from(2), 16)
+    } else {
+        from_str::<u8>(s)
+    }
+}
+
+// Parses a definitions file. The error tells the first line that is
+// neither a definition, a comment nor blank.
+pub fn parse_definitions(text: &str) -> Result<Vec<Definition>, ~str> {
+    let mut definitions = vec!();
+    for (i, line) in text.lines().enumerate() {
+        let line = match line.find('#') {
+            Some(end) => line.slice_to(end),
+            None => line
+        };
+        let words: Vec<&str> = line.words().collect();
+        let definition = match words.as_slice() {
+            [] => continue,
+            // A colon would make the name read as <unit>:<selector>.
+            [name, unit, selector] if !name.contains_char(':') => {
+                match (parse_u8(unit), parse_u8(selector)) {
+                    (Some(unit), Some(selector)) => {
+                        Some(Definition { name: name.to_owned(), unit: unit, selector: selector })
+                    }
+                    _ => None
+                }
+            }
+            _ => None
+        };
+        match definition {
+            Some(definition) => definitions.push(definition),
+            None => return Err(format!("line {}: expected <name> <unit> <selector>", i + 1))
+        }
+    }
+    Ok(definitions)
+}
+
+pub fn load_definitions(path: &Path) -> IoResult<Vec<Definition>> {
+    let text = try!(File::open(path).read_to_str());
+    parse_definitions(text.as_slice()).map_err(|e| IoError {
+        kind: io::InvalidInput,
+        desc: "load_definitions(): invalid definitions file",
+        detail: Some(format!("{}: {}", path.display(), e))
+    })
+}
+
+// The unit and selector of the control `s` names: a defined name, or
+// <unit>:<selector>.
+pub fn resolve(definitions: &[Definition], s: &str) -> Option<(u8, u8)> {
+    match definitions.iter().find(|definition| definition.name.as_slice() == s) {
+        Some(definition) => return Some((definition.unit, definition.selector)),
+        None => {}
+    }
+    let parts: Vec<&str> = s.split(':').collect();
+    match parts.as_slice() {
+        [unit, selector] => {
+            match (parse_u8(unit), parse_u8(selector)) {
+                (Some(unit), Some(selector)) => Some((unit, selector)),
+                _ => None
+            }
+        }
+        _ => None
+    }
+}
+
+// How error messages name a control.
+pub fn address_to_str(unit: u8, selector: u8) -> ~str {
+    format!("extension unit {} selector {}", unit, selector)
+}
+
+// Parses a value written as hex bytes, optionally separated by colons:
+// "01:ff:00" or "01ff00".
+pub fn parse_value(s: &str) -> Option<Vec<u8>> {
+    let mut digits = vec!();
+    for c in s.chars() {
+        if c == ':' {
+            continue;
+        }
+        match c.to_digit(16) {
+            Some(digit) => digits.push(digit as u8),
+            None => return None
+        }
+    }
+    if digits.is_empty() || digits.len() % 2 != 0 {
+        return None;
+    }
+    Some(digits.as_slice().chunks(2).map(|pair| pair[0] << 4 | pair[1]).collect())
+}
+
+// The value as parse_value() takes it, followed by its text if it reads
+// as such, as version strings do.
+pub fn value_to_str(value: &[u8]) -> ~str {
+    let hex: Vec<~str> = value.iter().map(|byte| format!("{:02x}", *byte)).collect();
+    let text = value.slice_to(value.iter().position(|&b| b == 0).unwrap_or(value.len()));
+    match str::from_utf8(text) {
+        Some(text) if text.len() > 1 && text.chars().all(|c| c >= ' ' && c <= '~') => {
+            format!("{} \"{}\"", hex.as_slice().connect(":"), text)
+        }
+        _ => hex.as_slice().connect(":")
+    }
+}
+
+// The UVC_CONTROL_CAP_* flags GET_INFO reports.
+#[deriving(Clone, Eq)]
+pub struct Info(pub u8);
+
+impl Info {
+    pub fn can_get(&self) -> bool {
+        let Info(flags) = *self;
+        (flags & uvc::UVC_CONTROL_CAP_GET) != 0
+    }
+
+    pub fn can_set(&self) -> bool {
+        let Info(flags) = *self;
+        (flags & uvc::UVC_CONTROL_CAP_SET) != 0
+    }
+}
+
+impl fmt::Show for Info {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Info(flags) = *self;
+        let names = [(uvc::UVC_CONTROL_CAP_GET, "get"), (uvc::UVC_CONTROL_CAP_SET, "set"),
+                     (uvc::UVC_CONTROL_CAP_DISABLED, "disabled"),
+                     (uvc::UVC_CONTROL_CAP_AUTOUPDATE, "autoupdate"),
+                     (uvc::UVC_CONTROL_CAP_ASYNCHRONOUS, "asynchronous")];
+        let set: Vec<&str> = names.iter().filter(|&&(flag, _)| (flags & flag) != 0).map(|&(_, name)| {
+            name
+        }).collect();
+        write!(f, "{}", set.as_slice().connect(","))
+    }
+}
+
+// GET_LEN: how many bytes the value of the control takes.
+pub fn get_len(device: &mut Device, unit: u8, selector: u8) -> Result<u16, c_int> {
+    let mut data = [0u8, ..2];
+    try!(device.xu_query(unit, selector, uvc::UVC_GET_LEN, data.as_mut_slice()));
+    Ok(data[0] as u16 | data[1] as u16 << 8)
+}
+
+pub fn get_info(device: &mut Device, unit: u8, selector: u8) -> Result<Info, c_int> {
+    let mut data = [0u8, ..1];
+    try!(device.xu_query(unit, selector, uvc::UVC_GET_INFO, data.as_mut_slice()));
+    Ok(Info(data[0]))
+}
+
+// GET_CUR of a control whose value takes `len` bytes.
+pub fn get_cur(device: &mut Device, unit: u8, selector: u8, len: u16) -> Result<Vec<u8>, c_int> {
+    let mut data = Vec::from_elem(len as uint, 0u8);
+    try!(device.xu_query(unit, selector, uvc::UVC_GET_CUR, data.as_mut_slice()));
+    Ok(data)
+}
+
+pub fn set_cur(device: &mut Device, unit: u8, selector: u8, value: &[u8]) -> Result<(), c_int> {
+    // The driver only reads the value, but takes it through a *mut.
+    let mut data = Vec::from_slice(value);
+    device.xu_query(unit, selector, uvc::UVC_SET_CUR, data.as_mut_slice())
+}
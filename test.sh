@@ -0,0 +1,17 @@
+#!/bin/sh
+
+set -e
+
+mkdir -p bin lib
+
+gcc -shared -fpic -o lib/libxioctl.so src/uvcview/xioctl.c
+
+old_ifs="$IFS"
+IFS=":"
+for pkg_path in $RUST_PATH; do
+    LIBS="$LIBS -L $pkg_path/lib"
+done
+IFS="$old_ifs"
+
+rustc --test -L lib $LIBS src/uvcview/test.rs -o bin/uvcview-test
+LD_LIBRARY_PATH=lib bin/uvcview-test